@@ -0,0 +1,115 @@
+// LNP/BP Core Library implementing LNPBP specifications & standards
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Benchmarks of the deterministic bitcoin commitments into each of the
+//! supported `scriptPubkey` compositions
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use lnpbp::bitcoin::blockdata::opcodes::all::OP_CHECKSIG;
+use lnpbp::bitcoin::blockdata::script::Builder;
+use lnpbp::bitcoin::hashes::{sha256, Hash};
+use lnpbp::bitcoin::secp256k1;
+use lnpbp::bp::dbc::{
+    ScriptInfo, ScriptPubkeyCommitment, ScriptPubkeyComposition, ScriptPubkeyContainer,
+};
+use lnpbp::bp::LockScript;
+use lnpbp::commit_verify::EmbedCommitVerify;
+use lnpbp::SECP256K1;
+
+fn containers() -> Vec<(&'static str, ScriptPubkeyContainer)> {
+    use ScriptPubkeyComposition::*;
+
+    let tag = sha256::Hash::hash(b"BENCH_TAG");
+    // Any key may be used as a taproot internal key: taproot commitment
+    // negates keys with odd Y coordinate (like the one for secret key 0x01..01)
+    let pubkey = secp256k1::PublicKey::from_secret_key(
+        &SECP256K1,
+        &secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap(),
+    );
+    let lockscript = LockScript::from(
+        Builder::new()
+            .push_slice(&pubkey.serialize())
+            .push_opcode(OP_CHECKSIG)
+            .into_script(),
+    );
+
+    vec![
+        ("p2pk", PublicKey, ScriptInfo::None),
+        ("p2pkh", PubkeyHash, ScriptInfo::None),
+        ("p2pk-uncompressed", UncompressedPublicKey, ScriptInfo::None),
+        (
+            "p2pkh-uncompressed",
+            UncompressedPubkeyHash,
+            ScriptInfo::None,
+        ),
+        ("p2wpkh", WPubkeyHash, ScriptInfo::None),
+        ("p2sh-p2wpkh", SHWPubkeyHash, ScriptInfo::None),
+        ("op_return", OpReturn, ScriptInfo::None),
+        (
+            "p2s",
+            PlainScript,
+            ScriptInfo::LockScript(lockscript.clone()),
+        ),
+        (
+            "p2sh",
+            ScriptHash,
+            ScriptInfo::LockScript(lockscript.clone()),
+        ),
+        (
+            "p2wsh",
+            WScriptHash,
+            ScriptInfo::LockScript(lockscript.clone()),
+        ),
+        (
+            "p2sh-p2wsh",
+            SHWScriptHash,
+            ScriptInfo::LockScript(lockscript),
+        ),
+        ("p2tr", TapRoot, ScriptInfo::Taproot(None)),
+    ]
+    .into_iter()
+    .map(|(name, composition, script_info)| {
+        (
+            name,
+            ScriptPubkeyContainer::construct(&tag, pubkey, script_info, composition),
+        )
+    })
+    .collect()
+}
+
+fn bench_commit(c: &mut Criterion) {
+    let msg = "benchmarked message";
+    let mut group = c.benchmark_group("scriptpubkey_commit");
+    for (name, container) in containers() {
+        group.bench_function(name, |b| {
+            b.iter(|| ScriptPubkeyCommitment::embed_commit(black_box(&container), &msg))
+        });
+    }
+    group.finish();
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let msg = "benchmarked message";
+    let mut group = c.benchmark_group("scriptpubkey_verify");
+    for (name, container) in containers() {
+        let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+        group.bench_function(name, |b| {
+            b.iter(|| commitment.verify(black_box(&container), &msg))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_commit, bench_verify);
+criterion_main!(benches);
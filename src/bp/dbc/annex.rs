@@ -0,0 +1,188 @@
+// LNP/BP Rust Library
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Commitments placed into the annex of the taproot input witness (BIP-341),
+//! which leave both the output key and the script tree untouched.
+//!
+//! NB: annex is reserved for the future soft forks, so the transactions with
+//! an annex in any of the input witnesses are non-standard under the current
+//! Bitcoin Core relay policy: they are not relayed by the nodes and must be
+//! delivered to a miner directly.
+
+use amplify::Wrapper;
+use bitcoin::hashes::{sha256, Hash, HashEngine};
+
+use super::Error;
+use crate::commit_verify::EmbedCommitVerify;
+
+/// First byte of the taproot witness annex, according to BIP-341
+pub const ANNEX_PREFIX: u8 = 0x50;
+
+/// Version of the commitment annex format; the only one known to the current
+/// implementation
+pub const ANNEX_COMMITMENT_VERSION: u8 = 0x00;
+
+/// Length of the commitment annex: prefix byte, version byte and 32-byte
+/// commitment digest
+const ANNEX_COMMITMENT_LEN: usize = 34;
+
+/// Constructs annex bytes carrying the given commitment digest, in the format
+/// `0x50 || ANNEX_COMMITMENT_VERSION || digest`
+pub fn annex_with_digest(digest: &sha256::Hash) -> Vec<u8> {
+    let mut annex = Vec::with_capacity(ANNEX_COMMITMENT_LEN);
+    annex.push(ANNEX_PREFIX);
+    annex.push(ANNEX_COMMITMENT_VERSION);
+    annex.extend(&digest[..]);
+    annex
+}
+
+/// Returns annex of the taproot input witness, if present. According to
+/// BIP-341 annex is the last element of the witness stack, which has at
+/// least two elements, starting with [ANNEX_PREFIX] byte.
+pub fn witness_annex(witness: &[Vec<u8>]) -> Option<&[u8]> {
+    match witness {
+        [_, .., last] if last.first() == Some(&ANNEX_PREFIX) => Some(last),
+        _ => None,
+    }
+}
+
+/// Container for the annex commitment; unlike other containers it doesn't
+/// hold any keys or scripts, since the annex is not committed to by the
+/// spent output
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct AnnexContainer {
+    /// Single SHA256 hash of the protocol-specific tag
+    pub tag: sha256::Hash,
+}
+
+wrapper!(
+    AnnexCommitment,
+    Vec<u8>,
+    doc = "Taproot witness annex containing commitment digest `SHA256(tag || msg)`",
+    derive = [PartialEq, Eq, Hash]
+);
+
+impl AnnexCommitment {
+    /// Parses annex taken from the taproot witness, failing with
+    /// [Error::InvalidAnnex] if it does not follow the commitment annex
+    /// format
+    pub fn from_annex(annex: &[u8]) -> Result<Self, Error> {
+        match annex {
+            [ANNEX_PREFIX, ANNEX_COMMITMENT_VERSION, digest @ ..] if digest.len() == 32 => {
+                Ok(Self::from_inner(annex.to_vec()))
+            }
+            _ => Err(Error::InvalidAnnex),
+        }
+    }
+
+    /// Returns commitment digest carried by the annex
+    pub fn digest(&self) -> sha256::Hash {
+        sha256::Hash::from_slice(&self.as_inner()[2..])
+            .expect("annex commitment length is checked on construction")
+    }
+
+    /// Appends annex to the taproot witness stack, which must already
+    /// contain all other witness elements. Transaction with the annex is
+    /// non-standard and will not be relayed by the nodes following Bitcoin
+    /// Core relay policy (see the module documentation).
+    pub fn push_to_witness(&self, witness: &mut Vec<Vec<u8>>) {
+        witness.push(self.as_inner().clone());
+    }
+}
+
+impl<MSG> EmbedCommitVerify<MSG> for AnnexCommitment
+where
+    MSG: AsRef<[u8]>,
+{
+    type Container = AnnexContainer;
+    type Error = Error;
+
+    fn embed_commit(container: &Self::Container, msg: &MSG) -> Result<Self, Self::Error> {
+        let mut engine = sha256::Hash::engine();
+        engine.input(&container.tag[..]);
+        engine.input(msg.as_ref());
+        let digest = sha256::Hash::from_engine(engine);
+        Ok(Self::from_inner(annex_with_digest(&digest)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_annex_roundtrip() {
+        let container = AnnexContainer {
+            tag: sha256::Hash::hash(b"TEST_TAG"),
+        };
+        let msg = "test message";
+        let commitment = AnnexCommitment::embed_commit(&container, &msg).unwrap();
+        assert_eq!(commitment.len(), 34);
+        assert_eq!(commitment[0], ANNEX_PREFIX);
+
+        let signature = vec![0xAAu8; 64];
+        let mut witness = vec![signature.clone()];
+        assert_eq!(witness_annex(&witness), None);
+        commitment.push_to_witness(&mut witness);
+        assert_eq!(witness[0], signature);
+
+        let annex = witness_annex(&witness).unwrap();
+        let recovered = AnnexCommitment::from_annex(annex).unwrap();
+        assert_eq!(recovered, commitment);
+
+        let mut engine = sha256::Hash::engine();
+        engine.input(&container.tag[..]);
+        engine.input(msg.as_bytes());
+        assert_eq!(recovered.digest(), sha256::Hash::from_engine(engine));
+
+        assert!(recovered.verify(&container, &msg).unwrap());
+        assert!(!recovered.verify(&container, &"other message").unwrap());
+        let other = AnnexContainer {
+            tag: sha256::Hash::hash(b"OTHER_TAG"),
+        };
+        assert!(!recovered.verify(&other, &msg).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_annex() {
+        let digest = sha256::Hash::hash(b"digest");
+        let annex = annex_with_digest(&digest);
+        assert_eq!(
+            AnnexCommitment::from_annex(&annex).unwrap().digest(),
+            digest
+        );
+
+        let mut wrong_prefix = annex.clone();
+        wrong_prefix[0] = 0x51;
+        let mut wrong_version = annex.clone();
+        wrong_version[1] = 0x01;
+        let mut too_long = annex.clone();
+        too_long.push(0);
+        for annex in &[
+            wrong_prefix,
+            wrong_version,
+            too_long,
+            annex[..33].to_vec(),
+            vec![],
+        ] {
+            assert_eq!(AnnexCommitment::from_annex(annex), Err(Error::InvalidAnnex));
+        }
+
+        // Single-element witness can't contain annex, even if the element
+        // starts with the annex prefix
+        assert_eq!(witness_annex(&[annex.clone()]), None);
+        assert_eq!(witness_annex(&[]), None);
+        assert_eq!(witness_annex(&[vec![], annex.clone()]), Some(&annex[..]));
+    }
+}
@@ -11,8 +11,9 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
-use crate::bp::{scripts::Error as ScriptPubkeyError, PubkeyParseError};
+use crate::bp::{scripts::Error as ScriptPubkeyError, PubkeyParseError, StrategyError};
 use bitcoin::secp256k1;
+use miniscript::MiniscriptKey;
 
 #[derive(Clone, PartialEq, Debug, Display, Error, From)]
 #[display_from(Debug)]
@@ -25,16 +26,80 @@ pub enum Error {
     #[derive_from]
     InvalidScriptPubkey(ScriptPubkeyError),
 
-    LockscriptParseError,
+    /// `scriptPubkey` for the commitment can't be generated from the
+    /// container data
+    #[derive_from]
+    ScriptGeneration(StrategyError),
+
+    /// Lock script can't be parsed either as a bitcoin script or as a
+    /// miniscript; the inner string holds the description of the original
+    /// error (miniscript errors do not support comparison and cloning)
+    LockscriptParseError(String),
+
+    /// Lock script contains public key hash in place where only plain public
+    /// keys are allowed
+    LockscriptContainsPubkeyHash(<bitcoin::PublicKey as MiniscriptKey>::Hash),
 
     LockscriptContainsNoKeys,
 
     LockscriptContainsUnknownHashes,
 
+    /// The key which must contain the commitment is repeated within a single
+    /// multisig key list, so tweaking it would be ambiguous
+    LockscriptContainsRepeatedKey,
+
     /// Attempt to commit into LockScript has failed: the key that must contain
     /// the commitment/tweak was not found either in plain nor hash form in
     /// any of the script branches
     LockscriptKeyNotFound,
+
+    /// The script which must hold the commitment is empty, so hashed
+    /// outputs (like P2WSH) would be spendable by anyone
+    EmptyScript,
+
+    /// The script which must hold the commitment is provably unspendable or
+    /// exceeds maximum script size, so the resulting output would never be
+    /// spendable
+    NonStandardScript,
+
+    /// Witness script exceeds the standardness limit of
+    /// [super::MAX_STANDARD_WITNESS_SCRIPT_SIZE] bytes, so
+    /// the resulting output would not be relayed by the network nodes
+    ScriptTooLarge,
+
+    /// `OP_RETURN` output with the commitment exceeds the standardness limit
+    /// of [super::MAX_STANDARD_OP_RETURN_SIZE] bytes, so it would not be
+    /// relayed by the network nodes
+    OpReturnTooLarge,
+
+    /// `OP_RETURN` output with the commitment has non-zero value, which
+    /// would burn the funds and make the transaction non-standard
+    NonZeroOpReturnValue(u64),
+
+    /// `scriptPubkey` does not match any of the output templates which may
+    /// hold the commitment, or its template can't be detected without
+    /// additional data (like for P2SH, which may wrap different scripts)
+    UnrecognizedScriptPubkey,
+
+    /// Output descriptor contains single public key which is different from
+    /// the key which must hold the commitment
+    DescriptorKeyMismatch,
+
+    /// Requested commitment scheme can't be applied to the container: either
+    /// the container script data or the output type do not match the scheme
+    CommitmentSchemeMismatch,
+
+    /// Transaction contains commitments of the same protocol in several
+    /// outputs (listed by their numbers), so it is not known which of them is
+    /// authoritative
+    ConflictingCommitments(Vec<u32>),
+
+    /// Taproot witness annex does not follow the commitment annex format
+    InvalidAnnex,
+
+    /// Provided public key can't be used as taproot internal key, since it
+    /// does not correspond to the BIP-340 x-only key (has odd Y coordinate)
+    InvalidTaprootInternalKey,
 }
 
 impl From<secp256k1::Error> for Error {
@@ -44,7 +109,11 @@ impl From<secp256k1::Error> for Error {
 }
 
 impl From<PubkeyParseError> for Error {
-    fn from(_: PubkeyParseError) -> Self {
-        Self::LockscriptParseError
+    fn from(err: PubkeyParseError) -> Self {
+        match err {
+            PubkeyParseError::PubkeyHash(hash) => Self::LockscriptContainsPubkeyHash(hash),
+            PubkeyParseError::Miniscript(err) => Self::LockscriptParseError(err.to_string()),
+            PubkeyParseError::Script(err) => Self::LockscriptParseError(err.to_string()),
+        }
     }
 }
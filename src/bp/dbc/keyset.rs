@@ -164,7 +164,7 @@ mod test {
         let msg = "test message";
         gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
             let lnpbp1_commitment =
-                LNPBP1Commitment::embed_commit(&LNPBP1Container { pubkey, tag }, &msg).unwrap();
+                LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey, tag), &msg).unwrap();
             let lnpbp2_commitment = LNPBP2Commitment::embed_commit(
                 &KeysetContainer {
                     pubkey,
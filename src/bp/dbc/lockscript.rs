@@ -23,6 +23,8 @@
 //! private key modifications (tweaks) inside all the existing types of Bitcoin
 //! transaction output and arbitrary complex Bitcoin scripts.
 
+use bitcoin::blockdata::opcodes::all::{OP_CHECKMULTISIG, OP_CHECKMULTISIGVERIFY};
+use bitcoin::blockdata::script::Instruction;
 use bitcoin::secp256k1;
 use bitcoin::{hashes::sha256, PubkeyHash};
 use core::cell::RefCell;
@@ -31,7 +33,7 @@ use std::collections::HashSet;
 use super::{Container, Error, LNPBP2Commitment, Proof, ScriptInfo};
 use crate::bp::dbc::KeysetContainer;
 use crate::bp::scripts::*;
-use crate::commit_verify::EmbedCommitVerify;
+use crate::commit_verify::{constant_time_eq, EmbedCommitVerify};
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
 #[display_from(Debug)]
@@ -42,6 +44,62 @@ pub struct LockscriptContainer {
     pub tag: sha256::Hash,
 }
 
+impl LockscriptContainer {
+    /// Constructs container selecting the public key which will carry the
+    /// commitment by its position among the keys pushed into the `script`
+    /// (see [LockScript::extract_pushed_pubkeys]). If no index is given, the
+    /// first key found in the script is used.
+    pub fn with_key_index(
+        script: LockScript,
+        index: Option<usize>,
+        tag: sha256::Hash,
+    ) -> Result<Self, Error> {
+        let keys = script.extract_pushed_pubkeys()?;
+        if keys.is_empty() {
+            Err(Error::LockscriptContainsNoKeys)?;
+        }
+        let pubkey = keys
+            .get(index.unwrap_or(0))
+            .ok_or(Error::LockscriptKeyNotFound)?
+            .key;
+        Ok(Self {
+            script,
+            pubkey,
+            tag,
+        })
+    }
+}
+
+/// Detects whether the `pubkey` is repeated within the key list of a single
+/// `OP_CHECKMULTISIG` or `OP_CHECKMULTISIGVERIFY` operation, in which case
+/// tweaking the key is ambiguous and may corrupt the multisig. Compressed and
+/// uncompressed serializations of the key are treated as the same key.
+/// Repetitions of the key in different script branches are not detected.
+fn is_repeated_in_multisig(script: &LockScript, pubkey: &secp256k1::PublicKey) -> bool {
+    let parse_key = |instr: &Instruction| match instr {
+        Instruction::PushBytes(data) => secp256k1::PublicKey::from_slice(data).ok(),
+        _ => None,
+    };
+    let instructions = script.iter(false).collect::<Vec<_>>();
+    instructions
+        .iter()
+        .enumerate()
+        .any(|(idx, instr)| match instr {
+            Instruction::Op(op) if *op == OP_CHECKMULTISIG || *op == OP_CHECKMULTISIGVERIFY => {
+                // Skipping the number of keys which precedes the operation
+                instructions[..idx.saturating_sub(1)]
+                    .iter()
+                    .rev()
+                    .map(parse_key)
+                    .take_while(Option::is_some)
+                    .filter(|key| key.as_ref() == Some(pubkey))
+                    .count()
+                    > 1
+            }
+            _ => false,
+        })
+}
+
 impl Container for LockscriptContainer {
     /// Out supplement is a protocol-specific tag in its hashed form
     type Supplement = sha256::Hash;
@@ -70,6 +128,7 @@ impl Container for LockscriptContainer {
             Proof {
                 script_info: ScriptInfo::LockScript(self.script),
                 pubkey: self.pubkey,
+                nonce: None,
             },
             self.tag,
         )
@@ -80,6 +139,7 @@ impl Container for LockscriptContainer {
         Proof {
             script_info: ScriptInfo::LockScript(self.script.clone()),
             pubkey: self.pubkey.clone(),
+            nonce: None,
         }
     }
 
@@ -88,6 +148,7 @@ impl Container for LockscriptContainer {
         Proof {
             script_info: ScriptInfo::LockScript(self.script),
             pubkey: self.pubkey,
+            nonce: None,
         }
     }
 }
@@ -129,6 +190,11 @@ where
     /// 4. If no public keys were found fail the procedure; return the collected
     ///    keys otherwise.
     ///
+    /// Additionally, the procedure fails with
+    /// [Error::LockscriptContainsRepeatedKey] if the key carrying the
+    /// commitment is repeated within the key list of a single multisig
+    /// operation, since the resulting script would be ambiguous.
+    ///
     /// **NB: SUBJECT TO CHANGE UPON RELEASE**
     /// By "miniscript" we mean usage of `rust-miniscript` library at commit
     /// `a5ba1219feb8b5a289c8f12176d632635eb8a959`
@@ -143,6 +209,10 @@ where
         }
         .pubkey_hash();
 
+        if is_repeated_in_multisig(&container.script, &container.pubkey) {
+            Err(Error::LockscriptContainsRepeatedKey)?;
+        }
+
         let (keys, hashes) = container.script.extract_pubkey_hash_set()?;
         if keys.is_empty() && hashes.is_empty() {
             Err(Error::LockscriptContainsNoKeys)?;
@@ -210,6 +280,16 @@ where
 
         Ok(lockscript.into())
     }
+
+    /// Verifies the commitment comparing the scripts in constant time (see
+    /// [constant_time_eq]), since tweaked keys inside the script are derived
+    /// from the message which may be secret
+    fn verify(&self, container: &Self::Container, msg: &MSG) -> Result<bool, Self::Error> {
+        Ok(match Self::embed_commit(container, msg) {
+            Ok(commitment) => constant_time_eq(commitment.as_bytes(), self.as_bytes()),
+            Err(_) => false,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -450,6 +530,114 @@ mod test {
             });
     }
 
+    #[test]
+    fn test_checksigadd_multisig() {
+        use bitcoin::blockdata::opcodes::{self, all::OP_CHECKSIG, all::OP_NUMEQUAL};
+        use bitcoin::blockdata::script::Builder;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "Test message";
+        // Tapscript keys are x-only, so we use them in the even Y form
+        let keys: Vec<secp256k1::PublicKey> = pubkeys(3)
+            .into_iter()
+            .map(|pk| {
+                let mut even = pk.key.serialize();
+                even[0] = 0x02;
+                secp256k1::PublicKey::from_slice(&even).unwrap()
+            })
+            .collect();
+        let ls = LockScript::from(
+            keys.iter()
+                .enumerate()
+                .fold(Builder::new(), |builder, (no, key)| {
+                    builder
+                        .push_slice(&key.serialize()[1..])
+                        .push_opcode(if no == 0 {
+                            OP_CHECKSIG
+                        } else {
+                            opcodes::All::from(OP_CHECKSIGADD)
+                        })
+                })
+                .push_int(2)
+                .push_opcode(OP_NUMEQUAL)
+                .into_script(),
+        );
+
+        let container = LockscriptContainer {
+            script: ls.clone(),
+            pubkey: keys[1],
+            tag,
+        };
+        let commitment = LockscriptCommitment::embed_commit(&container, &msg).unwrap();
+        assert_ne!(*commitment, ls);
+        assert!(commitment.verify(&container, &msg).unwrap());
+        assert!(!commitment.verify(&container, &"Other message").unwrap());
+
+        // Only the selected key is tweaked and the script remains a multisig
+        let (threshold, tweaked) = commitment.extract_checksigadd_multisig().unwrap();
+        assert_eq!(threshold, 2);
+        assert_eq!(tweaked[0], keys[0]);
+        assert_ne!(tweaked[1], keys[1]);
+        assert_eq!(tweaked[2], keys[2]);
+
+        let (proof, supplement) = container.clone().deconstruct();
+        let reconstructed = LockscriptContainer::reconstruct(&proof, &supplement, &None).unwrap();
+        assert!(commitment.verify(&reconstructed, &msg).unwrap());
+    }
+
+    #[test]
+    fn test_multisig_repeated_key() {
+        use bitcoin::blockdata::script::Builder;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let (keys, _, _) = gen_test_data();
+        let mut uncompressed = keys[0];
+        uncompressed.compressed = false;
+
+        vec![keys[0], uncompressed]
+            .into_iter()
+            .for_each(|repeated| {
+                let ls = LockScript::from(
+                    Builder::new()
+                        .push_int(2)
+                        .push_key(&keys[0])
+                        .push_key(&repeated)
+                        .push_key(&keys[1])
+                        .push_int(3)
+                        .push_opcode(OP_CHECKMULTISIG)
+                        .into_script(),
+                );
+                assert!(is_repeated_in_multisig(&ls, &keys[0].key));
+                assert!(!is_repeated_in_multisig(&ls, &keys[1].key));
+                assert_eq!(
+                    LockscriptCommitment::embed_commit(
+                        &LockscriptContainer {
+                            script: ls,
+                            pubkey: keys[0].key,
+                            tag
+                        },
+                        &"Test message"
+                    )
+                    .err(),
+                    Some(Error::LockscriptContainsRepeatedKey)
+                );
+            });
+
+        // The same key in different branches is not a multisig repetition
+        let ls = LockScript::from(
+            ms_str!(
+                "or_d(multi(2,{},{}),and_v(vc:pk_k({}),older(10)))",
+                keys[0],
+                keys[1],
+                keys[0]
+            )
+            .encode(),
+        );
+        assert!(!is_repeated_in_multisig(&ls, &keys[0].key));
+        let ls = LockScript::from(ms_str!("multi(2,{},{})", keys[0], keys[1]).encode());
+        assert!(!is_repeated_in_multisig(&ls, &keys[0].key));
+    }
+
     #[test]
     fn test_complex_scripts_unique_key() {
         let tag = sha256::Hash::hash(b"TEST_TAG");
@@ -475,4 +663,86 @@ mod test {
         let commitment = LockscriptCommitment::embed_commit(&container.clone(), &msg).unwrap();
         assert!(commitment.verify(&container, &msg).unwrap())
     }
+
+    #[test]
+    fn test_key_selection() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let (keys, _, _) = gen_test_data();
+        let msg = "Test message";
+        let ls = LockScript::from(ms_str!("multi(2,{},{})", keys[0], keys[1]).encode());
+
+        let first = LockscriptContainer::with_key_index(ls.clone(), None, tag).unwrap();
+        assert_eq!(first.pubkey, keys[0].key);
+        assert_eq!(
+            LockscriptContainer::with_key_index(ls.clone(), Some(0), tag).unwrap(),
+            first
+        );
+        let second = LockscriptContainer::with_key_index(ls.clone(), Some(1), tag).unwrap();
+        assert_eq!(second.pubkey, keys[1].key);
+        assert_eq!(
+            LockscriptContainer::with_key_index(ls.clone(), Some(2), tag).err(),
+            Some(Error::LockscriptKeyNotFound)
+        );
+
+        let commitment1 = LockscriptCommitment::embed_commit(&first, &msg).unwrap();
+        let commitment2 = LockscriptCommitment::embed_commit(&second, &msg).unwrap();
+        assert_ne!(commitment1, commitment2);
+
+        // Only the selected key must be tweaked
+        let keys1 = commitment1.extract_pushed_pubkeys().unwrap();
+        let keys2 = commitment2.extract_pushed_pubkeys().unwrap();
+        assert_ne!(keys1[0], keys[0]);
+        assert_eq!(keys1[1], keys[1]);
+        assert_eq!(keys2[0], keys[0]);
+        assert_ne!(keys2[1], keys[1]);
+
+        assert!(commitment1.verify(&first, &msg).unwrap());
+        assert!(commitment2.verify(&second, &msg).unwrap());
+        assert!(!commitment1.verify(&second, &msg).unwrap());
+        assert!(!commitment2.verify(&first, &msg).unwrap());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let (keys, key_hashes, _) = gen_test_data();
+        let msg = "Test message";
+
+        // Truncated data push: not a valid bitcoin script
+        let ls = LockScript::from(bitcoin::Script::from(vec![0x05u8, 0x01]));
+        match LockscriptContainer::with_key_index(ls, None, tag) {
+            Err(Error::LockscriptParseError(_)) => {}
+            _ => panic!("Invalid script must fail with LockscriptParseError"),
+        }
+
+        // Valid bitcoin script which is not a miniscript
+        let ls = LockScript::from(
+            bitcoin::blockdata::script::Builder::new()
+                .push_key(&keys[0])
+                .push_opcode(bitcoin::blockdata::opcodes::all::OP_CHECKSIG)
+                .push_opcode(bitcoin::blockdata::opcodes::all::OP_CHECKSIG)
+                .into_script(),
+        );
+        let container = LockscriptContainer {
+            script: ls,
+            pubkey: keys[0].key,
+            tag,
+        };
+        match LockscriptCommitment::embed_commit(&container, &msg) {
+            Err(Error::LockscriptParseError(_)) => {}
+            _ => panic!("Non-miniscript script must fail with LockscriptParseError"),
+        }
+
+        // Public key hash where only keys are allowed
+        let ls = LockScript::from(ms_str!("c:pk_h({})", key_hashes[1]).encode());
+        let proof = Proof {
+            script_info: ScriptInfo::LockScript(ls),
+            pubkey: keys[0].key,
+            nonce: None,
+        };
+        match KeysetContainer::reconstruct(&proof, &tag, &None) {
+            Err(Error::LockscriptContainsPubkeyHash(_)) => {}
+            _ => panic!("Public key hash must fail with LockscriptContainsPubkeyHash"),
+        }
+    }
 }
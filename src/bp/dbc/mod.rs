@@ -11,10 +11,12 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+mod annex;
 pub mod digests;
 mod error;
 mod keyset;
 mod lockscript;
+mod psbt;
 mod pubkey;
 mod scriptpubkey;
 mod taproot;
@@ -22,12 +24,32 @@ mod tx;
 mod txout;
 mod types;
 
+pub use annex::{
+    annex_with_digest, witness_annex, AnnexCommitment, AnnexContainer, ANNEX_COMMITMENT_VERSION,
+    ANNEX_PREFIX,
+};
 pub use error::Error;
 pub use keyset::{KeysetContainer, LNPBP2Commitment};
 pub use lockscript::{LockscriptCommitment, LockscriptContainer};
-pub use pubkey::{LNPBP1Commitment, LNPBP1Container};
-pub use scriptpubkey::{ScriptPubkeyCommitment, ScriptPubkeyComposition, ScriptPubkeyContainer};
-pub use taproot::{TaprootCommitment, TaprootContainer};
-pub use tx::{compute_lnpbp3_vout, TxCommitment, TxContainer, TxSupplement};
+pub use psbt::{
+    dbc_reveal_key, dbc_reveal_pair, extract_dbc_reveal, insert_dbc_reveal, parse_dbc_reveal,
+    PSBT_OUT_DBC_REVEAL_KEY, PSBT_PROPRIETARY_TYPE,
+};
+pub use pubkey::{verify_with_pubkey, LNPBP1Commitment, LNPBP1Container};
+pub use scriptpubkey::{
+    check_single_commitment, commit_to_descriptor, commit_to_scheme, commit_to_txout,
+    enumerate_commitment_sites, supported_commitments, CommitmentProof, CommitmentScheme,
+    NameParseError, NegotiatedCommitment, ScriptPubkeyCategory, ScriptPubkeyCommitment,
+    ScriptPubkeyComposition, ScriptPubkeyContainer, MAX_SCRIPT_SIZE, MAX_STANDARD_OP_RETURN_SIZE,
+    MAX_STANDARD_WITNESS_SCRIPT_SIZE,
+};
+pub use taproot::{
+    TapleafCommitment, TapleafContainer, TaprootCommitment, TaprootContainer,
+    TAPSCRIPT_LEAF_VERSION,
+};
+pub use tx::{
+    compute_lnpbp3_vout, deterministic_output_index, verify_lnpbp3_vout, verify_output_index,
+    TxCommitment, TxContainer, TxSupplement,
+};
 pub use txout::{TxoutCommitment, TxoutContainer};
 pub use types::{Container, Proof, ScriptInfo};
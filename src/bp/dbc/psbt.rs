@@ -0,0 +1,157 @@
+// LNP/BP Rust Library
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Helpers for keeping revealed commitment data within PSBT outputs (as
+//! BIP-174 proprietary keys), such that the finalizer can verify the
+//! commitment before signing
+
+use bitcoin::hashes::sha256;
+use bitcoin::util::psbt::{self, raw};
+
+use super::ScriptPubkeyContainer;
+use crate::strict_encoding::{self, strict_decode, strict_encode};
+
+/// PSBT key type for the proprietary use, according to BIP-174
+pub const PSBT_PROPRIETARY_TYPE: u8 = 0xFC;
+
+/// Proprietary key data (prefix `dbc` with subtype `0x01`) for the output
+/// commitment reveal data: strict-encoded [ScriptPubkeyContainer] followed
+/// by the committed message digest
+pub const PSBT_OUT_DBC_REVEAL_KEY: &[u8] = b"\x03dbc\x01";
+
+/// Returns PSBT proprietary key for the commitment reveal data
+pub fn dbc_reveal_key() -> raw::Key {
+    raw::Key {
+        type_value: PSBT_PROPRIETARY_TYPE,
+        key: PSBT_OUT_DBC_REVEAL_KEY.to_vec(),
+    }
+}
+
+/// Serializes container and the committed message digest into PSBT
+/// proprietary key-value pair
+pub fn dbc_reveal_pair(
+    container: &ScriptPubkeyContainer,
+    digest: &sha256::Hash,
+) -> Result<(raw::Key, Vec<u8>), strict_encoding::Error> {
+    let mut value = strict_encode(container)?;
+    value.extend(strict_encode(digest)?);
+    Ok((dbc_reveal_key(), value))
+}
+
+/// Parses value of the proprietary key-value pair produced by
+/// [dbc_reveal_pair]
+pub fn parse_dbc_reveal(
+    value: &[u8],
+) -> Result<(ScriptPubkeyContainer, sha256::Hash), strict_encoding::Error> {
+    strict_decode(&value)
+}
+
+/// Puts commitment reveal data into the PSBT output, replacing the data
+/// which may be already present
+pub fn insert_dbc_reveal(
+    output: &mut psbt::Output,
+    container: &ScriptPubkeyContainer,
+    digest: &sha256::Hash,
+) -> Result<(), strict_encoding::Error> {
+    let (key, value) = dbc_reveal_pair(container, digest)?;
+    output.unknown.insert(key, value);
+    Ok(())
+}
+
+/// Extracts commitment reveal data from the PSBT output; returns `None` if
+/// the output does not contain them
+pub fn extract_dbc_reveal(
+    output: &psbt::Output,
+) -> Result<Option<(ScriptPubkeyContainer, sha256::Hash)>, strict_encoding::Error> {
+    output
+        .unknown
+        .get(&dbc_reveal_key())
+        .map(|value| parse_dbc_reveal(value))
+        .transpose()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::dbc::{ScriptInfo, ScriptPubkeyCommitment, ScriptPubkeyComposition};
+    use crate::bp::test::gen_secp_pubkeys;
+    use crate::commit_verify::EmbedCommitVerify;
+    use amplify::Wrapper;
+    use bitcoin::consensus::encode::{deserialize, serialize};
+    use bitcoin::hashes::Hash;
+    use bitcoin::util::psbt::PartiallySignedTransaction;
+    use bitcoin::{OutPoint, Script, Transaction, TxIn, TxOut};
+
+    #[test]
+    fn test_psbt_dbc_reveal() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            gen_secp_pubkeys(1)[0],
+            ScriptInfo::None,
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        let digest = sha256::Hash::hash(b"client-side-validated data");
+        let commitment =
+            ScriptPubkeyCommitment::embed_commit(&container, &digest.into_inner()).unwrap();
+
+        let tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::default(),
+                script_sig: Script::new(),
+                sequence: 0xFFFFFFFF,
+                witness: vec![],
+            }],
+            output: vec![
+                TxOut {
+                    value: 1000,
+                    script_pubkey: commitment.into_inner().into_inner(),
+                },
+                TxOut {
+                    value: 2000,
+                    script_pubkey: Script::new(),
+                },
+            ],
+        };
+        let mut psbt = PartiallySignedTransaction::from_unsigned_tx(tx).unwrap();
+        assert!(extract_dbc_reveal(&psbt.outputs[0]).unwrap().is_none());
+        insert_dbc_reveal(&mut psbt.outputs[0], &container, &digest).unwrap();
+
+        // Proprietary data must survive PSBT serialization
+        let psbt: PartiallySignedTransaction = deserialize(&serialize(&psbt)).unwrap();
+        let (revealed, revealed_digest) = extract_dbc_reveal(&psbt.outputs[0])
+            .unwrap()
+            .expect("reveal data must be present");
+        assert_eq!(revealed, container);
+        assert_eq!(revealed_digest, digest);
+        assert!(extract_dbc_reveal(&psbt.outputs[1]).unwrap().is_none());
+
+        // Finalizer verifies the commitment against the output
+        let output_commitment = ScriptPubkeyCommitment::from_inner(
+            psbt.global.unsigned_tx.output[0]
+                .script_pubkey
+                .clone()
+                .into(),
+        );
+        assert!(output_commitment
+            .verify(&revealed, &revealed_digest.into_inner())
+            .unwrap());
+
+        // Corrupted data are reported
+        let mut corrupted = psbt.outputs[0].clone();
+        corrupted.unknown.insert(dbc_reveal_key(), vec![0x01, 0x02]);
+        assert!(extract_dbc_reveal(&corrupted).is_err());
+    }
+}
@@ -29,7 +29,7 @@ use bitcoin::hashes::{sha256, Hash, HashEngine, Hmac, HmacEngine};
 use bitcoin::secp256k1;
 
 use super::{Container, Error, Proof};
-use crate::commit_verify::EmbedCommitVerify;
+use crate::commit_verify::{constant_time_eq, EmbedCommitVerify};
 use crate::SECP256K1;
 
 /// Single SHA256 hash of "LNPBP1" string according to LNPBP-1 acting as a
@@ -39,6 +39,10 @@ pub(super) static SHA256_LNPBP1: [u8; 32] = [
     252, 82, 171, 140, 204, 209, 41, 17, 12, 0, 64, 175,
 ];
 
+/// Tag used instead of "LNPBP1" for the first prefix of nonce-based
+/// commitments
+pub(super) const LNPBP1_NONCE_TAG: &'static str = "LNPBP1:nonce";
+
 /// Container for LNPBP-1 commitments. In order to be constructed, commitment
 /// requires an original public key and a protocol-specific tag, which
 /// must be hashed during commitment process. Here we use pre-hashed version
@@ -50,6 +54,103 @@ pub struct LNPBP1Container {
     pub pubkey: secp256k1::PublicKey,
     /// Single SHA256 hash of the protocol-specific tag
     pub tag: sha256::Hash,
+    /// Optional nonce mixed into the tweaking factor. Without it anyone who
+    /// knows the original public key and guesses the message is able to
+    /// confirm the guess; with the nonce the commitment becomes hiding. The
+    /// nonce is a part of the [Proof], so it is revealed to the verifier
+    /// together with the original public key.
+    nonce: Option<[u8; 32]>,
+}
+
+impl LNPBP1Container {
+    /// Constructs container for the commitment without nonce
+    #[inline]
+    pub fn new(pubkey: secp256k1::PublicKey, tag: sha256::Hash) -> Self {
+        Self {
+            pubkey,
+            tag,
+            nonce: None,
+        }
+    }
+
+    /// Constructs container which will mix caller-supplied `nonce` into the
+    /// commitment
+    pub fn with_nonce(pubkey: secp256k1::PublicKey, tag: sha256::Hash, nonce: [u8; 32]) -> Self {
+        Self {
+            pubkey,
+            tag,
+            nonce: Some(nonce),
+        }
+    }
+
+    /// Constructs container with the nonce deterministically derived from
+    /// the secret `seed` (known only to the committing party), original
+    /// public key, tag and the message:
+    /// `nonce = HMAC_SHA256(seed, P || SHA256(<protocol-specific-tag>) || msg)`
+    pub fn with_deterministic_nonce(
+        pubkey: secp256k1::PublicKey,
+        tag: sha256::Hash,
+        seed: &[u8],
+        msg: &impl AsRef<[u8]>,
+    ) -> Self {
+        let mut hmac_engine = HmacEngine::<sha256::Hash>::new(seed);
+        hmac_engine.input(&pubkey.serialize());
+        hmac_engine.input(&tag[..]);
+        hmac_engine.input(msg.as_ref());
+        Self::with_nonce(pubkey, tag, Hmac::from_engine(hmac_engine).into_inner())
+    }
+
+    /// Returns nonce mixed into the commitment, if any
+    #[inline]
+    pub fn nonce(&self) -> Option<[u8; 32]> {
+        self.nonce
+    }
+
+    /// Computes LNPBP-1 tweaking factor for the message:
+    /// `f = HMAC_SHA256(SHA256("LNPBP1") || SHA256(<protocol-specific-tag>) || msg, P)`,
+    /// or, for the containers with nonce,
+    /// `f = HMAC_SHA256(SHA256("LNPBP1:nonce") || SHA256(<protocol-specific-tag>) || nonce || msg, P)`.
+    /// The tweaked public key is `T = P + G * f`.
+    ///
+    /// NB: LNPBP-1 defines the factor as HMAC keyed with the original public
+    /// key, not as a BIP-340 style tagged hash; switching to the tagged hash
+    /// would break compatibility with the existing commitments.
+    pub fn tweaking_factor(&self, msg: &impl AsRef<[u8]>) -> Hmac<sha256::Hash> {
+        // ! [CONSENSUS-CRITICAL]:
+        // ! [STANDARD-CRITICAL]: HMAC engine is based on sha256 hash
+        let mut hmac_engine = HmacEngine::<sha256::Hash>::new(&self.pubkey.serialize());
+
+        // ! [CONSENSUS-CRITICAL]:
+        // ! [STANDARD-CRITICAL]: Hash process started with consuming first
+        //                        protocol prefix: single SHA256 hash of
+        //                        ASCII "LNPBP-1" string
+        match self.nonce {
+            None => hmac_engine.input(&SHA256_LNPBP1),
+            Some(_) => hmac_engine.input(&sha256::Hash::hash(LNPBP1_NONCE_TAG.as_bytes())[..]),
+        }
+
+        // ! [CONSENSUS-CRITICAL]:
+        // ! [STANDARD-CRITICAL]: The second prefix comes from the upstream
+        //                        protocol as a part of the container
+        hmac_engine.input(&self.tag[..]);
+
+        // ! [CONSENSUS-CRITICAL]:
+        // ! [STANDARD-CRITICAL]: Optional nonce precedes the message
+        if let Some(nonce) = self.nonce {
+            hmac_engine.input(&nonce);
+        }
+
+        // ! [CONSENSUS-CRITICAL]:
+        // ! [STANDARD-CRITICAL]: Next we hash the message. The message must be
+        //                        prefixed with the protocol-specific prefix:
+        //                        another single SHA256 hash of protocol name.
+        //                        However this is not the part of this function,
+        //                        the function expect that the `msg` is already
+        //                        properly prefixed
+        hmac_engine.input(msg.as_ref());
+
+        Hmac::from_engine(hmac_engine)
+    }
 }
 
 impl Container for LNPBP1Container {
@@ -66,25 +167,30 @@ impl Container for LNPBP1Container {
         Ok(Self {
             pubkey: proof.pubkey,
             tag: supplement.clone(),
+            nonce: proof.nonce,
         })
     }
 
     #[inline]
     fn deconstruct(self) -> (Proof, Self::Supplement) {
-        (Proof::from(self.pubkey), self.tag)
+        let tag = self.tag;
+        (self.into_proof(), tag)
     }
 
     // A proof for the LNPBP-1 public key commitment is the original public key
-    // value, so the commitment container (original public key) just returns a
-    // copy of itself
+    // value (and the nonce, if any), so the commitment container just returns
+    // a copy of itself
     #[inline]
     fn to_proof(&self) -> Proof {
-        Proof::from(self.pubkey.clone())
+        self.clone().into_proof()
     }
 
     #[inline]
     fn into_proof(self) -> Proof {
-        Proof::from(self.pubkey)
+        Proof {
+            nonce: self.nonce,
+            ..Proof::from(self.pubkey)
+        }
     }
 }
 
@@ -95,6 +201,55 @@ wrapper!(
     derive = [PartialEq, Eq, Hash]
 );
 
+impl LNPBP1Commitment {
+    /// Returns the public key after the message tweak, i.e. `T = P + G * f`
+    /// (see [LNPBP1Commitment::embed_commit]), which may be matched against
+    /// the key found in the transaction witness or used to build an address
+    #[inline]
+    pub fn tweaked_pubkey(&self) -> secp256k1::PublicKey {
+        self.0
+    }
+
+    /// Returns `true` if the tweaked public key has odd Y coordinate. Such key
+    /// is negated when used as BIP-340 x-only key, so the owner of the
+    /// original private key has to negate the tweaked private key as well to
+    /// produce a valid Schnorr signature (or taproot key-path witness).
+    #[inline]
+    pub fn requires_negation(&self) -> bool {
+        self.0.serialize()[0] == 0x03
+    }
+
+    /// Returns BIP-340 x-only representation of the tweaked public key
+    #[inline]
+    pub fn to_xonly(&self) -> [u8; 32] {
+        let mut xonly = [0u8; 32];
+        xonly.copy_from_slice(&self.0.serialize()[1..]);
+        xonly
+    }
+
+    /// Verifies that the x-only public key (for instance coming from taproot
+    /// output) contains commitment to the message with the provided
+    /// container. Returns `None` if the commitment does not match, otherwise
+    /// the parity flag of the tweaked key (see
+    /// [LNPBP1Commitment::requires_negation]) which was dropped by the x-only
+    /// serialization.
+    pub fn verify_xonly<MSG>(
+        xonly: &[u8; 32],
+        container: &LNPBP1Container,
+        msg: &MSG,
+    ) -> Result<Option<bool>, secp256k1::Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        let commitment = Self::embed_commit(container, msg)?;
+        Ok(if &commitment.to_xonly() == xonly {
+            Some(commitment.requires_negation())
+        } else {
+            None
+        })
+    }
+}
+
 impl<MSG> EmbedCommitVerify<MSG> for LNPBP1Commitment
 where
     MSG: AsRef<[u8]>,
@@ -133,45 +288,68 @@ where
     /// The final formula for the commitment is:
     /// `T = P + G * HMAC_SHA256(SHA256("LNPBP1") || SHA256(<protocol-specific-tag>) || msg, P)`
     ///
+    /// If the container provides a `nonce`, the first prefix is replaced with
+    /// a single SHA256 hash of `LNPBP1:nonce` string and the nonce is put
+    /// in front of the message:
+    /// `T = P + G * HMAC_SHA256(SHA256("LNPBP1:nonce") || SHA256(<protocol-specific-tag>) || nonce || msg, P)`
+    /// Distinct prefix guarantees that a nonce-based commitment can't be
+    /// interpreted as a commitment without nonce to `nonce || msg`.
+    ///
     /// NB: According to LNPBP-1 the message supplied here must be already
     /// prefixed with 32-byte SHA256 hash of the protocol-specific prefix
+    ///
+    /// The message bytes are fed into the HMAC engine directly from the
+    /// provided slice without copying, so the cost of the commitment grows
+    /// linearly with the message size and no additional memory is allocated.
+    /// SHA256 limits the message size to `2^61 - 1` bytes; in practice the
+    /// limit is defined by the memory required to keep the message itself.
 
     // #[consensus_critical]
     // #[standard_critical("LNPBP-1")]
     fn embed_commit(pubkey_container: &Self::Container, msg: &MSG) -> Result<Self, Self::Error> {
-        // ! [CONSENSUS-CRITICAL]:
-        // ! [STANDARD-CRITICAL]: HMAC engine is based on sha256 hash
-        let mut hmac_engine = HmacEngine::<sha256::Hash>::new(&pubkey_container.pubkey.serialize());
-
-        // ! [CONSENSUS-CRITICAL]:
-        // ! [STANDARD-CRITICAL]: Hash process started with consuming first
-        //                        protocol prefix: single SHA256 hash of
-        //                        ASCII "LNPBP-1" string
-        hmac_engine.input(&SHA256_LNPBP1);
-
-        // ! [CONSENSUS-CRITICAL]:
-        // ! [STANDARD-CRITICAL]: The second prefix comes from the upstream
-        //                        protocol as a part of the container
-        hmac_engine.input(&pubkey_container.tag[..]);
-
-        // ! [CONSENSUS-CRITICAL]:
-        // ! [STANDARD-CRITICAL]: Next we hash the message. The message must be
-        //                        prefixed with the protocol-specific prefix:
-        //                        another single SHA256 hash of protocol name.
-        //                        However this is not the part of this function,
-        //                        the function expect that the `msg` is already
-        //                        properly prefixed
-        hmac_engine.input(msg.as_ref());
-
         // Producing tweaking factor
-        let factor = &Hmac::from_engine(hmac_engine)[..];
+        let factor = pubkey_container.tweaking_factor(msg);
         // Applying tweaking factor to public key
         let mut tweaked_pubkey = pubkey_container.pubkey.clone();
-        tweaked_pubkey.add_exp_assign(&SECP256K1, factor)?;
+        tweaked_pubkey.add_exp_assign(&SECP256K1, &factor[..])?;
 
         // Returning tweaked public key
         Ok(LNPBP1Commitment(tweaked_pubkey))
     }
+
+    /// Verifies the commitment comparing serialized tweaked keys in constant
+    /// time (see [constant_time_eq]), since the tweak is derived from the
+    /// message which may be secret
+    fn verify(&self, container: &Self::Container, msg: &MSG) -> Result<bool, Self::Error> {
+        Ok(match Self::embed_commit(container, msg) {
+            Ok(commitment) => constant_time_eq(&commitment.0.serialize(), &self.0.serialize()),
+            Err(_) => false,
+        })
+    }
+}
+
+/// Verifies that the `tweaked` public key (for instance, extracted from the
+/// transaction witness) commits to the message with LNPBP-1 procedure applied
+/// to the `original` public key and the protocol-specific `tag`, without
+/// constructing [LNPBP1Container] and a full commitment. Applicable to
+/// commitments without nonce only. Keys are compared in constant time (see
+/// [constant_time_eq]); tweaks which fail the procedure (see
+/// [LNPBP1Commitment::embed_commit]) verify as `false`.
+pub fn verify_with_pubkey<MSG>(
+    original: &secp256k1::PublicKey,
+    tweaked: &secp256k1::PublicKey,
+    tag: &sha256::Hash,
+    msg: &MSG,
+) -> bool
+where
+    MSG: AsRef<[u8]>,
+{
+    let container = LNPBP1Container::new(*original, *tag);
+    let mut expected = *original;
+    match expected.add_exp_assign(&SECP256K1, &container.tweaking_factor(msg)[..]) {
+        Ok(_) => constant_time_eq(&expected.serialize(), &tweaked.serialize()),
+        Err(_) => false,
+    }
 }
 
 #[cfg(test)]
@@ -179,6 +357,7 @@ mod test {
     use super::*;
     use crate::bp::test::*;
     use crate::commit_verify::test::*;
+    use crate::strict_encoding::{strict_decode, strict_encode};
     use amplify::Wrapper;
     use bitcoin::hashes::{hex::ToHex, sha256};
     use bitcoin::secp256k1;
@@ -222,11 +401,79 @@ mod test {
         gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
             embed_commit_verify_suite::<Vec<u8>, LNPBP1Commitment>(
                 gen_messages(),
-                &LNPBP1Container { pubkey, tag },
+                &LNPBP1Container::new(pubkey, tag),
             );
         });
     }
 
+    #[test]
+    fn test_tweaked_pubkey() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
+            let commitment =
+                LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey, tag), &msg).unwrap();
+
+            let mut engine = HmacEngine::<sha256::Hash>::new(&pubkey.serialize());
+            engine.input(&SHA256_LNPBP1);
+            engine.input(&tag[..]);
+            engine.input(msg.as_bytes());
+            let factor = Hmac::from_engine(engine);
+            let tweak = secp256k1::SecretKey::from_slice(&factor[..]).unwrap();
+            let tweak_point = secp256k1::PublicKey::from_secret_key(&SECP256K1, &tweak);
+            let expected = pubkey.combine(&tweak_point).unwrap();
+
+            assert_eq!(commitment.tweaked_pubkey(), expected);
+            assert_eq!(commitment.tweaked_pubkey(), *commitment);
+            assert_ne!(commitment.tweaked_pubkey(), pubkey);
+        });
+    }
+
+    #[test]
+    fn test_large_message() {
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        // 4 MB of non-repeating data
+        let msg = (0..4 * 1024 * 1024u32)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<u8>>();
+        let container = LNPBP1Container::new(pubkey, tag);
+        let commitment = LNPBP1Commitment::embed_commit(&container, &msg).unwrap();
+
+        // Reference tweak computed by streaming the message in small chunks
+        let mut engine = HmacEngine::<sha256::Hash>::new(&pubkey.serialize());
+        engine.input(&SHA256_LNPBP1);
+        engine.input(&tag[..]);
+        msg.chunks(1000).for_each(|chunk| engine.input(chunk));
+        let factor = Hmac::from_engine(engine);
+        let tweak = secp256k1::SecretKey::from_slice(&factor[..]).unwrap();
+        let tweak_point = secp256k1::PublicKey::from_secret_key(&SECP256K1, &tweak);
+        assert_eq!(*commitment, pubkey.combine(&tweak_point).unwrap());
+
+        assert!(commitment.verify(&container, &msg).unwrap());
+        let mut changed = msg.clone();
+        changed[3 * 1024 * 1024] ^= 0x01;
+        assert!(!commitment.verify(&container, &changed).unwrap());
+    }
+
+    #[test]
+    fn test_verify_matches_equality() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let container = LNPBP1Container::new(pubkey, tag);
+        let messages = gen_messages();
+        messages.iter().for_each(|msg| {
+            let commitment = LNPBP1Commitment::embed_commit(&container, msg).unwrap();
+            messages.iter().for_each(|other| {
+                let expected = LNPBP1Commitment::embed_commit(&container, other).unwrap();
+                assert_eq!(
+                    commitment.verify(&container, other).unwrap(),
+                    commitment == expected
+                );
+            });
+        });
+    }
+
     #[test]
     fn test_tweaking_results() {
         let tag = sha256::Hash::hash(b"TEST_TAG");
@@ -236,10 +483,223 @@ mod test {
         )
         .unwrap();
         let commitment =
-            LNPBP1Commitment::embed_commit(&LNPBP1Container { pubkey, tag }, &msg).unwrap();
+            LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey, tag), &msg).unwrap();
         assert_eq!(
             commitment.as_inner().to_hex(),
             "0278565af0da38a7754d3d4551a09bf80cf98841dbec7330db53023af5503acf8d"
         );
     }
+
+    #[test]
+    fn test_verify_with_pubkey() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let original = secp256k1::PublicKey::from_str(
+            "0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166",
+        )
+        .unwrap();
+        let tweaked = secp256k1::PublicKey::from_str(
+            "0278565af0da38a7754d3d4551a09bf80cf98841dbec7330db53023af5503acf8d",
+        )
+        .unwrap();
+
+        assert!(verify_with_pubkey(&original, &tweaked, &tag, &msg));
+        assert!(!verify_with_pubkey(
+            &original,
+            &tweaked,
+            &tag,
+            &"other message"
+        ));
+        assert!(!verify_with_pubkey(
+            &original,
+            &tweaked,
+            &sha256::Hash::hash(b"OTHER_TAG"),
+            &msg
+        ));
+        assert!(!verify_with_pubkey(&tweaked, &original, &tag, &msg));
+        assert!(!verify_with_pubkey(&original, &original, &tag, &msg));
+
+        gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
+            let commitment =
+                LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey, tag), &msg).unwrap();
+            assert!(verify_with_pubkey(&pubkey, &commitment, &tag, &msg));
+        });
+    }
+
+    #[test]
+    fn test_tweaking_factor() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = secp256k1::PublicKey::from_str(
+            "0218845781f631c48f1c9709e23092067d06837f30aa0cd0544ac887fe91ddd166",
+        )
+        .unwrap();
+        let container = LNPBP1Container::new(pubkey, tag);
+        let factor = container.tweaking_factor(&msg);
+        // Computed independently of this library as
+        // `HMAC_SHA256(key = P, SHA256("LNPBP1") || SHA256("TEST_TAG") || msg)`
+        assert_eq!(
+            factor.to_hex(),
+            "56ba36e0dc186ae0525f2563a8c4b209ab2b10940762f1b403e02381119ebeb7"
+        );
+
+        // T = P + G * f
+        let tweak = secp256k1::SecretKey::from_slice(&factor[..]).unwrap();
+        let tweak_point = secp256k1::PublicKey::from_secret_key(&SECP256K1, &tweak);
+        assert_eq!(
+            pubkey.combine(&tweak_point).unwrap().to_string(),
+            "0278565af0da38a7754d3d4551a09bf80cf98841dbec7330db53023af5503acf8d"
+        );
+        assert_eq!(
+            *LNPBP1Commitment::embed_commit(&container, &msg).unwrap(),
+            pubkey.combine(&tweak_point).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_nonce_commitment() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
+            let plain = LNPBP1Container::new(pubkey, tag);
+            let nonced = LNPBP1Container::with_nonce(pubkey, tag, [0xA5; 32]);
+            let other_nonce = LNPBP1Container::with_nonce(pubkey, tag, [0x5A; 32]);
+
+            embed_commit_verify_suite::<Vec<u8>, LNPBP1Commitment>(gen_messages(), &nonced);
+
+            let commitment = LNPBP1Commitment::embed_commit(&nonced, &msg).unwrap();
+            assert_ne!(
+                commitment,
+                LNPBP1Commitment::embed_commit(&plain, &msg).unwrap()
+            );
+            assert!(commitment.verify(&nonced, &msg).unwrap());
+            assert!(!commitment.verify(&plain, &msg).unwrap());
+            assert!(!commitment.verify(&other_nonce, &msg).unwrap());
+
+            // Nonce-based commitment must not be equal to a commitment without
+            // nonce to the nonce-prefixed message
+            let mut prefixed = [0xA5u8; 32].to_vec();
+            prefixed.extend(msg.as_bytes());
+            assert!(!commitment.verify(&plain, &prefixed).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_deterministic_nonce() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+
+        let container = LNPBP1Container::with_deterministic_nonce(pubkey, tag, b"seed", &msg);
+        assert_eq!(
+            container,
+            LNPBP1Container::with_deterministic_nonce(pubkey, tag, b"seed", &msg)
+        );
+        assert_ne!(
+            container.nonce,
+            LNPBP1Container::with_deterministic_nonce(pubkey, tag, b"other seed", &msg).nonce
+        );
+        assert_ne!(
+            container.nonce,
+            LNPBP1Container::with_deterministic_nonce(pubkey, tag, b"seed", &"other").nonce
+        );
+
+        let commitment = LNPBP1Commitment::embed_commit(&container, &msg).unwrap();
+        assert!(commitment.verify(&container, &msg).unwrap());
+    }
+
+    #[test]
+    fn test_nonce_proof_roundtrip() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let container = LNPBP1Container::with_nonce(pubkey, tag, [0xA5; 32]);
+        let commitment = LNPBP1Commitment::embed_commit(&container, &msg).unwrap();
+
+        // Nonce is carried by the proof and restored by the reconstruction
+        let (proof, supplement) = container.clone().deconstruct();
+        assert_eq!(proof, container.to_proof());
+        assert_eq!(proof.nonce, Some([0xA5; 32]));
+        let decoded: Proof = strict_decode(&strict_encode(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+        let reconstructed = LNPBP1Container::reconstruct(&decoded, &supplement, &None).unwrap();
+        assert_eq!(reconstructed, container);
+        assert!(commitment.verify(&reconstructed, &msg).unwrap());
+
+        // Proof without the nonce does not verify the nonce-based commitment
+        let stripped = Proof::from(pubkey);
+        let reconstructed = LNPBP1Container::reconstruct(&stripped, &supplement, &None).unwrap();
+        assert_eq!(reconstructed, LNPBP1Container::new(pubkey, tag));
+        assert!(!commitment.verify(&reconstructed, &msg).unwrap());
+
+        // Proofs without nonce keep their encoding: public key and script
+        // info tag only
+        let encoded = strict_encode(&stripped).unwrap();
+        assert_eq!(encoded.len(), 34);
+        assert_eq!(strict_encode(&proof).unwrap().len(), 34 + 33);
+        let decoded: Proof = strict_decode(&encoded).unwrap();
+        assert_eq!(decoded, stripped);
+    }
+
+    #[test]
+    fn test_tweak_parity() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let (mut even, mut odd) = (0usize, 0usize);
+        gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
+            let container = LNPBP1Container::new(pubkey, tag);
+            gen_messages().iter().for_each(|msg| {
+                let commitment = LNPBP1Commitment::embed_commit(&container, msg).unwrap();
+                let xonly = commitment.to_xonly();
+                let parity = commitment.requires_negation();
+                if parity {
+                    odd += 1;
+                    // Negated key must have even Y and the same x coordinate
+                    let mut negated = *commitment.as_inner();
+                    negated.negate_assign(&SECP256K1);
+                    assert_eq!(negated.serialize()[0], 0x02);
+                    assert_eq!(&negated.serialize()[1..], &xonly[..]);
+                } else {
+                    even += 1;
+                    assert_eq!(commitment.as_inner().serialize()[0], 0x02);
+                }
+                assert_eq!(
+                    LNPBP1Commitment::verify_xonly(&xonly, &container, msg).unwrap(),
+                    Some(parity)
+                );
+                assert_eq!(
+                    LNPBP1Commitment::verify_xonly(&xonly, &container, &b"other".to_vec()).unwrap(),
+                    None
+                );
+            });
+        });
+        // With 90 tweaks both outcomes must be present
+        assert_ne!(even, 0);
+        assert_ne!(odd, 0);
+    }
+
+    #[test]
+    fn test_borrowed_messages() {
+        let container =
+            LNPBP1Container::new(gen_secp_pubkeys(1)[0], sha256::Hash::hash(b"TEST_TAG"));
+        gen_messages().into_iter().for_each(|owned| {
+            let expected = LNPBP1Commitment::embed_commit(&container, &owned).unwrap();
+            let slice: &[u8] = &owned;
+            // Messages only need to implement `AsRef<[u8]>`, which holds for
+            // owned data, slices and any references to them
+            assert_eq!(
+                LNPBP1Commitment::embed_commit(&container, &slice).unwrap(),
+                expected
+            );
+            assert_eq!(
+                LNPBP1Commitment::embed_commit(&container, &&owned).unwrap(),
+                expected
+            );
+            assert_eq!(
+                LNPBP1Commitment::embed_commit(&container, &&slice).unwrap(),
+                expected
+            );
+            assert!(expected.verify(&container, &slice).unwrap());
+            assert!(expected.verify(&container, &&owned).unwrap());
+        });
+    }
 }
@@ -13,30 +13,397 @@
 
 use amplify::Wrapper;
 use bitcoin::blockdata::script::Builder;
-use bitcoin::{hashes::sha256, secp256k1};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{secp256k1, Transaction, TxOut};
 use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
+use miniscript::Descriptor;
+use num_derive::{FromPrimitive, ToPrimitive};
+use std::collections::BTreeSet;
 
 use super::{
     Container, Error, LNPBP1Commitment, LNPBP1Container, LockscriptCommitment, LockscriptContainer,
     Proof, ScriptInfo, TaprootCommitment, TaprootContainer,
 };
-use crate::bp::{GenerateScripts, LockScript, PubkeyScript, ScriptPubkeyDescriptor, Strategy};
-use crate::commit_verify::EmbedCommitVerify;
+use crate::bp::{
+    GenerateScripts, LockScript, PubkeyScript, RedeemScript, ScriptPubkeyDescriptor, Strategy,
+    WitnessScript,
+};
+use crate::commit_verify::{constant_time_eq, EmbedCommitVerify};
 
-#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+/// Maximum size of a script which may be spent, according to the Bitcoin
+/// consensus rules
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// Maximum size of a P2WSH witness script which is relayed by the nodes
+/// following Bitcoin Core standardness policy
+pub const MAX_STANDARD_WITNESS_SCRIPT_SIZE: usize = 3600;
+
+/// Maximum size of an `OP_RETURN` output `scriptPubkey` which is relayed by
+/// the nodes following Bitcoin Core standardness policy: 80 bytes of data
+/// plus `OP_RETURN` and push opcodes
+pub const MAX_STANDARD_OP_RETURN_SIZE: usize = 83;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display, ToPrimitive, FromPrimitive)]
 #[display_from(Debug)]
 #[non_exhaustive]
+#[repr(u8)]
 pub enum ScriptPubkeyComposition {
-    PublicKey,
-    PubkeyHash,
-    ScriptHash,
-    WPubkeyHash,
-    WScriptHash,
-    SHWPubkeyHash,
-    SHWScriptHash,
-    TapRoot,
-    OpReturn,
-    PlainScript,
+    PublicKey = 0,
+    PubkeyHash = 1,
+    ScriptHash = 2,
+    WPubkeyHash = 3,
+    WScriptHash = 4,
+    SHWPubkeyHash = 5,
+    SHWScriptHash = 6,
+    TapRoot = 7,
+    OpReturn = 8,
+    PlainScript = 9,
+    /// P2PK output with the uncompressed serialization of the tweaked key
+    UncompressedPublicKey = 10,
+    /// P2PKH output hashing the uncompressed serialization of the tweaked
+    /// key
+    UncompressedPubkeyHash = 11,
+}
+
+impl ScriptPubkeyComposition {
+    /// Returns whether the output uses the compressed serialization of the
+    /// tweaked public key. Compositions which do not serialize a single
+    /// public key always report `true` here.
+    pub fn is_compressed(&self) -> bool {
+        match self {
+            ScriptPubkeyComposition::UncompressedPublicKey
+            | ScriptPubkeyComposition::UncompressedPubkeyHash => false,
+            _ => true,
+        }
+    }
+}
+
+/// Category of the transaction output produced by [ScriptPubkeyContainer],
+/// named after the corresponding [ScriptPubkeyDescriptor] variants. Nested
+/// segwit outputs (P2SH-P2WPKH, P2SH-P2WSH) fall into the `P2SH` category.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ScriptPubkeyCategory {
+    P2S,
+    P2PK,
+    P2PKH,
+    P2SH,
+    P2OR,
+    P2WPKH,
+    P2WSH,
+    P2TR,
+}
+
+/// Commitment schemes which may be used to embed a commitment into the
+/// transaction output
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, ToPrimitive, FromPrimitive)]
+#[repr(u8)]
+pub enum CommitmentScheme {
+    /// LNPBP-1 tweak of a single public key ([LNPBP1Commitment])
+    PublicKey = 0,
+    /// LNPBP-2 tweak of the public keys inside the script
+    /// ([LockscriptCommitment])
+    LockScript = 1,
+    /// Tweak of the taproot intermediate key ([TaprootCommitment])
+    Taproot = 2,
+    /// LNPBP-1 tweak of the public key pushed by the `OP_RETURN` output,
+    /// optionally following the prefix push ([ScriptInfo::OpReturnPrefix])
+    OpReturn = 3,
+}
+
+/// Error parsing [ScriptPubkeyCategory] or [CommitmentScheme] from a string
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display_from(Debug)]
+pub enum NameParseError {
+    /// Unknown `scriptPubkey` category name
+    UnknownCategory(String),
+    /// Unknown commitment scheme name
+    UnknownScheme(String),
+}
+
+impl ScriptPubkeyCategory {
+    /// Stable lowercase name of the category, used by [fmt::Display] and
+    /// [FromStr]
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScriptPubkeyCategory::P2S => "p2s",
+            ScriptPubkeyCategory::P2PK => "p2pk",
+            ScriptPubkeyCategory::P2PKH => "p2pkh",
+            ScriptPubkeyCategory::P2SH => "p2sh",
+            ScriptPubkeyCategory::P2OR => "opret",
+            ScriptPubkeyCategory::P2WPKH => "p2wpkh",
+            ScriptPubkeyCategory::P2WSH => "p2wsh",
+            ScriptPubkeyCategory::P2TR => "p2tr",
+        }
+    }
+}
+
+impl fmt::Display for ScriptPubkeyCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ScriptPubkeyCategory {
+    type Err = NameParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "p2s" => ScriptPubkeyCategory::P2S,
+            "p2pk" => ScriptPubkeyCategory::P2PK,
+            "p2pkh" => ScriptPubkeyCategory::P2PKH,
+            "p2sh" => ScriptPubkeyCategory::P2SH,
+            "opret" => ScriptPubkeyCategory::P2OR,
+            "p2wpkh" => ScriptPubkeyCategory::P2WPKH,
+            "p2wsh" => ScriptPubkeyCategory::P2WSH,
+            "p2tr" => ScriptPubkeyCategory::P2TR,
+            _ => Err(NameParseError::UnknownCategory(s.to_string()))?,
+        })
+    }
+}
+
+impl CommitmentScheme {
+    /// Stable lowercase name of the scheme, used by [fmt::Display] and
+    /// [FromStr]
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommitmentScheme::PublicKey => "pubkey",
+            CommitmentScheme::LockScript => "lockscript",
+            CommitmentScheme::Taproot => "taproot",
+            CommitmentScheme::OpReturn => "opret",
+        }
+    }
+}
+
+impl fmt::Display for CommitmentScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for CommitmentScheme {
+    type Err = NameParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "pubkey" => CommitmentScheme::PublicKey,
+            "lockscript" => CommitmentScheme::LockScript,
+            "taproot" => CommitmentScheme::Taproot,
+            "opret" => CommitmentScheme::OpReturn,
+            _ => Err(NameParseError::UnknownScheme(s.to_string()))?,
+        })
+    }
+}
+
+/// Returns list of the commitment schemes which may be applied to the output
+/// of a given category; empty list means that commitments into this output
+/// category are not supported
+pub fn supported_commitments(category: ScriptPubkeyCategory) -> &'static [CommitmentScheme] {
+    use CommitmentScheme::*;
+    use ScriptPubkeyCategory as Cat;
+    match category {
+        Cat::P2S => &[LockScript],
+        Cat::P2PK => &[PublicKey],
+        Cat::P2PKH => &[PublicKey],
+        // P2SH may wrap either a custom script or P2WPKH
+        Cat::P2SH => &[PublicKey, LockScript],
+        // OP_RETURN outputs hold a tweaked public key, but can't be a taproot
+        // or script-based commitment
+        Cat::P2OR => &[OpReturn],
+        Cat::P2WPKH => &[PublicKey],
+        Cat::P2WSH => &[LockScript],
+        Cat::P2TR => &[Taproot],
+    }
+}
+
+impl From<&ScriptPubkeyDescriptor> for ScriptPubkeyCategory {
+    fn from(descriptor: &ScriptPubkeyDescriptor) -> Self {
+        use ScriptPubkeyCategory as Cat;
+        use ScriptPubkeyDescriptor as Descr;
+        match descriptor {
+            Descr::P2S(_) => Cat::P2S,
+            Descr::P2PK(_) => Cat::P2PK,
+            Descr::P2PKH(_) => Cat::P2PKH,
+            Descr::P2SH(_) => Cat::P2SH,
+            Descr::P2OR(_) => Cat::P2OR,
+            Descr::P2WPKH(_) => Cat::P2WPKH,
+            Descr::P2WSH(_) => Cat::P2WSH,
+            Descr::P2TR(_) => Cat::P2TR,
+        }
+    }
+}
+
+/// Detects composition of the (already tweaked) output from its
+/// `scriptPubkey`. P2PKH outputs are always reported as
+/// [ScriptPubkeyComposition::PubkeyHash], since the key serialization can't
+/// be detected from the hash. P2SH outputs, bare scripts and `OP_RETURN`
+/// outputs not ending with a public key push (including the ones with
+/// non-push opcodes or truncated pushes) fail with
+/// [Error::UnrecognizedScriptPubkey].
+impl TryFrom<&PubkeyScript> for ScriptPubkeyComposition {
+    type Error = Error;
+
+    fn try_from(script_pubkey: &PubkeyScript) -> Result<Self, Self::Error> {
+        use ScriptPubkeyComposition as Comp;
+        use ScriptPubkeyDescriptor as Descr;
+
+        Ok(match ScriptPubkeyDescriptor::try_from(script_pubkey)? {
+            Descr::P2PK(key) if !key.compressed => Comp::UncompressedPublicKey,
+            Descr::P2PK(_) => Comp::PublicKey,
+            Descr::P2PKH(_) => Comp::PubkeyHash,
+            Descr::P2WPKH(_) => Comp::WPubkeyHash,
+            Descr::P2WSH(_) => Comp::WScriptHash,
+            Descr::P2TR(_) => Comp::TapRoot,
+            Descr::P2OR(data) => match data.as_slice() {
+                [pubkey] | [_, pubkey] if secp256k1::PublicKey::from_slice(pubkey).is_ok() => {
+                    Comp::OpReturn
+                }
+                _ => Err(Error::UnrecognizedScriptPubkey)?,
+            },
+            Descr::P2SH(_) | Descr::P2S(_) => Err(Error::UnrecognizedScriptPubkey)?,
+        })
+    }
+}
+
+impl TryFrom<PubkeyScript> for ScriptPubkeyComposition {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(script_pubkey: PubkeyScript) -> Result<Self, Self::Error> {
+        Self::try_from(&script_pubkey)
+    }
+}
+
+/// Lists all transaction outputs which may host a deterministic bitcoin
+/// commitment, together with the commitment schemes applicable to them (see
+/// [supported_commitments]). Outputs supporting several schemes are listed
+/// once per scheme; outputs which can't be parsed (like witness programs of
+/// unknown versions) and bare scripts which are provably unspendable (like
+/// `OP_RETURN` outputs with non-push opcodes) are skipped.
+pub fn enumerate_commitment_sites(tx: &Transaction) -> Vec<(usize, CommitmentScheme)> {
+    tx.output
+        .iter()
+        .enumerate()
+        .filter_map(|(vout, txout)| {
+            let script_pubkey = PubkeyScript::from(txout.script_pubkey.clone());
+            match ScriptPubkeyDescriptor::try_from(&script_pubkey) {
+                Ok(ScriptPubkeyDescriptor::P2S(script)) if script.is_provably_unspendable() => None,
+                Ok(descriptor) => Some((vout, ScriptPubkeyCategory::from(&descriptor))),
+                Err(_) => None,
+            }
+        })
+        .flat_map(|(vout, category)| {
+            supported_commitments(category)
+                .iter()
+                .map(move |scheme| (vout, *scheme))
+        })
+        .collect()
+}
+
+/// Checks that the transaction carries at most a single commitment of the
+/// protocol with the given tag, so the verifier can tell which of them is
+/// authoritative. `revealed` lists containers revealed for the transaction
+/// outputs (by output number); containers of other protocols and containers
+/// which can't produce the existing output (see
+/// [ScriptPubkeyCommitment::matches_category]) are not counted. Returns the
+/// sorted numbers of outputs with the protocol commitments; if there is
+/// more than one such output and `allow_multiple` is `false` fails with
+/// [Error::ConflictingCommitments].
+pub fn check_single_commitment(
+    tx: &Transaction,
+    protocol_tag: &sha256::Hash,
+    revealed: &[(u32, ScriptPubkeyContainer)],
+    allow_multiple: bool,
+) -> Result<Vec<u32>, Error> {
+    let sites = revealed
+        .iter()
+        .filter(|(_, container)| container.tag == *protocol_tag)
+        .filter(|(vout, container)| {
+            tx.output.get(*vout as usize).map_or(false, |txout| {
+                ScriptPubkeyCommitment::from_inner(PubkeyScript::from(txout.script_pubkey.clone()))
+                    .matches_category(container)
+            })
+        })
+        .map(|(vout, _)| *vout)
+        .collect::<BTreeSet<_>>();
+    if sites.len() > 1 && !allow_multiple {
+        Err(Error::ConflictingCommitments(sites.into_iter().collect()))?
+    }
+    Ok(sites.into_iter().collect())
+}
+
+/// Commits to the `msg` using explicitly selected commitment `scheme`. Fails
+/// with [Error::CommitmentSchemeMismatch] if the container data and output
+/// category do not correspond to the requested scheme (see
+/// [ScriptPubkeyContainer::commitment_scheme]), otherwise dispatches to the
+/// commitment procedure of the scheme.
+pub fn commit_to_scheme<MSG>(
+    scheme: CommitmentScheme,
+    container: &ScriptPubkeyContainer,
+    msg: &MSG,
+) -> Result<ScriptPubkeyCommitment, Error>
+where
+    MSG: AsRef<[u8]>,
+{
+    if container.commitment_scheme() != Some(scheme) {
+        Err(Error::CommitmentSchemeMismatch)?
+    }
+    ScriptPubkeyCommitment::embed_commit(container, msg)
+}
+
+/// Commits to the `msg` inside the output defined by the output `descriptor`,
+/// tweaking the `pubkey` (see [ScriptPubkeyContainer::construct_from_descriptor]
+/// for the details on the container construction)
+pub fn commit_to_descriptor<MSG>(
+    protocol_tag: &sha256::Hash,
+    descriptor: &Descriptor<bitcoin::PublicKey>,
+    pubkey: secp256k1::PublicKey,
+    msg: &MSG,
+) -> Result<ScriptPubkeyCommitment, Error>
+where
+    MSG: AsRef<[u8]>,
+{
+    let container =
+        ScriptPubkeyContainer::construct_from_descriptor(protocol_tag, descriptor, pubkey)?;
+    ScriptPubkeyCommitment::embed_commit(&container, msg)
+}
+
+/// Commits to the `msg` inside the transaction output, tweaking the `pubkey`
+/// the output is built on. The container is constructed from the output
+/// `scriptPubkey` with [ScriptPubkeyContainer::construct_from_scripts], so
+/// outputs requiring additional scripts (P2SH, P2WSH) and taproot outputs
+/// fail with [Error::InvalidProofStructure]; use the container constructor
+/// directly for them. Outputs with a single public key which does not
+/// correspond to `pubkey` fail with [Error::DescriptorKeyMismatch]. Returns
+/// the commitment together with the new output, which has the same value as
+/// the original one and the commitment as its `scriptPubkey`. Fails with
+/// [Error::NonZeroOpReturnValue] for `OP_RETURN` outputs with non-zero value.
+pub fn commit_to_txout<MSG>(
+    protocol_tag: &sha256::Hash,
+    pubkey: secp256k1::PublicKey,
+    txout: &TxOut,
+    msg: &MSG,
+) -> Result<(ScriptPubkeyCommitment, TxOut), Error>
+where
+    MSG: AsRef<[u8]>,
+{
+    let container = ScriptPubkeyContainer::construct_from_scripts(
+        protocol_tag,
+        pubkey,
+        &PubkeyScript::from(txout.script_pubkey.clone()),
+        None,
+        None,
+    )?;
+    if container.scriptpubkey_composition == ScriptPubkeyComposition::OpReturn && txout.value != 0 {
+        Err(Error::NonZeroOpReturnValue(txout.value))?
+    }
+    let commitment = ScriptPubkeyCommitment::embed_commit(&container, msg)?;
+    let txout = TxOut {
+        value: txout.value,
+        script_pubkey: commitment.as_inner().to_inner(),
+    };
+    Ok((commitment, txout))
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
@@ -63,6 +430,236 @@ impl ScriptPubkeyContainer {
             tag: protocol_tag.clone(),
         }
     }
+
+    /// Constructs container for the nested segwit P2SH-P2WPKH output: the
+    /// commitment tweaks `pubkey`, which is then wrapped into P2WPKH witness
+    /// program inside P2SH
+    #[inline]
+    pub fn nested_pubkey_hash(protocol_tag: &sha256::Hash, pubkey: secp256k1::PublicKey) -> Self {
+        Self::construct(
+            protocol_tag,
+            pubkey,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::SHWPubkeyHash,
+        )
+    }
+
+    /// Constructs container for the nested segwit P2SH-P2WSH output: the
+    /// commitment tweaks `pubkey` inside the `lockscript`, which is then
+    /// wrapped into P2WSH witness program inside P2SH
+    #[inline]
+    pub fn nested_script_hash(
+        protocol_tag: &sha256::Hash,
+        pubkey: secp256k1::PublicKey,
+        lockscript: LockScript,
+    ) -> Self {
+        Self::construct(
+            protocol_tag,
+            pubkey,
+            ScriptInfo::LockScript(lockscript),
+            ScriptPubkeyComposition::SHWScriptHash,
+        )
+    }
+
+    /// Constructs container for the output defined by the output `descriptor`
+    /// (in its not yet tweaked form). For the descriptors with a single public
+    /// key the `pubkey` must match the descriptor key, failing with
+    /// [Error::DescriptorKeyMismatch] otherwise; for the script-based
+    /// descriptors the `pubkey` is looked up in the script during the
+    /// commitment procedure.
+    pub fn construct_from_descriptor(
+        protocol_tag: &sha256::Hash,
+        descriptor: &Descriptor<bitcoin::PublicKey>,
+        pubkey: secp256k1::PublicKey,
+    ) -> Result<Self, Error> {
+        use ScriptPubkeyComposition as Comp;
+
+        let check_key = |key: &bitcoin::PublicKey| {
+            if key.key == pubkey {
+                Ok(ScriptInfo::None)
+            } else {
+                Err(Error::DescriptorKeyMismatch)
+            }
+        };
+        let compression = |key: &bitcoin::PublicKey, compressed, uncompressed| {
+            if key.compressed {
+                compressed
+            } else {
+                uncompressed
+            }
+        };
+        let lockscript = |ms: &miniscript::Miniscript<bitcoin::PublicKey>| {
+            ScriptInfo::LockScript(LockScript::from(ms.encode()))
+        };
+        let (script_info, composition) = match descriptor {
+            Descriptor::Bare(ms) => (lockscript(ms), Comp::PlainScript),
+            Descriptor::Pk(key) => (
+                check_key(key)?,
+                compression(key, Comp::PublicKey, Comp::UncompressedPublicKey),
+            ),
+            Descriptor::Pkh(key) => (
+                check_key(key)?,
+                compression(key, Comp::PubkeyHash, Comp::UncompressedPubkeyHash),
+            ),
+            Descriptor::Wpkh(key) => (check_key(key)?, Comp::WPubkeyHash),
+            Descriptor::ShWpkh(key) => (check_key(key)?, Comp::SHWPubkeyHash),
+            Descriptor::Sh(ms) => (lockscript(ms), Comp::ScriptHash),
+            Descriptor::Wsh(ms) => (lockscript(ms), Comp::WScriptHash),
+            Descriptor::ShWsh(ms) => (lockscript(ms), Comp::SHWScriptHash),
+        };
+
+        Ok(Self::construct(
+            protocol_tag,
+            pubkey,
+            script_info,
+            composition,
+        ))
+    }
+
+    /// Returns category of the output which will be produced by committing
+    /// to this container
+    pub fn classify(&self) -> ScriptPubkeyCategory {
+        use ScriptPubkeyCategory as Cat;
+        use ScriptPubkeyComposition as Comp;
+        match self.scriptpubkey_composition {
+            Comp::PlainScript => Cat::P2S,
+            Comp::PublicKey | Comp::UncompressedPublicKey => Cat::P2PK,
+            Comp::PubkeyHash | Comp::UncompressedPubkeyHash => Cat::P2PKH,
+            Comp::ScriptHash | Comp::SHWPubkeyHash | Comp::SHWScriptHash => Cat::P2SH,
+            Comp::OpReturn => Cat::P2OR,
+            Comp::WPubkeyHash => Cat::P2WPKH,
+            Comp::WScriptHash => Cat::P2WSH,
+            Comp::TapRoot => Cat::P2TR,
+        }
+    }
+
+    /// Returns commitment scheme which will be used for committing to this
+    /// container, or `None` if the container data do not match any of the
+    /// schemes supported by the container output category
+    pub fn commitment_scheme(&self) -> Option<CommitmentScheme> {
+        let scheme = match self.script_info {
+            ScriptInfo::None
+                if self.scriptpubkey_composition == ScriptPubkeyComposition::OpReturn =>
+            {
+                CommitmentScheme::OpReturn
+            }
+            ScriptInfo::None => CommitmentScheme::PublicKey,
+            ScriptInfo::OpReturnPrefix(_) => CommitmentScheme::OpReturn,
+            ScriptInfo::LockScript(_) => CommitmentScheme::LockScript,
+            ScriptInfo::Taproot(_) => CommitmentScheme::Taproot,
+        };
+        if supported_commitments(self.classify()).contains(&scheme) {
+            Some(scheme)
+        } else {
+            None
+        }
+    }
+
+    /// Constructs container for the transaction output from its (not yet
+    /// tweaked) `scriptPubkey` and, optionally, the redeem & witness scripts
+    /// (for instance, taken from the PSBT output data).
+    ///
+    /// The output type is detected by analyzing `script_pubkey`, and the script
+    /// which is actually committed to is selected accordingly: the redeem
+    /// script for P2SH, the witness script for P2WSH and P2SH-P2WSH, or the
+    /// `scriptPubkey` itself for bare non-standard scripts. Fails with
+    /// [Error::InvalidProofStructure] if the required script is absent or does
+    /// not match the hash in the `scriptPubkey`. For the outputs with a single
+    /// public key (P2PK, P2PKH, P2WPKH and P2SH-P2WPKH) the key or its hash
+    /// must correspond to `pubkey`, failing with [Error::DescriptorKeyMismatch]
+    /// otherwise. `OP_RETURN` outputs must end with the push of `pubkey`,
+    /// optionally preceded by a single prefix push, failing with
+    /// [Error::InvalidProofStructure] otherwise.
+    pub fn construct_from_scripts(
+        protocol_tag: &sha256::Hash,
+        pubkey: secp256k1::PublicKey,
+        script_pubkey: &PubkeyScript,
+        redeem_script: Option<&RedeemScript>,
+        witness_script: Option<&WitnessScript>,
+    ) -> Result<Self, Error> {
+        use ScriptPubkeyComposition as Comp;
+        use ScriptPubkeyDescriptor as Descr;
+
+        let compressed = bitcoin::PublicKey {
+            compressed: true,
+            key: pubkey,
+        };
+        let uncompressed = bitcoin::PublicKey {
+            compressed: false,
+            key: pubkey,
+        };
+        let (script_info, composition) = match ScriptPubkeyDescriptor::try_from(script_pubkey)? {
+            Descr::P2SH(script_hash) => {
+                let redeem_script = redeem_script.ok_or(Error::InvalidProofStructure)?;
+                if redeem_script.script_hash() != script_hash {
+                    Err(Error::InvalidProofStructure)?
+                }
+                let p2wpkh = compressed.gen_script_pubkey(Strategy::WitnessV0)?;
+                match (redeem_script.is_v0_p2wpkh(), witness_script) {
+                    (true, _) if **redeem_script != *p2wpkh => Err(Error::DescriptorKeyMismatch)?,
+                    (true, _) => (ScriptInfo::None, Comp::SHWPubkeyHash),
+                    (false, Some(witness_script)) => {
+                        let lockscript = LockScript::from(witness_script.to_inner());
+                        if *lockscript.gen_script_pubkey(Strategy::WitnessV0)? != **redeem_script {
+                            Err(Error::InvalidProofStructure)?
+                        }
+                        (ScriptInfo::LockScript(lockscript), Comp::SHWScriptHash)
+                    }
+                    (false, None) => (
+                        ScriptInfo::LockScript(LockScript::from(redeem_script.to_inner())),
+                        Comp::ScriptHash,
+                    ),
+                }
+            }
+            Descr::P2WSH(wscript_hash) => {
+                let witness_script = witness_script.ok_or(Error::InvalidProofStructure)?;
+                if witness_script.script_hash() != wscript_hash {
+                    Err(Error::InvalidProofStructure)?
+                }
+                (
+                    ScriptInfo::LockScript(LockScript::from(witness_script.to_inner())),
+                    Comp::WScriptHash,
+                )
+            }
+            Descr::P2S(script) => (
+                ScriptInfo::LockScript(LockScript::from(script.to_inner())),
+                Comp::PlainScript,
+            ),
+            Descr::P2PK(key) if key.key != pubkey => Err(Error::DescriptorKeyMismatch)?,
+            Descr::P2PK(key) if !key.compressed => (ScriptInfo::None, Comp::UncompressedPublicKey),
+            Descr::P2PK(_) => (ScriptInfo::None, Comp::PublicKey),
+            // Untweaked key is known here, so we can detect which of its
+            // serializations is hashed by the output
+            Descr::P2PKH(pubkey_hash) if pubkey_hash == uncompressed.pubkey_hash() => {
+                (ScriptInfo::None, Comp::UncompressedPubkeyHash)
+            }
+            Descr::P2PKH(pubkey_hash) if pubkey_hash == compressed.pubkey_hash() => {
+                (ScriptInfo::None, Comp::PubkeyHash)
+            }
+            Descr::P2WPKH(wpubkey_hash) if wpubkey_hash == compressed.wpubkey_hash() => {
+                (ScriptInfo::None, Comp::WPubkeyHash)
+            }
+            Descr::P2PKH(_) | Descr::P2WPKH(_) => Err(Error::DescriptorKeyMismatch)?,
+            // The last push must be the public key itself; push preceding it
+            // is kept as the prefix of the commitment
+            Descr::P2OR(data) => match data.as_slice() {
+                [key] if key[..] == pubkey.serialize()[..] => (ScriptInfo::None, Comp::OpReturn),
+                [prefix, key] if key[..] == pubkey.serialize()[..] => {
+                    (ScriptInfo::OpReturnPrefix(prefix.clone()), Comp::OpReturn)
+                }
+                _ => Err(Error::InvalidProofStructure)?,
+            },
+            // Taproot script root can't be recovered from the output data
+            Descr::P2TR(_) => Err(Error::InvalidProofStructure)?,
+        };
+
+        Ok(Self::construct(
+            protocol_tag,
+            pubkey,
+            script_info,
+            composition,
+        ))
+    }
 }
 
 impl Container for ScriptPubkeyContainer {
@@ -78,51 +675,53 @@ impl Container for ScriptPubkeyContainer {
         use ScriptPubkeyComposition as Comp;
         use ScriptPubkeyDescriptor as Descr;
 
-        let (lockscript, _) = match &proof.script_info {
-            ScriptInfo::None => (None, None),
-            ScriptInfo::LockScript(script) => (Some(script), None),
-            ScriptInfo::Taproot(hash) => (None, Some(hash)),
-        };
-
-        let mut proof = proof.clone();
-        let composition = match ScriptPubkeyDescriptor::try_from(host.clone())? {
-            Descr::P2SH(script_hash) => {
-                let script = Builder::gen_p2sh(&script_hash).into_script();
-                if let Some(lockscript) = lockscript {
-                    if *lockscript.gen_script_pubkey(Strategy::LegacyHashed) == script {
-                        Comp::ScriptHash
-                    } else if *lockscript.gen_script_pubkey(Strategy::WitnessScriptHash) == script {
-                        Comp::SHWScriptHash
-                    } else {
-                        Err(Error::InvalidProofStructure)?
-                    }
-                } else {
-                    if *proof.pubkey.gen_script_pubkey(Strategy::WitnessScriptHash) == script {
-                        Comp::SHWPubkeyHash
-                    } else {
-                        Err(Error::InvalidProofStructure)?
-                    }
+        let composition = match ScriptPubkeyDescriptor::try_from(host)? {
+            // P2SH outputs may contain either legacy-hashed lockscript or
+            // P2WSH/P2WPKH nested into P2SH. Since the host contains already
+            // tweaked keys, we can't distinguish the two lockscript cases here
+            // without the message; so we default to `ScriptHash` and
+            // `ScriptPubkeyCommitment::verify` tries both options
+            Descr::P2SH(_) => match proof.script_info {
+                ScriptInfo::LockScript(_) => Comp::ScriptHash,
+                _ => Comp::SHWPubkeyHash,
+            },
+            Descr::P2S(_) => Comp::PlainScript,
+            Descr::P2PK(key) if !key.compressed => Comp::UncompressedPublicKey,
+            Descr::P2PK(_) => Comp::PublicKey,
+            // Key serialization can't be detected from the hash, so
+            // `ScriptPubkeyCommitment::verify` tries both options
+            Descr::P2PKH(_) => Comp::PubkeyHash,
+            // OP_RETURN commitment must contain exactly a single push of the
+            // tweaked public key, optionally preceded by the push of the
+            // prefix from the proof
+            Descr::P2OR(data) => match (data.as_slice(), &proof.script_info) {
+                ([pubkey], ScriptInfo::None)
+                    if secp256k1::PublicKey::from_slice(pubkey).is_ok() =>
+                {
+                    Comp::OpReturn
                 }
-            }
-            Descr::P2S(script) => {
-                proof.script_info = ScriptInfo::LockScript(LockScript::from(script.to_inner()));
-                Comp::PlainScript
-            }
-            Descr::P2PK(_) => Comp::PubkeyHash,
-            Descr::P2PKH(_) => Comp::PublicKey,
-            Descr::P2OR(_) => Comp::OpReturn,
+                ([prefix, pubkey], ScriptInfo::OpReturnPrefix(expected))
+                    if prefix == expected && secp256k1::PublicKey::from_slice(pubkey).is_ok() =>
+                {
+                    Comp::OpReturn
+                }
+                _ => Err(Error::InvalidProofStructure)?,
+            },
             Descr::P2WPKH(_) => Comp::WPubkeyHash,
             Descr::P2WSH(_) => Comp::WScriptHash,
             Descr::P2TR(_) => Comp::TapRoot,
         };
-        let proof = proof;
+        let proof = proof.clone();
 
         match composition {
+            // Script info was already checked against the OP_RETURN data
+            Comp::OpReturn => {}
             Comp::PublicKey
             | Comp::PubkeyHash
+            | Comp::UncompressedPublicKey
+            | Comp::UncompressedPubkeyHash
             | Comp::WPubkeyHash
-            | Comp::SHWPubkeyHash
-            | Comp::OpReturn => {
+            | Comp::SHWPubkeyHash => {
                 if let ScriptInfo::None = proof.script_info {
                 } else {
                     Err(Error::InvalidProofStructure)?
@@ -155,6 +754,7 @@ impl Container for ScriptPubkeyContainer {
             Proof {
                 pubkey: self.pubkey,
                 script_info: self.script_info,
+                nonce: None,
             },
             self.tag,
         )
@@ -164,6 +764,7 @@ impl Container for ScriptPubkeyContainer {
         Proof {
             pubkey: self.pubkey.clone(),
             script_info: self.script_info.clone(),
+            nonce: None,
         }
     }
 
@@ -171,6 +772,7 @@ impl Container for ScriptPubkeyContainer {
         Proof {
             pubkey: self.pubkey,
             script_info: self.script_info,
+            nonce: None,
         }
     }
 }
@@ -182,16 +784,197 @@ wrapper!(
     derive = [PartialEq, Eq, Hash]
 );
 
-impl<MSG> EmbedCommitVerify<MSG> for ScriptPubkeyCommitment
-where
-    MSG: AsRef<[u8]>,
-{
-    type Container = ScriptPubkeyContainer;
-    type Error = super::Error;
+impl ScriptPubkeyCommitment {
+    /// Commits the same message to each of the provided containers, returning
+    /// per-container commitment results in the same order
+    pub fn commit_batch<MSG>(
+        containers: &[ScriptPubkeyContainer],
+        msg: &MSG,
+    ) -> Vec<Result<Self, Error>>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        containers
+            .iter()
+            .map(|container| Self::embed_commit(container, msg))
+            .collect()
+    }
 
-    fn embed_commit(container: &Self::Container, msg: &MSG) -> Result<Self, Self::Error> {
+    /// Checks whether transaction output number `vout` contains deterministic
+    /// commitment to the `msg`, reconstructing the original container from
+    /// the `proof` and the output `scriptPubkey`. Returns `false` if there is
+    /// no such output, the proof does not match the output structure, or the
+    /// output does not commit to the message.
+    pub fn verify_against_tx<MSG>(
+        tx: &Transaction,
+        vout: usize,
+        proof: &Proof,
+        protocol_tag: &sha256::Hash,
+        msg: &MSG,
+    ) -> bool
+    where
+        MSG: AsRef<[u8]>,
+    {
+        let host = match tx.output.get(vout) {
+            Some(txout) => PubkeyScript::from(txout.script_pubkey.clone()),
+            None => return false,
+        };
+        let container = match ScriptPubkeyContainer::reconstruct(proof, protocol_tag, &host) {
+            Ok(container) => container,
+            Err(_) => return false,
+        };
+        Self::from_inner(host)
+            .verify(&container, msg)
+            .unwrap_or(false)
+    }
+
+    /// Verifies the commitment to the `msg` like [EmbedCommitVerify::verify]
+    /// and additionally checks that the commitment equals to the `expected`
+    /// on-chain `scriptPubkey`. Prevents accepting a valid commitment which
+    /// does not actually appear in the transaction output.
+    pub fn verify_script_pubkey<MSG>(
+        &self,
+        container: &ScriptPubkeyContainer,
+        msg: &MSG,
+        expected: &PubkeyScript,
+    ) -> Result<bool, Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        if !self.matches_script(expected) {
+            return Ok(false);
+        }
+        self.verify(container, msg)
+    }
+
+    /// Checks that the commitment corresponds to the given on-chain `script`
+    /// without re-running the commitment procedure; unlike
+    /// [ScriptPubkeyCommitment::verify_script_pubkey] this does not prove that
+    /// the script commits to any specific message.
+    #[inline]
+    pub fn matches_script(&self, script: &PubkeyScript) -> bool {
+        self.as_inner() == script
+    }
+
+    /// Checks that the commitment output belongs to the category of the
+    /// outputs produced by the `container` (see
+    /// [ScriptPubkeyContainer::classify]), so the container may be used to
+    /// reveal the commitment. Commitments which can't be classified (like
+    /// witness programs of unknown versions) are matched only by the
+    /// containers of [ScriptPubkeyCategory::P2S] category. Provably
+    /// unspendable bare scripts (like `OP_RETURN` outputs with non-push
+    /// opcodes) are never produced by the containers and match none of them.
+    pub fn matches_category(&self, container: &ScriptPubkeyContainer) -> bool {
+        let category = container.classify();
+        match ScriptPubkeyDescriptor::try_from(self.as_inner()) {
+            Ok(ScriptPubkeyDescriptor::P2S(script)) if script.is_provably_unspendable() => false,
+            Ok(descriptor) => ScriptPubkeyCategory::from(&descriptor) == category,
+            Err(_) => category == ScriptPubkeyCategory::P2S,
+        }
+    }
+
+    /// Reads tweaked public key back from the `OP_RETURN` output, skipping
+    /// the protocol prefix push if present. Returns `None` if the commitment
+    /// is not an `OP_RETURN` output or contains anything except an optional
+    /// prefix followed by a single push of a valid public key.
+    pub fn op_return_pubkey(&self) -> Option<secp256k1::PublicKey> {
+        match ScriptPubkeyDescriptor::try_from(self.as_inner()) {
+            Ok(ScriptPubkeyDescriptor::P2OR(data)) => match data.as_slice() {
+                [pubkey] | [_, pubkey] => secp256k1::PublicKey::from_slice(pubkey).ok(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Creates commitment like [EmbedCommitVerify::embed_commit], but accepts
+    /// witness scripts up to the consensus limit of [MAX_SCRIPT_SIZE] bytes
+    /// instead of the standardness limit of
+    /// [MAX_STANDARD_WITNESS_SCRIPT_SIZE]. Such outputs are valid, but may not
+    /// be relayed by the network nodes.
+    pub fn embed_commit_consensus<MSG>(
+        container: &ScriptPubkeyContainer,
+        msg: &MSG,
+    ) -> Result<Self, Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        Self::commit_with_limit(container, msg, MAX_SCRIPT_SIZE)
+    }
+
+    /// Creates commitment with [EmbedCommitVerify::embed_commit] and returns
+    /// it together with the `scriptPubkey` which must be put into the
+    /// transaction output, such that the two can't diverge
+    pub fn embed_commit_with_script<MSG>(
+        container: &ScriptPubkeyContainer,
+        msg: &MSG,
+    ) -> Result<(Self, PubkeyScript), Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        let commitment = Self::embed_commit(container, msg)?;
+        let script_pubkey = commitment.as_inner().clone();
+        Ok((commitment, script_pubkey))
+    }
+
+    /// Creates commitment with [EmbedCommitVerify::embed_commit] and returns
+    /// it together with the lock script tweaked by the commitment, which is
+    /// required for spending the output: it must be used as the witness
+    /// script for P2WSH and P2SH-P2WSH outputs, or as the redeem script for
+    /// P2SH outputs. For containers without lock script returns `None` in
+    /// place of the script.
+    pub fn embed_commit_with_lockscript<MSG>(
+        container: &ScriptPubkeyContainer,
+        msg: &MSG,
+    ) -> Result<(Self, Option<LockScript>), Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        let commitment = Self::embed_commit(container, msg)?;
+        let lockscript = match container.script_info {
+            ScriptInfo::LockScript(ref lockscript) => Some(
+                LockscriptCommitment::embed_commit(
+                    &LockscriptContainer {
+                        script: lockscript.clone(),
+                        pubkey: container.pubkey,
+                        tag: container.tag,
+                    },
+                    msg,
+                )?
+                .into_inner(),
+            ),
+            _ => None,
+        };
+        Ok((commitment, lockscript))
+    }
+
+    fn commit_with_limit<MSG>(
+        container: &ScriptPubkeyContainer,
+        msg: &MSG,
+        max_witness_script_size: usize,
+    ) -> Result<Self, Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
         use ScriptPubkeyComposition::*;
+        log_trace!(
+            "Committing into {} output using {:?} commitment scheme",
+            container.scriptpubkey_composition,
+            container.commitment_scheme()
+        );
         let script_pubkey = if let ScriptInfo::LockScript(ref lockscript) = container.script_info {
+            if lockscript.is_empty() {
+                Err(Error::EmptyScript)?
+            }
+            if lockscript.is_provably_unspendable() || lockscript.len() > MAX_SCRIPT_SIZE {
+                Err(Error::NonStandardScript)?
+            }
+            match container.scriptpubkey_composition {
+                WScriptHash | SHWScriptHash if lockscript.len() > max_witness_script_size => {
+                    Err(Error::ScriptTooLarge)?
+                }
+                _ => {}
+            }
             let lockscript = LockscriptCommitment::embed_commit(
                 &LockscriptContainer {
                     script: lockscript.clone(),
@@ -202,17 +985,17 @@ where
             )?
             .into_inner();
             match container.scriptpubkey_composition {
-                PlainScript => lockscript.gen_script_pubkey(Strategy::Exposed),
-                ScriptHash => lockscript.gen_script_pubkey(Strategy::LegacyHashed),
-                WScriptHash => lockscript.gen_script_pubkey(Strategy::WitnessV0),
-                SHWScriptHash => lockscript.gen_script_pubkey(Strategy::WitnessScriptHash),
+                PlainScript => lockscript.gen_script_pubkey(Strategy::Exposed)?,
+                ScriptHash => lockscript.gen_script_pubkey(Strategy::LegacyHashed)?,
+                WScriptHash => lockscript.gen_script_pubkey(Strategy::WitnessV0)?,
+                SHWScriptHash => lockscript.gen_script_pubkey(Strategy::WitnessScriptHash)?,
                 _ => Err(Error::InvalidProofStructure)?,
             }
         } else if let ScriptInfo::Taproot(taproot_hash) = container.script_info {
             if container.scriptpubkey_composition != TapRoot {
                 Err(Error::InvalidProofStructure)?
             }
-            let _taproot = TaprootCommitment::embed_commit(
+            let taproot = TaprootCommitment::embed_commit(
                 &TaprootContainer {
                     script_root: taproot_hash,
                     intermediate_key: container.pubkey,
@@ -220,28 +1003,2355 @@ where
                 },
                 msg,
             )?;
-            // TODO: Finalize taproot commitments once taproot will be finalized
-            // We don't know yet how to form scripPubkey from Taproot data
-            unimplemented!()
+            // Output key is already tweaked, so we put it into the witness
+            // program as is
+            ScriptPubkeyDescriptor::P2TR(taproot.output_key()?).into()
         } else {
             let pubkey = *LNPBP1Commitment::embed_commit(
-                &LNPBP1Container {
-                    pubkey: container.pubkey,
-                    tag: container.tag,
-                },
+                &LNPBP1Container::new(container.pubkey, container.tag),
                 msg,
             )?;
-            match container.scriptpubkey_composition {
-                PublicKey => pubkey.gen_script_pubkey(Strategy::Exposed),
-                PubkeyHash => pubkey.gen_script_pubkey(Strategy::LegacyHashed),
-                WPubkeyHash => pubkey.gen_script_pubkey(Strategy::WitnessV0),
-                SHWScriptHash => pubkey.gen_script_pubkey(Strategy::WitnessScriptHash),
-                OpReturn => Builder::gen_op_return(&pubkey.serialize().to_vec())
-                    .into_script()
-                    .into(),
+            let uncompressed = bitcoin::PublicKey {
+                compressed: false,
+                key: pubkey,
+            };
+            match (&container.scriptpubkey_composition, &container.script_info) {
+                (PublicKey, ScriptInfo::None) => pubkey.gen_script_pubkey(Strategy::Exposed)?,
+                (PubkeyHash, ScriptInfo::None) => {
+                    pubkey.gen_script_pubkey(Strategy::LegacyHashed)?
+                }
+                (UncompressedPublicKey, ScriptInfo::None) => {
+                    uncompressed.gen_script_pubkey(Strategy::Exposed)?
+                }
+                (UncompressedPubkeyHash, ScriptInfo::None) => {
+                    uncompressed.gen_script_pubkey(Strategy::LegacyHashed)?
+                }
+                (WPubkeyHash, ScriptInfo::None) => pubkey.gen_script_pubkey(Strategy::WitnessV0)?,
+                (SHWPubkeyHash, ScriptInfo::None) => {
+                    pubkey.gen_script_pubkey(Strategy::WitnessScriptHash)?
+                }
+                (OpReturn, ScriptInfo::None) => {
+                    Builder::gen_op_return(&pubkey.serialize().to_vec())
+                        .into_script()
+                        .into()
+                }
+                (OpReturn, ScriptInfo::OpReturnPrefix(prefix)) => {
+                    let script = Builder::gen_op_return(prefix)
+                        .push_slice(&pubkey.serialize())
+                        .into_script();
+                    if script.len() > MAX_STANDARD_OP_RETURN_SIZE {
+                        Err(Error::OpReturnTooLarge)?
+                    }
+                    script.into()
+                }
                 _ => Err(Error::InvalidProofStructure)?,
             }
         };
+        log_debug!(
+            "Commitment into {} output produced scriptPubkey {:?}",
+            container.scriptpubkey_composition,
+            script_pubkey.as_inner()
+        );
         Ok(ScriptPubkeyCommitment::from_inner(script_pubkey))
     }
 }
+
+impl<MSG> EmbedCommitVerify<MSG> for ScriptPubkeyCommitment
+where
+    MSG: AsRef<[u8]>,
+{
+    type Container = ScriptPubkeyContainer;
+    type Error = super::Error;
+
+    /// Creates commitment rejecting witness scripts larger than
+    /// [MAX_STANDARD_WITNESS_SCRIPT_SIZE] with [Error::ScriptTooLarge], since
+    /// the resulting outputs would not be relayed by the network nodes. Use
+    /// [ScriptPubkeyCommitment::embed_commit_consensus] to allow witness
+    /// scripts up to the consensus limit.
+    #[inline]
+    fn embed_commit(container: &Self::Container, msg: &MSG) -> Result<Self, Self::Error> {
+        Self::commit_with_limit(container, msg, MAX_STANDARD_WITNESS_SCRIPT_SIZE)
+    }
+
+    /// Verifies commitment against the container. Since legacy P2SH and
+    /// P2SH-nested P2WSH outputs can't be distinguished during container
+    /// reconstruction (see [ScriptPubkeyContainer::reconstruct]), for the
+    /// `ScriptHash` composition both options are checked. Scripts are compared
+    /// in constant time (see [constant_time_eq]). Containers which can't
+    /// produce output of the commitment category are rejected before
+    /// re-committing (see [ScriptPubkeyCommitment::matches_category]).
+    fn verify(&self, container: &Self::Container, msg: &MSG) -> Result<bool, Self::Error> {
+        if !self.matches_category(container) {
+            log_debug!(
+                "Commitment verification failed: {:?} container can't produce {:?}",
+                container.classify(),
+                self.as_inner().as_inner()
+            );
+            return Ok(false);
+        }
+        let mut container = container.clone();
+        loop {
+            // Outputs with witness scripts above the standardness limit are
+            // still valid, so we verify them up to the consensus limit
+            if let Ok(commitment) = Self::commit_with_limit(&container, msg, MAX_SCRIPT_SIZE) {
+                let matches =
+                    constant_time_eq(commitment.as_inner().as_bytes(), self.as_inner().as_bytes());
+                log_trace!(
+                    "Commitment verification for {} composition: {}",
+                    container.scriptpubkey_composition,
+                    if matches { "match" } else { "mismatch" }
+                );
+                if matches {
+                    return Ok(true);
+                }
+            }
+            match container.scriptpubkey_composition {
+                ScriptPubkeyComposition::ScriptHash => {
+                    container.scriptpubkey_composition = ScriptPubkeyComposition::SHWScriptHash
+                }
+                ScriptPubkeyComposition::PubkeyHash => {
+                    container.scriptpubkey_composition =
+                        ScriptPubkeyComposition::UncompressedPubkeyHash
+                }
+                _ => return Ok(false),
+            }
+        }
+    }
+}
+
+/// Self-contained proof of the commitment to some message digest, which may
+/// be transferred to the verifier together with the client-side-validated
+/// data. Holds the revealed container, the committed digest and the
+/// `scriptPubkey` which is expected to be found in the transaction output.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct CommitmentProof {
+    /// Revealed container used for the commitment
+    pub container: ScriptPubkeyContainer,
+    /// Digest of the committed message, used as the commitment message
+    pub digest: sha256::Hash,
+    /// `scriptPubkey` containing the commitment
+    pub script_pubkey: PubkeyScript,
+}
+
+impl CommitmentProof {
+    /// Commits to the message `digest` with the given container and
+    /// constructs the proof holding the resulting `scriptPubkey`
+    pub fn commit(container: ScriptPubkeyContainer, digest: sha256::Hash) -> Result<Self, Error> {
+        let commitment = ScriptPubkeyCommitment::embed_commit(&container, &digest.into_inner())?;
+        Ok(Self {
+            container,
+            digest,
+            script_pubkey: commitment.into_inner(),
+        })
+    }
+
+    /// Recomputes the commitment from the container and the digest and
+    /// checks that it matches the `scriptPubkey` of the proof
+    pub fn verify(&self) -> Result<bool, Error> {
+        ScriptPubkeyCommitment::from_inner(self.script_pubkey.clone())
+            .verify(&self.container, &self.digest.into_inner())
+    }
+}
+
+/// Agreement between the parties of a multi-party protocol (like the ones
+/// collaboratively constructing a PSBT) on the transaction output and the
+/// commitment scheme which will carry the commitment. Strict encoding of
+/// the agreement may be exchanged as a value of the PSBT proprietary key.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct NegotiatedCommitment {
+    /// Index of the transaction output which will carry the commitment
+    vout: u32,
+    /// Commitment scheme agreed by the parties
+    scheme: CommitmentScheme,
+    /// Container for the output which will carry the commitment
+    container: ScriptPubkeyContainer,
+}
+
+impl NegotiatedCommitment {
+    /// Constructs the agreement, failing with
+    /// [Error::CommitmentSchemeMismatch] if the scheme can't be applied to
+    /// the container (see [ScriptPubkeyContainer::commitment_scheme])
+    pub fn with(
+        vout: u32,
+        scheme: CommitmentScheme,
+        container: ScriptPubkeyContainer,
+    ) -> Result<Self, Error> {
+        if container.commitment_scheme() != Some(scheme) {
+            Err(Error::CommitmentSchemeMismatch)?
+        }
+        Ok(Self {
+            vout,
+            scheme,
+            container,
+        })
+    }
+
+    #[inline]
+    pub fn vout(&self) -> u32 {
+        self.vout
+    }
+
+    #[inline]
+    pub fn scheme(&self) -> CommitmentScheme {
+        self.scheme
+    }
+
+    #[inline]
+    pub fn container(&self) -> &ScriptPubkeyContainer {
+        &self.container
+    }
+
+    /// Commits to the message with the agreed scheme and container,
+    /// producing `scriptPubkey` for the agreed output
+    pub fn embed_commit<MSG>(&self, msg: &MSG) -> Result<ScriptPubkeyCommitment, Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        commit_to_scheme(self.scheme, &self.container, msg)
+    }
+}
+
+mod strict_encoding {
+    use super::*;
+    use crate::strict_encoding::{strategies, Error, Strategy, StrictDecode, StrictEncode};
+    use num_traits::{FromPrimitive, ToPrimitive};
+    use std::io;
+
+    impl_enum_strict_encoding!(ScriptPubkeyComposition);
+    impl_enum_strict_encoding!(CommitmentScheme);
+
+    impl Strategy for ScriptPubkeyCommitment {
+        type Strategy = strategies::Wrapped;
+    }
+
+    impl StrictEncode for ScriptPubkeyContainer {
+        type Error = Error;
+
+        fn strict_encode<E: io::Write>(&self, mut e: E) -> Result<usize, Error> {
+            Ok(strict_encode_list!(e;
+                self.pubkey,
+                self.script_info,
+                self.scriptpubkey_composition,
+                self.tag
+            ))
+        }
+    }
+
+    impl StrictDecode for ScriptPubkeyContainer {
+        type Error = Error;
+
+        fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, Error> {
+            Ok(Self {
+                pubkey: secp256k1::PublicKey::strict_decode(&mut d)?,
+                script_info: ScriptInfo::strict_decode(&mut d)?,
+                scriptpubkey_composition: ScriptPubkeyComposition::strict_decode(&mut d)?,
+                tag: sha256::Hash::strict_decode(&mut d)?,
+            })
+        }
+    }
+
+    impl StrictEncode for CommitmentProof {
+        type Error = Error;
+
+        fn strict_encode<E: io::Write>(&self, mut e: E) -> Result<usize, Error> {
+            Ok(strict_encode_list!(e;
+                self.container,
+                self.digest,
+                self.script_pubkey
+            ))
+        }
+    }
+
+    impl StrictDecode for CommitmentProof {
+        type Error = Error;
+
+        fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, Error> {
+            Ok(Self {
+                container: ScriptPubkeyContainer::strict_decode(&mut d)?,
+                digest: sha256::Hash::strict_decode(&mut d)?,
+                script_pubkey: PubkeyScript::strict_decode(&mut d)?,
+            })
+        }
+    }
+
+    impl StrictEncode for NegotiatedCommitment {
+        type Error = Error;
+
+        fn strict_encode<E: io::Write>(&self, mut e: E) -> Result<usize, Error> {
+            Ok(strict_encode_list!(e;
+                self.vout,
+                self.scheme,
+                self.container
+            ))
+        }
+    }
+
+    impl StrictDecode for NegotiatedCommitment {
+        type Error = Error;
+
+        /// Fails with [Error::DataIntegrityError] if the decoded scheme can't
+        /// be applied to the decoded container
+        fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, Error> {
+            NegotiatedCommitment::with(
+                u32::strict_decode(&mut d)?,
+                CommitmentScheme::strict_decode(&mut d)?,
+                ScriptPubkeyContainer::strict_decode(&mut d)?,
+            )
+            .map_err(|_| {
+                Error::DataIntegrityError(
+                    "Commitment scheme does not match the container".to_string(),
+                )
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::test::*;
+    use crate::commit_verify::test::gen_messages;
+    use bitcoin::blockdata::opcodes::all::{OP_CHECKMULTISIG, OP_CHECKSIG};
+    use bitcoin::hashes::Hash;
+    use bitcoin::Script;
+
+    fn check_reconstruction(container: ScriptPubkeyContainer, expected: ScriptPubkeyComposition) {
+        let msg = "test message";
+        let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+        let reconstructed = ScriptPubkeyContainer::reconstruct(
+            &container.to_proof(),
+            &container.tag,
+            commitment.as_inner(),
+        )
+        .unwrap();
+        assert_eq!(reconstructed.scriptpubkey_composition, expected);
+        assert_eq!(reconstructed.pubkey, container.pubkey);
+        assert_eq!(reconstructed.script_info, container.script_info);
+        assert!(commitment.verify(&reconstructed, &msg).unwrap());
+        assert!(!commitment.verify(&reconstructed, &"other message").unwrap());
+    }
+
+    #[test]
+    fn test_pubkey_reconstruction() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
+            vec![PublicKey, PubkeyHash, WPubkeyHash, SHWPubkeyHash, OpReturn]
+                .into_iter()
+                .for_each(|composition| {
+                    check_reconstruction(
+                        ScriptPubkeyContainer::construct(
+                            &tag,
+                            pubkey,
+                            ScriptInfo::None,
+                            composition.clone(),
+                        ),
+                        composition,
+                    )
+                });
+        });
+    }
+
+    #[test]
+    fn test_key_compression() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        gen_bitcoin_pubkeys(3, false).into_iter().for_each(|key| {
+            let tweaked =
+                *LNPBP1Commitment::embed_commit(&LNPBP1Container::new(key.key, tag), &msg).unwrap();
+            let commit = |composition| {
+                let container =
+                    ScriptPubkeyContainer::construct(&tag, key.key, ScriptInfo::None, composition);
+                ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap()
+            };
+
+            // P2PK uses the serialization required by the composition
+            let compressed = commit(PublicKey);
+            assert_eq!(compressed.len(), 35);
+            assert_eq!(compressed.as_bytes()[1..34], tweaked.serialize()[..]);
+            let uncompressed = commit(UncompressedPublicKey);
+            assert_eq!(uncompressed.len(), 67);
+            assert_eq!(
+                uncompressed.as_bytes()[1..66],
+                tweaked.serialize_uncompressed()[..]
+            );
+            assert!(PublicKey.is_compressed());
+            assert!(!UncompressedPublicKey.is_compressed());
+
+            // Compression of P2PK keys is detected from the output
+            check_reconstruction(
+                ScriptPubkeyContainer::construct(&tag, key.key, ScriptInfo::None, PublicKey),
+                PublicKey,
+            );
+            check_reconstruction(
+                ScriptPubkeyContainer::construct(
+                    &tag,
+                    key.key,
+                    ScriptInfo::None,
+                    UncompressedPublicKey,
+                ),
+                UncompressedPublicKey,
+            );
+
+            // P2PKH key serialization can't be detected from the tweaked
+            // output, but the commitment still verifies
+            let container = ScriptPubkeyContainer::construct(
+                &tag,
+                key.key,
+                ScriptInfo::None,
+                UncompressedPubkeyHash,
+            );
+            let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+            assert_ne!(commitment, commit(PubkeyHash));
+            let reconstructed = ScriptPubkeyContainer::reconstruct(
+                &container.to_proof(),
+                &tag,
+                commitment.as_inner(),
+            )
+            .unwrap();
+            assert_eq!(reconstructed.scriptpubkey_composition, PubkeyHash);
+            assert!(commitment.verify(&reconstructed, &msg).unwrap());
+            assert!(!commitment.verify(&reconstructed, &"other message").unwrap());
+
+            // ... while the untweaked one reveals the serialization
+            let container = ScriptPubkeyContainer::construct_from_scripts(
+                &tag,
+                key.key,
+                &key.gen_script_pubkey(Strategy::LegacyHashed).unwrap(),
+                None,
+                None,
+            )
+            .unwrap();
+            assert_eq!(container.scriptpubkey_composition, UncompressedPubkeyHash);
+            let compressed_key = bitcoin::PublicKey {
+                compressed: true,
+                key: key.key,
+            };
+            let container = ScriptPubkeyContainer::construct_from_scripts(
+                &tag,
+                key.key,
+                &compressed_key
+                    .gen_script_pubkey(Strategy::LegacyHashed)
+                    .unwrap(),
+                None,
+                None,
+            )
+            .unwrap();
+            assert_eq!(container.scriptpubkey_composition, PubkeyHash);
+
+            // Descriptors define the serialization explicitly
+            let container = ScriptPubkeyContainer::construct_from_descriptor(
+                &tag,
+                &Descriptor::Pk(key),
+                key.key,
+            )
+            .unwrap();
+            assert_eq!(container.scriptpubkey_composition, UncompressedPublicKey);
+            let container = ScriptPubkeyContainer::construct_from_descriptor(
+                &tag,
+                &Descriptor::Pkh(compressed_key),
+                key.key,
+            )
+            .unwrap();
+            assert_eq!(container.scriptpubkey_composition, PubkeyHash);
+        });
+    }
+
+    #[test]
+    fn test_composition_from_script_pubkey() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let lockscript = LockScript::from(
+            Builder::new()
+                .push_slice(&pubkey.serialize())
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        vec![
+            (PublicKey, ScriptInfo::None, PublicKey),
+            (
+                UncompressedPublicKey,
+                ScriptInfo::None,
+                UncompressedPublicKey,
+            ),
+            (PubkeyHash, ScriptInfo::None, PubkeyHash),
+            (UncompressedPubkeyHash, ScriptInfo::None, PubkeyHash),
+            (WPubkeyHash, ScriptInfo::None, WPubkeyHash),
+            (
+                WScriptHash,
+                ScriptInfo::LockScript(lockscript.clone()),
+                WScriptHash,
+            ),
+            (TapRoot, ScriptInfo::Taproot(None), TapRoot),
+            (OpReturn, ScriptInfo::None, OpReturn),
+            (
+                OpReturn,
+                ScriptInfo::OpReturnPrefix(b"LNPB".to_vec()),
+                OpReturn,
+            ),
+        ]
+        .into_iter()
+        .for_each(|(composition, script_info, expected)| {
+            let container =
+                ScriptPubkeyContainer::construct(&tag, pubkey, script_info, composition);
+            let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+            assert_eq!(
+                ScriptPubkeyComposition::try_from(commitment.as_inner()),
+                Ok(expected.clone())
+            );
+            assert_eq!(
+                ScriptPubkeyComposition::try_from(commitment.into_inner()),
+                Ok(expected)
+            );
+        });
+
+        vec![ScriptHash, SHWPubkeyHash, SHWScriptHash, PlainScript]
+            .into_iter()
+            .for_each(|composition| {
+                let script_info = match composition {
+                    SHWPubkeyHash => ScriptInfo::None,
+                    _ => ScriptInfo::LockScript(lockscript.clone()),
+                };
+                let container =
+                    ScriptPubkeyContainer::construct(&tag, pubkey, script_info, composition);
+                let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+                assert_eq!(
+                    ScriptPubkeyComposition::try_from(commitment.as_inner()),
+                    Err(Error::UnrecognizedScriptPubkey)
+                );
+            });
+
+        // OP_RETURN without public key
+        let script = PubkeyScript::from(Builder::gen_op_return(b"data").into_script());
+        assert_eq!(
+            ScriptPubkeyComposition::try_from(&script),
+            Err(Error::UnrecognizedScriptPubkey)
+        );
+
+        // OP_RETURN with non-push opcodes or truncated pushes
+        let mut with_opcode = vec![0x6a, 0x21];
+        with_opcode.extend(&pubkey.serialize()[..]);
+        with_opcode.push(0x51);
+        vec![vec![0x6a, 0x51], vec![0x6a, 0x21, 0x02, 0x03], with_opcode]
+            .into_iter()
+            .for_each(|bytes| {
+                let script = PubkeyScript::from(Script::from(bytes));
+                assert_eq!(
+                    ScriptPubkeyComposition::try_from(&script),
+                    Err(Error::UnrecognizedScriptPubkey)
+                );
+                assert_eq!(
+                    ScriptPubkeyComposition::try_from(script),
+                    Err(Error::UnrecognizedScriptPubkey)
+                );
+            });
+    }
+
+    #[test]
+    fn test_lockscript_reconstruction() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        gen_bitcoin_pubkeys(9, true).into_iter().for_each(|pubkey| {
+            // Bare `<pubkey> OP_CHECKSIG` script would be classified as P2PK
+            // output, so we use a bare multisig here
+            let lockscript = LockScript::from(
+                Builder::new()
+                    .push_int(1)
+                    .push_key(&pubkey)
+                    .push_int(1)
+                    .push_opcode(OP_CHECKMULTISIG)
+                    .into_script(),
+            );
+            vec![
+                (PlainScript, PlainScript),
+                (ScriptHash, ScriptHash),
+                (WScriptHash, WScriptHash),
+                // Reconstructs as `ScriptHash`, but still verifies
+                (SHWScriptHash, ScriptHash),
+            ]
+            .into_iter()
+            .for_each(|(composition, expected)| {
+                check_reconstruction(
+                    ScriptPubkeyContainer::construct(
+                        &tag,
+                        pubkey.key,
+                        ScriptInfo::LockScript(lockscript.clone()),
+                        composition,
+                    ),
+                    expected,
+                )
+            });
+        });
+    }
+
+    #[test]
+    fn test_nested_segwit_addresses() {
+        use bitcoin::{Address, Network};
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+
+        gen_bitcoin_pubkeys(4, true).into_iter().for_each(|pubkey| {
+            // P2SH-P2WPKH
+            let container = ScriptPubkeyContainer::nested_pubkey_hash(&tag, pubkey.key);
+            assert_eq!(container.classify(), ScriptPubkeyCategory::P2SH);
+            assert_eq!(
+                container.commitment_scheme(),
+                Some(CommitmentScheme::PublicKey)
+            );
+            let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+            let tweaked = bitcoin::PublicKey {
+                compressed: true,
+                key: *LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey.key, tag), &msg)
+                    .unwrap(),
+            };
+            let address = Address::from_script(commitment.as_inner(), Network::Bitcoin).unwrap();
+            assert_eq!(address, Address::p2shwpkh(&tweaked, Network::Bitcoin));
+            assert!(address.to_string().starts_with('3'));
+            assert!(commitment.verify(&container, &msg).unwrap());
+
+            // P2SH-P2WSH
+            let lockscript = LockScript::from(
+                Builder::new()
+                    .push_key(&pubkey)
+                    .push_opcode(OP_CHECKSIG)
+                    .into_script(),
+            );
+            let container =
+                ScriptPubkeyContainer::nested_script_hash(&tag, pubkey.key, lockscript.clone());
+            assert_eq!(container.classify(), ScriptPubkeyCategory::P2SH);
+            assert_eq!(
+                container.commitment_scheme(),
+                Some(CommitmentScheme::LockScript)
+            );
+            let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+            let tweaked = LockscriptCommitment::embed_commit(
+                &LockscriptContainer {
+                    script: lockscript,
+                    pubkey: pubkey.key,
+                    tag,
+                },
+                &msg,
+            )
+            .unwrap()
+            .into_inner();
+            let address = Address::from_script(commitment.as_inner(), Network::Bitcoin).unwrap();
+            assert_eq!(address, Address::p2shwsh(&tweaked, Network::Bitcoin));
+            assert!(address.to_string().starts_with('3'));
+            assert!(commitment.verify(&container, &msg).unwrap());
+        });
+    }
+
+    #[test]
+    fn test_invalid_proof_structure() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        let commitment = ScriptPubkeyCommitment::embed_commit(&container, &"test message").unwrap();
+        let proof = Proof {
+            pubkey,
+            script_info: ScriptInfo::Taproot(Some(sha256::Hash::hash(b"root"))),
+            nonce: None,
+        };
+        assert_eq!(
+            ScriptPubkeyContainer::reconstruct(&proof, &tag, commitment.as_inner()),
+            Err(Error::InvalidProofStructure)
+        );
+    }
+
+    #[test]
+    fn test_construct_from_scripts() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        gen_bitcoin_pubkeys(9, true).into_iter().for_each(|pubkey| {
+            // Exposed `<pubkey> OP_CHECKSIG` script is a P2PK output, so we
+            // use a bare multisig to get a plain script
+            let lockscript = LockScript::from(
+                Builder::new()
+                    .push_int(1)
+                    .push_key(&pubkey)
+                    .push_int(1)
+                    .push_opcode(OP_CHECKMULTISIG)
+                    .into_script(),
+            );
+            let redeem_script = RedeemScript::from(lockscript.clone());
+            let witness_script = WitnessScript::from(lockscript.clone());
+            let nested_redeem_script = RedeemScript::from(
+                lockscript
+                    .gen_script_pubkey(Strategy::WitnessV0)
+                    .unwrap()
+                    .to_inner(),
+            );
+
+            vec![
+                (Strategy::Exposed, None, None, PlainScript),
+                (
+                    Strategy::LegacyHashed,
+                    Some(&redeem_script),
+                    None,
+                    ScriptHash,
+                ),
+                (
+                    Strategy::WitnessV0,
+                    None,
+                    Some(&witness_script),
+                    WScriptHash,
+                ),
+                (
+                    Strategy::WitnessScriptHash,
+                    Some(&nested_redeem_script),
+                    Some(&witness_script),
+                    SHWScriptHash,
+                ),
+            ]
+            .into_iter()
+            .for_each(|(strategy, redeem_script, witness_script, composition)| {
+                let container = ScriptPubkeyContainer::construct_from_scripts(
+                    &tag,
+                    pubkey.key,
+                    &lockscript.gen_script_pubkey(strategy),
+                    redeem_script,
+                    witness_script,
+                )
+                .unwrap();
+                assert_eq!(container.scriptpubkey_composition, composition);
+                assert_eq!(
+                    container.script_info,
+                    ScriptInfo::LockScript(lockscript.clone())
+                );
+            });
+
+            // Missing or mismatching inner scripts must fail
+            assert_eq!(
+                ScriptPubkeyContainer::construct_from_scripts(
+                    &tag,
+                    pubkey.key,
+                    &lockscript.gen_script_pubkey(Strategy::WitnessV0).unwrap(),
+                    None,
+                    None,
+                ),
+                Err(Error::InvalidProofStructure)
+            );
+            assert_eq!(
+                ScriptPubkeyContainer::construct_from_scripts(
+                    &tag,
+                    pubkey.key,
+                    &lockscript
+                        .gen_script_pubkey(Strategy::LegacyHashed)
+                        .unwrap(),
+                    Some(&nested_redeem_script),
+                    None,
+                ),
+                Err(Error::InvalidProofStructure)
+            );
+
+            let container = ScriptPubkeyContainer::construct_from_scripts(
+                &tag,
+                pubkey.key,
+                &pubkey
+                    .gen_script_pubkey(Strategy::WitnessScriptHash)
+                    .unwrap(),
+                Some(&RedeemScript::from(
+                    pubkey
+                        .gen_script_pubkey(Strategy::WitnessV0)
+                        .unwrap()
+                        .to_inner(),
+                )),
+                None,
+            )
+            .unwrap();
+            assert_eq!(container.scriptpubkey_composition, SHWPubkeyHash);
+            assert_eq!(container.script_info, ScriptInfo::None);
+        });
+    }
+
+    #[test]
+    fn test_construct_from_scripts_key_mismatch() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let keys = gen_bitcoin_pubkeys(2, true);
+        let uncompressed = bitcoin::PublicKey {
+            compressed: false,
+            key: keys[1].key,
+        };
+        let construct = |script_pubkey: PubkeyScript, redeem_script: Option<&RedeemScript>| {
+            ScriptPubkeyContainer::construct_from_scripts(
+                &tag,
+                keys[0].key,
+                &script_pubkey,
+                redeem_script,
+                None,
+            )
+        };
+
+        vec![
+            keys[1].gen_script_pubkey(Strategy::Exposed).unwrap(),
+            keys[1].gen_script_pubkey(Strategy::LegacyHashed).unwrap(),
+            keys[1].gen_script_pubkey(Strategy::WitnessV0).unwrap(),
+            uncompressed
+                .gen_script_pubkey(Strategy::LegacyHashed)
+                .unwrap(),
+        ]
+        .into_iter()
+        .for_each(|script_pubkey| {
+            assert_eq!(
+                construct(script_pubkey, None),
+                Err(Error::DescriptorKeyMismatch)
+            )
+        });
+        assert_eq!(
+            construct(
+                keys[1]
+                    .gen_script_pubkey(Strategy::WitnessScriptHash)
+                    .unwrap(),
+                Some(&RedeemScript::from(
+                    keys[1]
+                        .gen_script_pubkey(Strategy::WitnessV0)
+                        .unwrap()
+                        .to_inner(),
+                )),
+            ),
+            Err(Error::DescriptorKeyMismatch)
+        );
+        assert_eq!(
+            commit_to_txout(
+                &tag,
+                keys[0].key,
+                &TxOut {
+                    value: 1000,
+                    script_pubkey: keys[1]
+                        .gen_script_pubkey(Strategy::WitnessV0)
+                        .unwrap()
+                        .into_inner(),
+                },
+                &"test message",
+            ),
+            Err(Error::DescriptorKeyMismatch)
+        );
+
+        // OP_RETURN outputs must push the same key, optionally after the
+        // prefix, and nothing else
+        let key = keys[0].key.serialize();
+        let other = keys[1].key.serialize();
+        vec![
+            Builder::gen_op_return(&other.to_vec()),
+            Builder::gen_op_return(b"LNPB").push_slice(&other),
+            Builder::gen_op_return(&key.to_vec()).push_slice(b"LNPB"),
+            Builder::gen_op_return(b"LNPB")
+                .push_slice(b"LNPB")
+                .push_slice(&key),
+            Builder::gen_op_return(b"data"),
+        ]
+        .into_iter()
+        .for_each(|builder| {
+            assert_eq!(
+                construct(PubkeyScript::from(builder.into_script()), None),
+                Err(Error::InvalidProofStructure)
+            )
+        });
+        assert_eq!(
+            commit_to_txout(
+                &tag,
+                keys[0].key,
+                &TxOut {
+                    value: 0,
+                    script_pubkey: Builder::gen_op_return(&other.to_vec()).into_script(),
+                },
+                &"test message",
+            ),
+            Err(Error::InvalidProofStructure)
+        );
+
+        // Matching keys are accepted with any of the serializations
+        assert_eq!(
+            construct(
+                keys[0].gen_script_pubkey(Strategy::WitnessV0).unwrap(),
+                None
+            )
+            .unwrap()
+            .scriptpubkey_composition,
+            ScriptPubkeyComposition::WPubkeyHash
+        );
+        assert_eq!(
+            construct(
+                PubkeyScript::from(
+                    Builder::gen_op_return(b"LNPB")
+                        .push_slice(&key)
+                        .into_script()
+                ),
+                None
+            ),
+            Ok(ScriptPubkeyContainer::construct(
+                &tag,
+                keys[0].key,
+                ScriptInfo::OpReturnPrefix(b"LNPB".to_vec()),
+                ScriptPubkeyComposition::OpReturn,
+            ))
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        use ScriptPubkeyCategory as Cat;
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        vec![
+            (PublicKey, Cat::P2PK),
+            (PubkeyHash, Cat::P2PKH),
+            (ScriptHash, Cat::P2SH),
+            (WPubkeyHash, Cat::P2WPKH),
+            (WScriptHash, Cat::P2WSH),
+            (SHWPubkeyHash, Cat::P2SH),
+            (SHWScriptHash, Cat::P2SH),
+            (TapRoot, Cat::P2TR),
+            (OpReturn, Cat::P2OR),
+            (PlainScript, Cat::P2S),
+        ]
+        .into_iter()
+        .for_each(|(composition, category)| {
+            let container =
+                ScriptPubkeyContainer::construct(&tag, pubkey, ScriptInfo::None, composition);
+            assert_eq!(container.classify(), category);
+        });
+    }
+
+    #[test]
+    fn test_non_standard_scripts() {
+        use bitcoin::blockdata::opcodes::all::{OP_NOP, OP_RETURN};
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let msg = "test message";
+        let p2pk = Builder::gen_p2pk(&bitcoin::PublicKey {
+            compressed: true,
+            key: pubkey,
+        });
+        let long = (0..MAX_SCRIPT_SIZE)
+            .fold(p2pk.clone(), |builder, _| builder.push_opcode(OP_NOP))
+            .into_script();
+        let unspendable = Builder::new()
+            .push_opcode(OP_RETURN)
+            .push_key(&bitcoin::PublicKey {
+                compressed: true,
+                key: pubkey,
+            })
+            .into_script();
+
+        vec![long, unspendable].into_iter().for_each(|script| {
+            vec![
+                ScriptPubkeyComposition::PlainScript,
+                ScriptPubkeyComposition::WScriptHash,
+            ]
+            .into_iter()
+            .for_each(|composition| {
+                let container = ScriptPubkeyContainer::construct(
+                    &tag,
+                    pubkey,
+                    ScriptInfo::LockScript(LockScript::from(script.clone())),
+                    composition,
+                );
+                assert_eq!(
+                    ScriptPubkeyCommitment::embed_commit(&container, &msg).err(),
+                    Some(Error::NonStandardScript)
+                );
+            });
+        });
+
+        // The same script without excessive opcodes is accepted
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::LockScript(LockScript::from(p2pk.into_script())),
+            ScriptPubkeyComposition::PlainScript,
+        );
+        assert!(ScriptPubkeyCommitment::embed_commit(&container, &msg).is_ok());
+    }
+
+    #[test]
+    fn test_commitment_proof() {
+        use crate::strict_encoding::{strict_decode, strict_encode};
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let digest = sha256::Hash::hash(b"client-side-validated data");
+        vec![
+            (ScriptPubkeyComposition::PublicKey, ScriptInfo::None),
+            (ScriptPubkeyComposition::WPubkeyHash, ScriptInfo::None),
+            (ScriptPubkeyComposition::TapRoot, ScriptInfo::Taproot(None)),
+        ]
+        .into_iter()
+        .for_each(|(composition, script_info)| {
+            let container =
+                ScriptPubkeyContainer::construct(&tag, pubkey, script_info, composition);
+            let proof = CommitmentProof::commit(container.clone(), digest).unwrap();
+            assert_eq!(
+                proof.script_pubkey,
+                ScriptPubkeyCommitment::embed_commit(&container, &digest.into_inner())
+                    .unwrap()
+                    .into_inner()
+            );
+            assert!(proof.verify().unwrap());
+
+            let decoded: CommitmentProof = strict_decode(&strict_encode(&proof).unwrap()).unwrap();
+            assert_eq!(decoded, proof);
+            assert!(decoded.verify().unwrap());
+
+            let mut wrong_digest = proof.clone();
+            wrong_digest.digest = sha256::Hash::hash(b"other data");
+            assert!(!wrong_digest.verify().unwrap());
+
+            let mut wrong_script = proof.clone();
+            wrong_script.script_pubkey = PubkeyScript::from(Script::new());
+            assert!(!wrong_script.verify().unwrap());
+        });
+    }
+
+    #[test]
+    fn test_negotiated_commitment() {
+        use crate::strict_encoding::{strict_decode, strict_encode};
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let msg = "test message";
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::Taproot(None),
+            ScriptPubkeyComposition::TapRoot,
+        );
+
+        let negotiated =
+            NegotiatedCommitment::with(3, CommitmentScheme::Taproot, container.clone()).unwrap();
+        assert_eq!(negotiated.vout(), 3);
+        assert_eq!(negotiated.scheme(), CommitmentScheme::Taproot);
+        assert_eq!(negotiated.container(), &container);
+        assert_eq!(
+            negotiated.embed_commit(&msg).unwrap(),
+            ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap()
+        );
+
+        let encoded = strict_encode(&negotiated).unwrap();
+        assert_eq!(&encoded[..5], &[3u8, 0, 0, 0, 2]);
+        let decoded: NegotiatedCommitment = strict_decode(&encoded).unwrap();
+        assert_eq!(decoded, negotiated);
+
+        assert_eq!(
+            NegotiatedCommitment::with(3, CommitmentScheme::PublicKey, container.clone()),
+            Err(Error::CommitmentSchemeMismatch)
+        );
+        // Scheme not matching the container is rejected during decoding
+        let mut mismatched = encoded.clone();
+        mismatched[4] = CommitmentScheme::LockScript as u8;
+        match strict_decode::<NegotiatedCommitment>(&mismatched) {
+            Err(crate::strict_encoding::Error::DataIntegrityError(_)) => {}
+            _ => panic!("Scheme mismatching the container must not be decoded"),
+        }
+        let mut unknown = encoded;
+        unknown[4] = 0xFF;
+        match strict_decode::<NegotiatedCommitment>(&unknown) {
+            Err(crate::strict_encoding::Error::EnumValueNotKnown(_, 0xFF)) => {}
+            _ => panic!("Unknown commitment scheme must not be decoded"),
+        }
+
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::OpReturn,
+        );
+        assert_eq!(
+            NegotiatedCommitment::with(1, CommitmentScheme::PublicKey, container.clone()),
+            Err(Error::CommitmentSchemeMismatch)
+        );
+        let negotiated =
+            NegotiatedCommitment::with(1, CommitmentScheme::OpReturn, container).unwrap();
+        let encoded = strict_encode(&negotiated).unwrap();
+        assert_eq!(&encoded[..5], &[1u8, 0, 0, 0, 3]);
+        let decoded: NegotiatedCommitment = strict_decode(&encoded).unwrap();
+        assert_eq!(decoded, negotiated);
+    }
+
+    #[test]
+    fn test_empty_script() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let msg = "test message";
+        vec![
+            ScriptPubkeyComposition::PlainScript,
+            ScriptPubkeyComposition::ScriptHash,
+            ScriptPubkeyComposition::WScriptHash,
+            ScriptPubkeyComposition::SHWScriptHash,
+        ]
+        .into_iter()
+        .for_each(|composition| {
+            let container = ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey,
+                ScriptInfo::LockScript(LockScript::from(Script::new())),
+                composition,
+            );
+            assert_eq!(
+                ScriptPubkeyCommitment::embed_commit(&container, &msg).err(),
+                Some(Error::EmptyScript)
+            );
+            assert_eq!(
+                ScriptPubkeyCommitment::embed_commit_consensus(&container, &msg).err(),
+                Some(Error::EmptyScript)
+            );
+        });
+    }
+
+    #[test]
+    fn test_category_scheme_names() {
+        use ScriptPubkeyCategory::*;
+        let categories = [P2S, P2PK, P2PKH, P2SH, P2OR, P2WPKH, P2WSH, P2TR];
+        for category in categories.iter() {
+            let name = category.to_string();
+            assert_eq!(name, category.name());
+            assert_eq!(name, name.to_lowercase());
+            assert_eq!(ScriptPubkeyCategory::from_str(&name), Ok(*category));
+            assert_eq!(
+                ScriptPubkeyCategory::from_str(&name.to_uppercase()),
+                Ok(*category)
+            );
+        }
+        assert_eq!(P2WPKH.to_string(), "p2wpkh");
+        assert_eq!(P2OR.to_string(), "opret");
+
+        let schemes = [
+            CommitmentScheme::PublicKey,
+            CommitmentScheme::LockScript,
+            CommitmentScheme::Taproot,
+            CommitmentScheme::OpReturn,
+        ];
+        for scheme in schemes.iter() {
+            let name = scheme.to_string();
+            assert_eq!(name, scheme.name());
+            assert_eq!(CommitmentScheme::from_str(&name), Ok(*scheme));
+        }
+        assert_eq!(CommitmentScheme::Taproot.to_string(), "taproot");
+        assert_eq!(CommitmentScheme::OpReturn.to_string(), "opret");
+        assert_eq!(CommitmentScheme::OpReturn as u8, 3);
+
+        assert_eq!(
+            ScriptPubkeyCategory::from_str("p2wpk"),
+            Err(NameParseError::UnknownCategory("p2wpk".to_string()))
+        );
+        assert_eq!(
+            ScriptPubkeyCategory::from_str("taproot"),
+            Err(NameParseError::UnknownCategory("taproot".to_string()))
+        );
+        assert_eq!(
+            CommitmentScheme::from_str("p2tr"),
+            Err(NameParseError::UnknownScheme("p2tr".to_string()))
+        );
+        assert_eq!(
+            CommitmentScheme::from_str(""),
+            Err(NameParseError::UnknownScheme("".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_supported_commitments() {
+        use CommitmentScheme::*;
+        use ScriptPubkeyCategory as Cat;
+        assert_eq!(supported_commitments(Cat::P2S), &[LockScript]);
+        assert_eq!(supported_commitments(Cat::P2PK), &[PublicKey]);
+        assert_eq!(supported_commitments(Cat::P2PKH), &[PublicKey]);
+        assert_eq!(supported_commitments(Cat::P2SH), &[PublicKey, LockScript]);
+        assert_eq!(supported_commitments(Cat::P2OR), &[OpReturn]);
+        assert_eq!(supported_commitments(Cat::P2WPKH), &[PublicKey]);
+        assert_eq!(supported_commitments(Cat::P2WSH), &[LockScript]);
+        assert_eq!(supported_commitments(Cat::P2TR), &[Taproot]);
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::OpReturn,
+        );
+        assert_eq!(container.commitment_scheme(), Some(OpReturn));
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::TapRoot,
+        );
+        assert_eq!(container.commitment_scheme(), None);
+    }
+
+    #[test]
+    fn test_hash_set_dedup() {
+        use std::collections::HashSet;
+        use ScriptPubkeyComposition::*;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_bitcoin_pubkeys(1, true)[0];
+        let lockscript = LockScript::from(Builder::gen_p2pk(&pubkey).into_script());
+        let containers: Vec<ScriptPubkeyContainer> = vec![
+            (ScriptInfo::None, PublicKey),
+            (ScriptInfo::None, PubkeyHash),
+            (ScriptInfo::None, WPubkeyHash),
+            (ScriptInfo::None, SHWPubkeyHash),
+            (ScriptInfo::None, OpReturn),
+            (ScriptInfo::LockScript(lockscript.clone()), ScriptHash),
+            (ScriptInfo::LockScript(lockscript.clone()), WScriptHash),
+            (ScriptInfo::LockScript(lockscript.clone()), SHWScriptHash),
+            (ScriptInfo::LockScript(lockscript), PlainScript),
+            (ScriptInfo::Taproot(None), TapRoot),
+            (
+                ScriptInfo::Taproot(Some(sha256::Hash::hash(b"root"))),
+                TapRoot,
+            ),
+        ]
+        .into_iter()
+        .map(|(script_info, composition)| {
+            ScriptPubkeyContainer::construct(&tag, pubkey.key, script_info, composition)
+        })
+        .collect();
+
+        let set: HashSet<_> = containers
+            .iter()
+            .chain(containers.iter())
+            .cloned()
+            .collect();
+        assert_eq!(set.len(), containers.len());
+        containers
+            .iter()
+            .for_each(|container| assert!(set.contains(container)));
+
+        let commitments: Vec<ScriptPubkeyCommitment> = containers
+            .iter()
+            .map(|container| ScriptPubkeyCommitment::embed_commit(container, &msg).unwrap())
+            .collect();
+        let set: HashSet<_> = commitments
+            .iter()
+            .chain(commitments.iter())
+            .cloned()
+            .collect();
+        assert_eq!(set.len(), commitments.len());
+        // Equal commitments produced independently are deduplicated
+        let commitment = ScriptPubkeyCommitment::embed_commit(&containers[2], &msg).unwrap();
+        assert!(set.contains(&commitment));
+
+        let taproot = |script_root| TaprootContainer {
+            script_root,
+            intermediate_key: pubkey.key,
+            tag,
+        };
+        let taproot: HashSet<_> = vec![
+            taproot(None),
+            taproot(None),
+            taproot(Some(sha256::Hash::hash(b"root"))),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(taproot.len(), 2);
+    }
+
+    #[test]
+    fn test_commit_to_descriptor() {
+        use miniscript::Miniscript;
+        use std::str::FromStr;
+        use ScriptPubkeyComposition::*;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let keys = gen_bitcoin_pubkeys(3, true);
+        let multi = Miniscript::<bitcoin::PublicKey>::from_str(&format!(
+            "multi(2,{},{})",
+            keys[0], keys[1]
+        ))
+        .unwrap();
+
+        vec![
+            (format!("wpkh({})", keys[0]), ScriptInfo::None, WPubkeyHash),
+            (
+                format!("sh(wpkh({}))", keys[0]),
+                ScriptInfo::None,
+                SHWPubkeyHash,
+            ),
+            (format!("pkh({})", keys[0]), ScriptInfo::None, PubkeyHash),
+            (
+                format!("wsh(multi(2,{},{}))", keys[0], keys[1]),
+                ScriptInfo::LockScript(LockScript::from(multi.encode())),
+                WScriptHash,
+            ),
+            (
+                format!("sh(wsh(multi(2,{},{})))", keys[0], keys[1]),
+                ScriptInfo::LockScript(LockScript::from(multi.encode())),
+                SHWScriptHash,
+            ),
+        ]
+        .into_iter()
+        .for_each(|(descriptor, script_info, composition)| {
+            let descriptor = Descriptor::<bitcoin::PublicKey>::from_str(&descriptor).unwrap();
+            let container =
+                ScriptPubkeyContainer::construct(&tag, keys[0].key, script_info, composition);
+            assert_eq!(
+                ScriptPubkeyContainer::construct_from_descriptor(&tag, &descriptor, keys[0].key)
+                    .unwrap(),
+                container
+            );
+            let commitment = commit_to_descriptor(&tag, &descriptor, keys[0].key, &msg).unwrap();
+            assert_eq!(
+                commitment,
+                ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap()
+            );
+            assert!(commitment.verify(&container, &msg).unwrap());
+        });
+
+        let descriptor =
+            Descriptor::<bitcoin::PublicKey>::from_str(&format!("wpkh({})", keys[0])).unwrap();
+        assert_eq!(
+            commit_to_descriptor(&tag, &descriptor, keys[1].key, &msg),
+            Err(Error::DescriptorKeyMismatch)
+        );
+        let descriptor = Descriptor::<bitcoin::PublicKey>::from_str(&format!(
+            "wsh(multi(2,{},{}))",
+            keys[0], keys[1]
+        ))
+        .unwrap();
+        assert_eq!(
+            commit_to_descriptor(&tag, &descriptor, keys[2].key, &msg),
+            Err(Error::LockscriptKeyNotFound)
+        );
+    }
+
+    #[test]
+    fn test_enumerate_commitment_sites() {
+        use bitcoin::blockdata::opcodes::all::{OP_PUSHNUM_2, OP_RETURN};
+        use CommitmentScheme::*;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_bitcoin_pubkeys(1, true)[0];
+        let taproot = ScriptPubkeyCommitment::embed_commit(
+            &ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey.key,
+                ScriptInfo::Taproot(None),
+                ScriptPubkeyComposition::TapRoot,
+            ),
+            &"test message",
+        )
+        .unwrap();
+        let scripts: Vec<Script> = vec![
+            pubkey
+                .gen_script_pubkey(Strategy::WitnessV0)
+                .unwrap()
+                .into_inner(),
+            LockScript::from(Builder::gen_p2pk(&pubkey).into_script())
+                .gen_script_pubkey(Strategy::WitnessV0)
+                .unwrap()
+                .into_inner(),
+            Builder::new()
+                .push_opcode(OP_RETURN)
+                .push_slice(&pubkey.key.serialize())
+                .into_script(),
+            taproot.as_inner().to_inner(),
+            // Witness program of unknown version can't host a commitment
+            Builder::new()
+                .push_opcode(OP_PUSHNUM_2)
+                .push_slice(&[0u8; 32])
+                .into_script(),
+            // Neither can OP_RETURN outputs with non-push opcodes or
+            // truncated pushes
+            Script::from(vec![0x6a, 0x51]),
+            Script::from(vec![0x6a, 0x05, 0x01, 0x02]),
+        ];
+        let tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: scripts
+                .into_iter()
+                .map(|script_pubkey| TxOut {
+                    value: 1000,
+                    script_pubkey,
+                })
+                .collect(),
+        };
+
+        assert_eq!(
+            enumerate_commitment_sites(&tx),
+            vec![(0, PublicKey), (1, LockScript), (2, OpReturn), (3, Taproot)]
+        );
+
+        let tx = Transaction {
+            output: vec![],
+            ..tx
+        };
+        assert_eq!(enumerate_commitment_sites(&tx), vec![]);
+    }
+
+    #[test]
+    fn test_check_single_commitment() {
+        use ScriptPubkeyComposition::*;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let other_tag = sha256::Hash::hash(b"OTHER_TAG");
+        let msg = "test message";
+        let pubkeys = gen_secp_pubkeys(2);
+        let op_return =
+            ScriptPubkeyContainer::construct(&tag, pubkeys[0], ScriptInfo::None, OpReturn);
+        let taproot =
+            ScriptPubkeyContainer::construct(&tag, pubkeys[1], ScriptInfo::Taproot(None), TapRoot);
+        let tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: vec![&op_return, &taproot]
+                .into_iter()
+                .map(|container| TxOut {
+                    value: 0,
+                    script_pubkey: ScriptPubkeyCommitment::embed_commit(container, &msg)
+                        .unwrap()
+                        .into_inner()
+                        .into_inner(),
+                })
+                .collect(),
+        };
+
+        let revealed = vec![(1, taproot.clone()), (0, op_return.clone())];
+        assert_eq!(
+            check_single_commitment(&tx, &tag, &revealed, false),
+            Err(Error::ConflictingCommitments(vec![0, 1]))
+        );
+        assert_eq!(
+            check_single_commitment(&tx, &tag, &revealed, true),
+            Ok(vec![0, 1])
+        );
+
+        // Commitments of other protocols do not conflict
+        let other = ScriptPubkeyContainer {
+            tag: other_tag,
+            ..op_return.clone()
+        };
+        assert_eq!(
+            check_single_commitment(&tx, &tag, &[(1, taproot.clone()), (0, other)], false),
+            Ok(vec![1])
+        );
+        assert_eq!(
+            check_single_commitment(&tx, &other_tag, &revealed, false),
+            Ok(vec![])
+        );
+
+        // Repeated reveals of the same output, reveals for absent outputs and
+        // containers which can't produce the output are not counted
+        let revealed = vec![
+            (1, taproot.clone()),
+            (1, taproot.clone()),
+            (2, op_return.clone()),
+            (1, op_return.clone()),
+        ];
+        assert_eq!(
+            check_single_commitment(&tx, &tag, &revealed, false),
+            Ok(vec![1])
+        );
+
+        // OP_RETURN outputs with non-push opcodes or truncated pushes are
+        // valid and can't be produced by the OP_RETURN container
+        let mut tx = tx;
+        tx.output.push(TxOut {
+            value: 0,
+            script_pubkey: Script::from(vec![0x6a, 0x51]),
+        });
+        tx.output.push(TxOut {
+            value: 0,
+            script_pubkey: Script::from(vec![0x6a, 0x05, 0x01, 0x02]),
+        });
+        let revealed = vec![(1, taproot), (2, op_return.clone()), (3, op_return)];
+        assert_eq!(
+            check_single_commitment(&tx, &tag, &revealed, false),
+            Ok(vec![1])
+        );
+    }
+
+    #[test]
+    fn test_commit_to_txout() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_bitcoin_pubkeys(1, true)[0];
+        let txout = TxOut {
+            value: 123_456,
+            script_pubkey: pubkey
+                .gen_script_pubkey(Strategy::WitnessV0)
+                .unwrap()
+                .into_inner(),
+        };
+
+        let (commitment, committed) = commit_to_txout(&tag, pubkey.key, &txout, &msg).unwrap();
+        assert_eq!(committed.value, txout.value);
+        assert_eq!(&committed.script_pubkey, commitment.as_inner().as_inner());
+        assert_ne!(committed.script_pubkey, txout.script_pubkey);
+        assert!(committed.script_pubkey.is_v0_p2wpkh());
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey.key,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        assert_eq!(
+            commitment,
+            ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap()
+        );
+        assert!(commitment.verify(&container, &msg).unwrap());
+
+        // P2WSH output requires witness script, which is not known
+        let p2wsh = TxOut {
+            value: 1000,
+            script_pubkey: LockScript::from(Builder::gen_p2pk(&pubkey).into_script())
+                .gen_script_pubkey(Strategy::WitnessV0)
+                .unwrap()
+                .into_inner(),
+        };
+        assert_eq!(
+            commit_to_txout(&tag, pubkey.key, &p2wsh, &msg),
+            Err(Error::InvalidProofStructure)
+        );
+
+        let op_return = TxOut {
+            value: 1000,
+            script_pubkey: Builder::gen_op_return(&pubkey.key.serialize().to_vec()).into_script(),
+        };
+        assert_eq!(
+            commit_to_txout(&tag, pubkey.key, &op_return, &msg),
+            Err(Error::NonZeroOpReturnValue(1000))
+        );
+        let (_, committed) = commit_to_txout(
+            &tag,
+            pubkey.key,
+            &TxOut {
+                value: 0,
+                ..op_return
+            },
+            &msg,
+        )
+        .unwrap();
+        assert_eq!(committed.value, 0);
+        assert!(committed.script_pubkey.is_op_return());
+
+        // Prefix push of the OP_RETURN output is kept in the commitment
+        let prefixed = TxOut {
+            value: 0,
+            script_pubkey: Builder::gen_op_return(b"LNPB")
+                .push_slice(&pubkey.key.serialize())
+                .into_script(),
+        };
+        let (commitment, committed) = commit_to_txout(&tag, pubkey.key, &prefixed, &msg).unwrap();
+        assert_eq!(committed.script_pubkey.len(), prefixed.script_pubkey.len());
+        assert_eq!(
+            committed.script_pubkey.as_bytes()[..6],
+            prefixed.script_pubkey.as_bytes()[..6]
+        );
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey.key,
+            ScriptInfo::OpReturnPrefix(b"LNPB".to_vec()),
+            ScriptPubkeyComposition::OpReturn,
+        );
+        assert!(commitment.verify(&container, &msg).unwrap());
+        assert!(!commitment.verify(&container, &"other message").unwrap());
+        let reconstructed =
+            ScriptPubkeyContainer::reconstruct(&container.to_proof(), &tag, commitment.as_inner())
+                .unwrap();
+        assert!(commitment.verify(&reconstructed, &msg).unwrap());
+    }
+
+    #[test]
+    fn test_commit_to_scheme() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_bitcoin_pubkeys(1, true)[0];
+
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey.key,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::PublicKey, &container, &msg).unwrap(),
+            ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap()
+        );
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::LockScript, &container, &msg),
+            Err(Error::CommitmentSchemeMismatch)
+        );
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::Taproot, &container, &msg),
+            Err(Error::CommitmentSchemeMismatch)
+        );
+
+        let lockscript = LockScript::from(
+            Builder::new()
+                .push_key(&pubkey)
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey.key,
+            ScriptInfo::LockScript(lockscript),
+            ScriptPubkeyComposition::WScriptHash,
+        );
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::LockScript, &container, &msg).unwrap(),
+            ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap()
+        );
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::PublicKey, &container, &msg),
+            Err(Error::CommitmentSchemeMismatch)
+        );
+
+        // Lockscript can't be committed to with P2WPKH output
+        let container = ScriptPubkeyContainer {
+            scriptpubkey_composition: ScriptPubkeyComposition::WPubkeyHash,
+            ..container
+        };
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::LockScript, &container, &msg),
+            Err(Error::CommitmentSchemeMismatch)
+        );
+
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey.key,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::OpReturn,
+        );
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::OpReturn, &container, &msg).unwrap(),
+            ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap()
+        );
+        assert_eq!(
+            commit_to_scheme(CommitmentScheme::PublicKey, &container, &msg),
+            Err(Error::CommitmentSchemeMismatch)
+        );
+        let container = ScriptPubkeyContainer {
+            script_info: ScriptInfo::OpReturnPrefix(b"LNPB".to_vec()),
+            ..container
+        };
+        assert!(
+            commit_to_scheme(CommitmentScheme::OpReturn, &container, &msg)
+                .unwrap()
+                .is_op_return()
+        );
+    }
+
+    #[test]
+    fn test_op_return_prefix() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let container = |prefix: &[u8]| {
+            ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey,
+                ScriptInfo::OpReturnPrefix(prefix.to_vec()),
+                ScriptPubkeyComposition::OpReturn,
+            )
+        };
+        let tweaked =
+            *LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey, tag), &msg).unwrap();
+
+        let magic = container(b"LNPB");
+        let commitment = ScriptPubkeyCommitment::embed_commit(&magic, &msg).unwrap();
+        assert!(commitment.is_op_return());
+        assert_eq!(commitment.len(), 40);
+        assert_eq!(commitment.as_bytes()[1..6], [4u8, b'L', b'N', b'P', b'B']);
+        assert_eq!(commitment.op_return_pubkey(), Some(tweaked));
+        assert_eq!(magic.commitment_scheme(), Some(CommitmentScheme::OpReturn));
+
+        let reconstructed =
+            ScriptPubkeyContainer::reconstruct(&magic.to_proof(), &tag, commitment.as_inner())
+                .unwrap();
+        assert_eq!(reconstructed, magic);
+        assert!(commitment.verify(&reconstructed, &msg).unwrap());
+        assert!(!commitment.verify(&reconstructed, &"other message").unwrap());
+        // Commitment without prefix does not verify against prefixed output
+        let unprefixed = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::OpReturn,
+        );
+        assert!(!commitment.verify(&unprefixed, &msg).unwrap());
+        assert_eq!(
+            ScriptPubkeyContainer::reconstruct(&unprefixed.to_proof(), &tag, commitment.as_inner()),
+            Err(Error::InvalidProofStructure)
+        );
+        assert_eq!(
+            ScriptPubkeyContainer::reconstruct(
+                &container(b"LNPX").to_proof(),
+                &tag,
+                commitment.as_inner()
+            ),
+            Err(Error::InvalidProofStructure)
+        );
+
+        // 47-byte prefix with the public key fits exactly into 80 bytes of data
+        let largest = container(&[0xA5u8; 47]);
+        let commitment = ScriptPubkeyCommitment::embed_commit(&largest, &msg).unwrap();
+        assert_eq!(commitment.len(), MAX_STANDARD_OP_RETURN_SIZE);
+        assert!(commitment.verify(&largest, &msg).unwrap());
+        assert_eq!(
+            ScriptPubkeyCommitment::embed_commit(&container(&[0xA5u8; 48]), &msg),
+            Err(Error::OpReturnTooLarge)
+        );
+
+        // Prefix can't be used with other output types
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::OpReturnPrefix(b"LNPB".to_vec()),
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        assert_eq!(container.commitment_scheme(), None);
+        assert_eq!(
+            ScriptPubkeyCommitment::embed_commit(&container, &msg),
+            Err(Error::InvalidProofStructure)
+        );
+    }
+
+    #[test]
+    fn test_op_return_roundtrip() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
+            let container = ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey,
+                ScriptInfo::None,
+                ScriptPubkeyComposition::OpReturn,
+            );
+            let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+            assert!(commitment.is_op_return());
+            assert_eq!(commitment.len(), 35);
+
+            let tweaked =
+                *LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey, tag), &msg).unwrap();
+            assert_eq!(commitment.op_return_pubkey(), Some(tweaked));
+
+            let reconstructed = ScriptPubkeyContainer::reconstruct(
+                &container.to_proof(),
+                &tag,
+                commitment.as_inner(),
+            )
+            .unwrap();
+            assert_eq!(reconstructed, container);
+            assert!(commitment.verify(&reconstructed, &msg).unwrap());
+        });
+
+        // OP_RETURN with data other than a public key can't be a commitment
+        let host = PubkeyScript::from(Builder::gen_op_return(&[0u8; 20]).into_script());
+        let proof = Proof {
+            pubkey: gen_secp_pubkeys(1)[0],
+            script_info: ScriptInfo::None,
+            nonce: None,
+        };
+        assert_eq!(
+            ScriptPubkeyContainer::reconstruct(&proof, &tag, &host),
+            Err(Error::InvalidProofStructure)
+        );
+        assert_eq!(
+            ScriptPubkeyCommitment::from_inner(host).op_return_pubkey(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_commitment_strict_encoding() {
+        use crate::strict_encoding::{strict_decode, strict_encode};
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        gen_messages().into_iter().for_each(|msg| {
+            gen_secp_pubkeys(3).into_iter().for_each(|pubkey| {
+                vec![
+                    (PublicKey, ScriptInfo::None),
+                    (PubkeyHash, ScriptInfo::None),
+                    (WPubkeyHash, ScriptInfo::None),
+                    (SHWPubkeyHash, ScriptInfo::None),
+                    (OpReturn, ScriptInfo::None),
+                    (TapRoot, ScriptInfo::Taproot(Some(sha256::Hash::hash(&msg)))),
+                ]
+                .into_iter()
+                .for_each(|(composition, script_info)| {
+                    let container =
+                        ScriptPubkeyContainer::construct(&tag, pubkey, script_info, composition);
+                    let commitment =
+                        ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+                    let encoded = strict_encode(&commitment).unwrap();
+                    assert_eq!(encoded[2..], commitment.as_bytes()[..]);
+                    let decoded: ScriptPubkeyCommitment = strict_decode(&encoded).unwrap();
+                    assert_eq!(decoded, commitment);
+                });
+            });
+        });
+    }
+
+    #[test]
+    fn test_container_strict_encoding() {
+        use crate::strict_encoding::{self, strict_decode, strict_encode};
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let lockscript = LockScript::from(
+            Builder::new()
+                .push_key(&gen_bitcoin_pubkeys(1, true)[0])
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        vec![
+            (PublicKey, ScriptInfo::None),
+            (PubkeyHash, ScriptInfo::None),
+            (ScriptHash, ScriptInfo::LockScript(lockscript.clone())),
+            (WPubkeyHash, ScriptInfo::None),
+            (WScriptHash, ScriptInfo::LockScript(lockscript.clone())),
+            (SHWPubkeyHash, ScriptInfo::None),
+            (SHWScriptHash, ScriptInfo::LockScript(lockscript.clone())),
+            (
+                TapRoot,
+                ScriptInfo::Taproot(Some(sha256::Hash::hash(b"root"))),
+            ),
+            (OpReturn, ScriptInfo::None),
+            (OpReturn, ScriptInfo::OpReturnPrefix(b"LNPB".to_vec())),
+            (OpReturn, ScriptInfo::OpReturnPrefix(vec![])),
+            (PlainScript, ScriptInfo::LockScript(lockscript.clone())),
+        ]
+        .into_iter()
+        .for_each(|(composition, script_info)| {
+            let container =
+                ScriptPubkeyContainer::construct(&tag, pubkey, script_info, composition);
+            let encoded = strict_encode(&container).unwrap();
+            let decoded: ScriptPubkeyContainer = strict_decode(&encoded).unwrap();
+            assert_eq!(decoded, container);
+        });
+
+        let container = ScriptPubkeyContainer::construct(&tag, pubkey, ScriptInfo::None, PublicKey);
+        let mut encoded = strict_encode(&container).unwrap();
+        // Composition byte follows 33-byte pubkey and 1-byte script info tag
+        encoded[34] = 0xFF;
+        match strict_decode::<ScriptPubkeyContainer>(&encoded) {
+            Err(strict_encoding::Error::EnumValueNotKnown(name, 0xFF)) => {
+                assert_eq!(name, "ScriptPubkeyComposition")
+            }
+            _ => panic!("Unknown scriptPubkey composition must not be decoded"),
+        }
+    }
+
+    #[test]
+    fn test_commit_batch() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_bitcoin_pubkeys(1, true)[0];
+        let lockscript = LockScript::from(
+            Builder::new()
+                .push_key(&pubkey)
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        let containers = vec![
+            ScriptPubkeyContainer::construct(&tag, pubkey.key, ScriptInfo::None, WPubkeyHash),
+            ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey.key,
+                ScriptInfo::LockScript(lockscript),
+                WScriptHash,
+            ),
+            ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey.key,
+                ScriptInfo::Taproot(Some(sha256::Hash::hash(b"root"))),
+                TapRoot,
+            ),
+            // Inconsistent container must fail without affecting the others
+            ScriptPubkeyContainer::construct(&tag, pubkey.key, ScriptInfo::None, WScriptHash),
+        ];
+
+        let results = ScriptPubkeyCommitment::commit_batch(&containers, &msg);
+        assert_eq!(results.len(), containers.len());
+        results
+            .iter()
+            .zip(containers.iter())
+            .take(3)
+            .for_each(|(result, container)| {
+                let commitment = result.as_ref().unwrap();
+                assert_eq!(
+                    *commitment,
+                    ScriptPubkeyCommitment::embed_commit(container, &msg).unwrap()
+                );
+                assert!(commitment.verify(container, &msg).unwrap());
+            });
+        assert_eq!(results[3], Err(Error::InvalidProofStructure));
+    }
+
+    #[test]
+    fn test_verify_against_tx() {
+        use bitcoin::{OutPoint, TxIn, TxOut};
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkeys = gen_secp_pubkeys(2);
+
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkeys[0],
+            ScriptInfo::None,
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+        let tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::default(),
+                script_sig: Default::default(),
+                sequence: 0xFFFFFFFF,
+                witness: vec![],
+            }],
+            output: vec![
+                TxOut {
+                    value: 1000,
+                    script_pubkey: pubkeys[1]
+                        .gen_script_pubkey(Strategy::WitnessV0)
+                        .unwrap()
+                        .into_inner(),
+                },
+                TxOut {
+                    value: 2000,
+                    script_pubkey: commitment.as_inner().to_inner(),
+                },
+            ],
+        };
+        let proof = container.to_proof();
+
+        assert!(ScriptPubkeyCommitment::verify_against_tx(
+            &tx, 1, &proof, &tag, &msg
+        ));
+        assert!(!ScriptPubkeyCommitment::verify_against_tx(
+            &tx, 0, &proof, &tag, &msg
+        ));
+        assert!(!ScriptPubkeyCommitment::verify_against_tx(
+            &tx, 2, &proof, &tag, &msg
+        ));
+        assert!(!ScriptPubkeyCommitment::verify_against_tx(
+            &tx,
+            1,
+            &proof,
+            &tag,
+            &"other message"
+        ));
+
+        // Foreign OP_RETURN outputs with non-push opcodes or truncated pushes
+        // do not break verification
+        let mut tx = tx;
+        tx.output.push(TxOut {
+            value: 0,
+            script_pubkey: Script::from(vec![0x6a, 0x51]),
+        });
+        tx.output.push(TxOut {
+            value: 0,
+            script_pubkey: Script::from(vec![0x6a, 0x21, 0x02, 0x03]),
+        });
+        assert!(ScriptPubkeyCommitment::verify_against_tx(
+            &tx, 1, &proof, &tag, &msg
+        ));
+        let op_return_proof = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkeys[0],
+            ScriptInfo::None,
+            ScriptPubkeyComposition::OpReturn,
+        )
+        .to_proof();
+        for vout in 2..4 {
+            assert!(!ScriptPubkeyCommitment::verify_against_tx(
+                &tx, vout, &proof, &tag, &msg
+            ));
+            assert!(!ScriptPubkeyCommitment::verify_against_tx(
+                &tx,
+                vout,
+                &op_return_proof,
+                &tag,
+                &msg
+            ));
+        }
+    }
+
+    #[test]
+    fn test_taproot_scriptpubkey() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let roots = vec![None, Some(sha256::Hash::hash(b"script root"))];
+        gen_secp_pubkeys(9).into_iter().for_each(|pubkey| {
+            roots.iter().for_each(|script_root| {
+                let container = ScriptPubkeyContainer {
+                    pubkey,
+                    script_info: ScriptInfo::Taproot(*script_root),
+                    scriptpubkey_composition: ScriptPubkeyComposition::TapRoot,
+                    tag,
+                };
+                let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+                let script = commitment.as_inner();
+                assert!(script.is_witness_program());
+                assert_eq!(script.len(), 34);
+
+                let taproot = TaprootCommitment::embed_commit(
+                    &TaprootContainer {
+                        script_root: *script_root,
+                        intermediate_key: pubkey,
+                        tag,
+                    },
+                    &msg,
+                )
+                .unwrap();
+                assert_eq!(
+                    script.as_bytes()[2..],
+                    taproot.output_key().unwrap().serialize()[1..]
+                );
+                assert!(commitment.verify(&container, &msg).unwrap());
+            });
+        });
+    }
+
+    #[test]
+    fn test_verify_script_pubkey() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let container = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::None,
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        let commitment = ScriptPubkeyCommitment::embed_commit(&container, &msg).unwrap();
+        assert!(commitment
+            .verify_script_pubkey(&container, &msg, commitment.as_inner())
+            .unwrap());
+        assert!(!commitment
+            .verify_script_pubkey(&container, &"other message", commitment.as_inner())
+            .unwrap());
+
+        // The message verifies, but the on-chain script is a different one
+        let other = ScriptPubkeyCommitment::embed_commit(
+            &ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey,
+                ScriptInfo::None,
+                ScriptPubkeyComposition::PubkeyHash,
+            ),
+            &msg,
+        )
+        .unwrap();
+        assert!(commitment.verify(&container, &msg).unwrap());
+        assert!(!commitment
+            .verify_script_pubkey(&container, &msg, other.as_inner())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_p2wpkh_commitment() {
+        use bitcoin::hashes::hash160;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        gen_secp_pubkeys(3).into_iter().for_each(|pubkey| {
+            let commitment = ScriptPubkeyCommitment::embed_commit(
+                &ScriptPubkeyContainer::construct(
+                    &tag,
+                    pubkey,
+                    ScriptInfo::None,
+                    ScriptPubkeyComposition::WPubkeyHash,
+                ),
+                &msg,
+            )
+            .unwrap();
+            let tweaked =
+                *LNPBP1Commitment::embed_commit(&LNPBP1Container::new(pubkey, tag), &msg).unwrap();
+            let mut expected = vec![0x00u8, 0x14];
+            expected.extend(&hash160::Hash::hash(&tweaked.serialize())[..]);
+            assert_eq!(commitment.as_bytes(), &expected[..]);
+        });
+    }
+
+    #[test]
+    fn test_matches_script() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let commit = |composition| {
+            ScriptPubkeyCommitment::embed_commit(
+                &ScriptPubkeyContainer::construct(&tag, pubkey, ScriptInfo::None, composition),
+                &msg,
+            )
+            .unwrap()
+        };
+
+        let commitment = commit(WPubkeyHash);
+        assert!(commitment.matches_script(commitment.as_inner()));
+        assert!(commitment.matches_script(&PubkeyScript::from(commitment.as_inner().to_inner())));
+        assert!(!commitment.matches_script(commit(PubkeyHash).as_inner()));
+        // Untweaked key does not match the commitment
+        let untweaked = pubkey.gen_script_pubkey(Strategy::WitnessV0).unwrap();
+        assert!(!commitment.matches_script(&untweaked));
+        assert!(!commitment.matches_script(&PubkeyScript::default()));
+    }
+
+    #[test]
+    fn test_matches_category() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let container = |composition| {
+            ScriptPubkeyContainer::construct(&tag, pubkey, ScriptInfo::None, composition)
+        };
+
+        let commitment = ScriptPubkeyCommitment::embed_commit(&container(PublicKey), &msg).unwrap();
+        for composition in &[UncompressedPublicKey, PublicKey] {
+            assert!(commitment.matches_category(&container(composition.clone())));
+        }
+        assert!(commitment.verify(&container(PublicKey), &msg).unwrap());
+        for composition in &[PubkeyHash, WPubkeyHash, SHWPubkeyHash, OpReturn, TapRoot] {
+            let mismatched = container(composition.clone());
+            assert!(!commitment.matches_category(&mismatched));
+            assert!(!commitment.verify(&mismatched, &msg).unwrap());
+        }
+
+        // Bare multisig is revealed by the plain script container only
+        let lockscript = LockScript::from(
+            Builder::new()
+                .push_int(1)
+                .push_slice(&pubkey.serialize())
+                .push_int(1)
+                .push_opcode(OP_CHECKMULTISIG)
+                .into_script(),
+        );
+        let plain = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::LockScript(lockscript),
+            PlainScript,
+        );
+        let commitment = ScriptPubkeyCommitment::embed_commit(&plain, &msg).unwrap();
+        assert!(commitment.matches_category(&plain));
+        assert!(commitment.verify(&plain, &msg).unwrap());
+        assert!(!commitment.matches_category(&container(WScriptHash)));
+
+        // Plain script container can't reveal commitments of other categories
+        let p2wpkh = ScriptPubkeyCommitment::embed_commit(&container(WPubkeyHash), &msg).unwrap();
+        assert!(!p2wpkh.matches_category(&plain));
+        assert!(!p2wpkh.verify(&plain, &msg).unwrap());
+
+        // Bare script looking like P2PK output is classified as P2PK, so the
+        // plain script container does not match it
+        let p2pk_like = ScriptPubkeyContainer::construct(
+            &tag,
+            pubkey,
+            ScriptInfo::LockScript(LockScript::from(
+                Builder::new()
+                    .push_slice(&pubkey.serialize())
+                    .push_opcode(OP_CHECKSIG)
+                    .into_script(),
+            )),
+            PlainScript,
+        );
+        let commitment = ScriptPubkeyCommitment::embed_commit(&p2pk_like, &msg).unwrap();
+        assert_eq!(
+            ScriptPubkeyCategory::from(
+                &ScriptPubkeyDescriptor::try_from(commitment.as_inner()).unwrap()
+            ),
+            ScriptPubkeyCategory::P2PK
+        );
+        assert!(!commitment.matches_category(&p2pk_like));
+        assert!(commitment.matches_category(&container(PublicKey)));
+
+        // Witness program of unknown version can't be classified
+        let unknown = ScriptPubkeyCommitment::from_inner(PubkeyScript::from(
+            Builder::new()
+                .push_opcode(bitcoin::blockdata::opcodes::all::OP_PUSHNUM_2)
+                .push_slice(&[0u8; 32])
+                .into_script(),
+        ));
+        assert!(unknown.matches_category(&plain));
+        assert!(!unknown.matches_category(&container(WPubkeyHash)));
+
+        // OP_RETURN outputs with non-push opcodes or truncated pushes can't
+        // be produced by any container
+        for bytes in vec![vec![0x6a, 0x51], vec![0x6a, 0x21, 0x02, 0x03]] {
+            let malformed =
+                ScriptPubkeyCommitment::from_inner(PubkeyScript::from(Script::from(bytes)));
+            for candidate in &[plain.clone(), container(OpReturn), container(PublicKey)] {
+                assert!(!malformed.matches_category(candidate));
+                assert!(!malformed.verify(candidate, &msg).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_embed_commit_with_lockscript() {
+        use ScriptPubkeyComposition as Comp;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let lockscript = LockScript::from(
+            Builder::new()
+                .push_slice(&pubkey.serialize())
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        let container = |composition| {
+            ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey,
+                ScriptInfo::LockScript(lockscript.clone()),
+                composition,
+            )
+        };
+
+        let (commitment, witness_script) = ScriptPubkeyCommitment::embed_commit_with_lockscript(
+            &container(Comp::WScriptHash),
+            &msg,
+        )
+        .unwrap();
+        let witness_script = witness_script.unwrap();
+        assert_ne!(witness_script, lockscript);
+        assert_eq!(
+            commitment.as_inner().as_inner(),
+            &Builder::gen_v0_p2wsh(&bitcoin::WScriptHash::hash(witness_script.as_bytes()))
+                .into_script()
+        );
+        assert_eq!(
+            commitment,
+            ScriptPubkeyCommitment::embed_commit(&container(Comp::WScriptHash), &msg).unwrap()
+        );
+
+        let (commitment, redeem_script) = ScriptPubkeyCommitment::embed_commit_with_lockscript(
+            &container(Comp::ScriptHash),
+            &msg,
+        )
+        .unwrap();
+        let redeem_script = redeem_script.unwrap();
+        assert_eq!(redeem_script, witness_script);
+        assert_eq!(
+            commitment.as_inner().as_inner(),
+            &Builder::gen_p2sh(&bitcoin::ScriptHash::hash(redeem_script.as_bytes())).into_script()
+        );
+
+        let (_, no_script) = ScriptPubkeyCommitment::embed_commit_with_lockscript(
+            &ScriptPubkeyContainer::construct(&tag, pubkey, ScriptInfo::None, Comp::WPubkeyHash),
+            &msg,
+        )
+        .unwrap();
+        assert_eq!(no_script, None);
+    }
+
+    #[test]
+    fn test_witness_script_size() {
+        use bitcoin::blockdata::opcodes::all::OP_NOP;
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let pubkey = gen_secp_pubkeys(1)[0];
+        let msg = "test message";
+        let p2pk = Builder::gen_p2pk(&bitcoin::PublicKey {
+            compressed: true,
+            key: pubkey,
+        });
+        let container = |size: usize, composition| {
+            let script = (p2pk.clone().into_script().len()..size)
+                .fold(p2pk.clone(), |builder, _| builder.push_opcode(OP_NOP))
+                .into_script();
+            assert_eq!(script.len(), size);
+            ScriptPubkeyContainer::construct(
+                &tag,
+                pubkey,
+                ScriptInfo::LockScript(LockScript::from(script)),
+                composition,
+            )
+        };
+
+        vec![
+            ScriptPubkeyComposition::WScriptHash,
+            ScriptPubkeyComposition::SHWScriptHash,
+        ]
+        .into_iter()
+        .for_each(|composition| {
+            let standard = container(MAX_STANDARD_WITNESS_SCRIPT_SIZE, composition.clone());
+            let commitment = ScriptPubkeyCommitment::embed_commit(&standard, &msg).unwrap();
+            assert!(commitment.verify(&standard, &msg).unwrap());
+
+            let large = container(MAX_STANDARD_WITNESS_SCRIPT_SIZE + 1, composition.clone());
+            assert_eq!(
+                ScriptPubkeyCommitment::embed_commit(&large, &msg).err(),
+                Some(Error::ScriptTooLarge)
+            );
+            let commitment = ScriptPubkeyCommitment::embed_commit_consensus(&large, &msg).unwrap();
+            assert!(commitment.verify(&large, &msg).unwrap());
+
+            let max = container(MAX_SCRIPT_SIZE, composition.clone());
+            assert!(ScriptPubkeyCommitment::embed_commit_consensus(&max, &msg).is_ok());
+            let oversized = container(MAX_SCRIPT_SIZE + 1, composition);
+            assert_eq!(
+                ScriptPubkeyCommitment::embed_commit_consensus(&oversized, &msg).err(),
+                Some(Error::NonStandardScript)
+            );
+        });
+
+        // Bare scripts are not subject to the witness script limit
+        let bare = container(
+            MAX_STANDARD_WITNESS_SCRIPT_SIZE + 1,
+            ScriptPubkeyComposition::PlainScript,
+        );
+        assert!(ScriptPubkeyCommitment::embed_commit(&bare, &msg).is_ok());
+    }
+
+    #[test]
+    fn test_embed_commit_with_script() {
+        use ScriptPubkeyComposition::*;
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        gen_secp_pubkeys(3).into_iter().for_each(|pubkey| {
+            vec![PublicKey, PubkeyHash, WPubkeyHash, SHWPubkeyHash]
+                .into_iter()
+                .for_each(|composition| {
+                    let container = ScriptPubkeyContainer::construct(
+                        &tag,
+                        pubkey,
+                        ScriptInfo::None,
+                        composition,
+                    );
+                    let (commitment, script_pubkey) =
+                        ScriptPubkeyCommitment::embed_commit_with_script(&container, &msg).unwrap();
+                    assert_eq!(commitment.as_inner(), &script_pubkey);
+
+                    let recovered = ScriptPubkeyContainer::reconstruct(
+                        &container.to_proof(),
+                        &tag,
+                        &script_pubkey,
+                    )
+                    .unwrap();
+                    assert_eq!(
+                        ScriptPubkeyCommitment::embed_commit(&recovered, &msg)
+                            .unwrap()
+                            .into_inner(),
+                        script_pubkey
+                    );
+                });
+        });
+    }
+}
@@ -11,22 +11,100 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
-use bitcoin::hashes::sha256;
+use amplify::Wrapper;
+use bitcoin::blockdata::{opcodes::all::OP_RETURN, script::Builder};
+use bitcoin::hashes::{sha256, Hash, HashEngine};
 use bitcoin::secp256k1;
 
 use super::{Container, Error, LNPBP1Commitment, Proof, ScriptInfo};
 use crate::bp::dbc::LNPBP1Container;
+use crate::bp::tagged256::tagged256hash;
+use crate::bp::{taptweak, TapScript};
 use crate::commit_verify::EmbedCommitVerify;
 
+/// Tag used for computing BIP-341 script tree leaf hashes
+const TAPLEAF_TAG: &'static str = "TapLeaf";
+/// Tag used for computing BIP-341 script tree branch hashes
+const TAPBRANCH_TAG: &'static str = "TapBranch";
+
+/// Leaf version for the tapscript leafs defined by BIP-342
+pub const TAPSCRIPT_LEAF_VERSION: u8 = 0xC0;
+
+/// Computes BIP-341 hash of the script tree leaf
+pub(super) fn tapleaf_hash(leaf_version: u8, script: &TapScript) -> sha256::Hash {
+    let mut data = vec![leaf_version];
+    data.extend(bitcoin::consensus::serialize(script.as_inner()));
+    sha256::Hash::from_inner(tagged256hash(TAPLEAF_TAG, data).into_inner())
+}
+
+/// Computes BIP-341 hash of the script tree branch from the hashes of its two
+/// child nodes, which are lexicographically sorted before hashing
+pub(super) fn tapbranch_hash(a: &sha256::Hash, b: &sha256::Hash) -> sha256::Hash {
+    let (first, second) = if a[..] < b[..] { (a, b) } else { (b, a) };
+    let mut data = first[..].to_vec();
+    data.extend(&second[..]);
+    sha256::Hash::from_inner(tagged256hash(TAPBRANCH_TAG, data).into_inner())
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
 #[display_from(Debug)]
 pub struct TaprootContainer {
-    pub script_root: sha256::Hash,
+    /// Root of the script tree; `None` for key-path-only outputs
+    pub script_root: Option<sha256::Hash>,
     pub intermediate_key: secp256k1::PublicKey,
     /// Single SHA256 hash of the protocol-specific tag
     pub tag: sha256::Hash,
 }
 
+impl TaprootContainer {
+    /// Constructs taproot container, checking that the provided internal key
+    /// can be used as BIP-340 x-only key, i.e. that it is lifted from its
+    /// x coordinate into exactly the same point (with even Y coordinate).
+    /// Fails with [Error::InvalidTaprootInternalKey] otherwise.
+    pub fn with(
+        internal_key: secp256k1::PublicKey,
+        script_root: sha256::Hash,
+        protocol_tag: &sha256::Hash,
+    ) -> Result<Self, Error> {
+        Self::construct(internal_key, Some(script_root), protocol_tag)
+    }
+
+    /// Constructs container for the key-path-only taproot output, which has
+    /// no script tree, checking the internal key like [TaprootContainer::with]
+    pub fn key_path_only(
+        internal_key: secp256k1::PublicKey,
+        protocol_tag: &sha256::Hash,
+    ) -> Result<Self, Error> {
+        Self::construct(internal_key, None, protocol_tag)
+    }
+
+    /// Returns taproot output key for the container without any commitment,
+    /// i.e. the intermediate key tweaked only with the script root according
+    /// to BIP-341 (see [TaprootCommitment::output_key] for the output key
+    /// containing the commitment)
+    #[inline]
+    pub fn output_key(&self) -> Result<secp256k1::PublicKey, secp256k1::Error> {
+        taptweak(&self.intermediate_key, self.script_root.as_ref())
+    }
+
+    fn construct(
+        internal_key: secp256k1::PublicKey,
+        script_root: Option<sha256::Hash>,
+        protocol_tag: &sha256::Hash,
+    ) -> Result<Self, Error> {
+        let mut xonly = internal_key.serialize();
+        xonly[0] = 0x02;
+        match secp256k1::PublicKey::from_slice(&xonly) {
+            Ok(lifted) if lifted == internal_key => Ok(Self {
+                script_root,
+                intermediate_key: internal_key,
+                tag: protocol_tag.clone(),
+            }),
+            _ => Err(Error::InvalidTaprootInternalKey),
+        }
+    }
+}
+
 impl Container for TaprootContainer {
     /// Out supplement is a protocol-specific tag in its hashed form
     type Supplement = sha256::Hash;
@@ -54,6 +132,7 @@ impl Container for TaprootContainer {
             Proof {
                 pubkey: self.intermediate_key,
                 script_info: ScriptInfo::Taproot(self.script_root),
+                nonce: None,
             },
             self.tag,
         )
@@ -63,6 +142,7 @@ impl Container for TaprootContainer {
         Proof {
             pubkey: self.intermediate_key.clone(),
             script_info: ScriptInfo::Taproot(self.script_root.clone()),
+            nonce: None,
         }
     }
 
@@ -70,6 +150,7 @@ impl Container for TaprootContainer {
         Proof {
             pubkey: self.intermediate_key,
             script_info: ScriptInfo::Taproot(self.script_root),
+            nonce: None,
         }
     }
 }
@@ -77,10 +158,39 @@ impl Container for TaprootContainer {
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
 #[display_from(Debug)]
 pub struct TaprootCommitment {
-    pub script_root: sha256::Hash,
+    /// Root of the script tree; `None` for key-path-only outputs
+    pub script_root: Option<sha256::Hash>,
     pub intermediate_key_commitment: LNPBP1Commitment,
 }
 
+impl TaprootCommitment {
+    /// Returns taproot output key, i.e. intermediate key containing the
+    /// commitment tweaked with the script root according to BIP-341
+    pub fn output_key(&self) -> Result<secp256k1::PublicKey, secp256k1::Error> {
+        taptweak(
+            &*self.intermediate_key_commitment,
+            self.script_root.as_ref(),
+        )
+    }
+
+    /// Verifies that the taproot output key, as it is found in the witness
+    /// program of the transaction output, is produced by tweaking the
+    /// revealed container intermediate key with the commitment to the `msg`
+    /// and then with the script root. Since the witness program contains
+    /// x-only key, only x coordinates of the keys are compared.
+    pub fn verify_output_key<MSG>(
+        container: &TaprootContainer,
+        output_key: &secp256k1::PublicKey,
+        msg: &MSG,
+    ) -> Result<bool, Error>
+    where
+        MSG: AsRef<[u8]>,
+    {
+        let commitment = Self::embed_commit(container, msg)?;
+        Ok(commitment.output_key()?.serialize()[1..] == output_key.serialize()[1..])
+    }
+}
+
 impl<MSG> EmbedCommitVerify<MSG> for TaprootCommitment
 where
     MSG: AsRef<[u8]>,
@@ -90,10 +200,7 @@ where
 
     fn embed_commit(container: &Self::Container, msg: &MSG) -> Result<Self, Self::Error> {
         let cmt = LNPBP1Commitment::embed_commit(
-            &LNPBP1Container {
-                pubkey: container.intermediate_key.clone(),
-                tag: container.tag.clone(),
-            },
+            &LNPBP1Container::new(container.intermediate_key.clone(), container.tag.clone()),
             msg,
         )?;
         Ok(Self {
@@ -102,3 +209,291 @@ where
         })
     }
 }
+
+/// Container for the taproot script-path commitment, where the commitment is
+/// placed into a dedicated leaf of the script tree, leaving the internal key
+/// (and key-path spending) untouched
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct TapleafContainer {
+    pub internal_key: secp256k1::PublicKey,
+    /// Version of the leaf that will contain the commitment
+    pub leaf_version: u8,
+    /// Hashes of the sibling nodes on the path from the commitment leaf to
+    /// the root of the script tree; empty if the commitment leaf is the only
+    /// leaf of the tree
+    pub merkle_path: Vec<sha256::Hash>,
+    /// Single SHA256 hash of the protocol-specific tag
+    pub tag: sha256::Hash,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct TapleafCommitment {
+    /// Script of the leaf containing the commitment
+    pub leaf_script: TapScript,
+    /// Script tree root containing the commitment leaf
+    pub script_root: sha256::Hash,
+    /// Taproot output key tweaked with the `script_root`
+    pub output_key: secp256k1::PublicKey,
+}
+
+impl<MSG> EmbedCommitVerify<MSG> for TapleafCommitment
+where
+    MSG: AsRef<[u8]>,
+{
+    type Container = TapleafContainer;
+    type Error = Error;
+
+    /// Creates unspendable `OP_RETURN <SHA256(tag || msg)>` leaf script,
+    /// computes the script tree root from it and the provided merkle path,
+    /// and tweaks the internal key with the resulting root according to
+    /// BIP-341
+    fn embed_commit(container: &Self::Container, msg: &MSG) -> Result<Self, Self::Error> {
+        let mut engine = sha256::Hash::engine();
+        engine.input(&container.tag[..]);
+        engine.input(msg.as_ref());
+        let digest = sha256::Hash::from_engine(engine);
+
+        let leaf_script = TapScript::from_inner(
+            Builder::new()
+                .push_opcode(OP_RETURN)
+                .push_slice(&digest[..])
+                .into_script(),
+        );
+        let script_root = container.merkle_path.iter().fold(
+            tapleaf_hash(container.leaf_version, &leaf_script),
+            |node, sibling| tapbranch_hash(&node, sibling),
+        );
+        let output_key = taptweak(&container.internal_key, Some(&script_root))?;
+
+        Ok(Self {
+            leaf_script,
+            script_root,
+            output_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::SECP256K1;
+    use bitcoin::hashes::{
+        hex::{FromHex, ToHex},
+        Hash,
+    };
+
+    #[test]
+    fn test_internal_key_validation() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let root = sha256::Hash::hash(b"script root");
+
+        // Secp256k1 generator point has even Y coordinate
+        let good = secp256k1::PublicKey::from_slice(
+            &Vec::<u8>::from_hex(
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let container = TaprootContainer::with(good, root, &tag).unwrap();
+        assert_eq!(container.intermediate_key, good);
+        assert_eq!(container.script_root, Some(root));
+
+        // Negated generator point has the same x coordinate, but odd Y
+        let mut bad = good.clone();
+        bad.negate_assign(&SECP256K1);
+        assert_eq!(
+            TaprootContainer::with(bad, root, &tag),
+            Err(Error::InvalidTaprootInternalKey)
+        );
+    }
+
+    fn xonly_key(hex: &str) -> secp256k1::PublicKey {
+        let mut data = vec![0x02];
+        data.extend(Vec::<u8>::from_hex(hex).unwrap());
+        secp256k1::PublicKey::from_slice(&data).unwrap()
+    }
+
+    #[test]
+    fn test_taptweak_bip341_vectors() {
+        // Test vectors from BIP-341 `wallet-test-vectors.json`
+        let internal_key =
+            xonly_key("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d");
+        assert_eq!(
+            taptweak(&internal_key, None).unwrap().serialize()[1..],
+            Vec::<u8>::from_hex("53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343")
+                .unwrap()[..]
+        );
+
+        let internal_key =
+            xonly_key("187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27");
+        let root = sha256::Hash::from_hex(
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21",
+        )
+        .unwrap();
+        assert_eq!(
+            taptweak(&internal_key, Some(&root)).unwrap().serialize()[1..],
+            Vec::<u8>::from_hex("147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3")
+                .unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_container_output_key() {
+        // Test vectors from BIP-341 `wallet-test-vectors.json`
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let container = TaprootContainer::key_path_only(
+            xonly_key("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d"),
+            &tag,
+        )
+        .unwrap();
+        assert_eq!(
+            container.output_key().unwrap().serialize()[1..],
+            Vec::<u8>::from_hex("53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343")
+                .unwrap()[..]
+        );
+
+        let root = sha256::Hash::from_hex(
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21",
+        )
+        .unwrap();
+        let container = TaprootContainer::with(
+            xonly_key("187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27"),
+            root,
+            &tag,
+        )
+        .unwrap();
+        let output_key = container.output_key().unwrap();
+        assert_eq!(
+            output_key.serialize()[1..],
+            Vec::<u8>::from_hex("147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3")
+                .unwrap()[..]
+        );
+
+        // Plain output key does not depend on the protocol tag and differs
+        // from the key containing the commitment
+        let other = TaprootContainer {
+            tag: sha256::Hash::hash(b"OTHER_TAG"),
+            ..container.clone()
+        };
+        assert_eq!(other.output_key().unwrap(), output_key);
+        let commitment = TaprootCommitment::embed_commit(&container, &"test message").unwrap();
+        assert_ne!(commitment.output_key().unwrap(), output_key);
+    }
+
+    #[test]
+    fn test_verify_output_key() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let internal_key =
+            xonly_key("187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27");
+        let root = sha256::Hash::from_hex(
+            "5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21",
+        )
+        .unwrap();
+        let container = TaprootContainer::with(internal_key, root, &tag).unwrap();
+        let commitment = TaprootCommitment::embed_commit(&container, &msg).unwrap();
+        let output_key = commitment.output_key().unwrap();
+
+        // On-chain key is x-only, so we lift it from its x coordinate
+        let onchain_key = xonly_key(&output_key.serialize()[1..].to_hex());
+        assert!(TaprootCommitment::verify_output_key(&container, &onchain_key, &msg).unwrap());
+        assert!(TaprootCommitment::verify_output_key(&container, &output_key, &msg).unwrap());
+        assert!(
+            !TaprootCommitment::verify_output_key(&container, &onchain_key, &"other message")
+                .unwrap()
+        );
+
+        // Output key without commitment must fail verification
+        let plain_key =
+            xonly_key("147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3");
+        assert!(!TaprootCommitment::verify_output_key(&container, &plain_key, &msg).unwrap());
+    }
+
+    #[test]
+    fn test_tapleaf_two_leaf_tree() {
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let msg = "test message";
+        let other_leaf = TapScript::from_inner(Builder::new().push_int(1).into_script());
+        let other_hash = tapleaf_hash(TAPSCRIPT_LEAF_VERSION, &other_leaf);
+
+        crate::bp::test::gen_secp_pubkeys(9)
+            .into_iter()
+            .for_each(|internal_key| {
+                let container = TapleafContainer {
+                    internal_key,
+                    leaf_version: TAPSCRIPT_LEAF_VERSION,
+                    merkle_path: vec![other_hash],
+                    tag,
+                };
+                let commitment = TapleafCommitment::embed_commit(&container, &msg).unwrap();
+
+                let leaf_hash = tapleaf_hash(TAPSCRIPT_LEAF_VERSION, &commitment.leaf_script);
+                assert_eq!(
+                    commitment.script_root,
+                    tapbranch_hash(&leaf_hash, &other_hash)
+                );
+                assert_eq!(
+                    commitment.script_root,
+                    tapbranch_hash(&other_hash, &leaf_hash)
+                );
+                assert_eq!(
+                    commitment.output_key,
+                    taptweak(&internal_key, Some(&commitment.script_root)).unwrap()
+                );
+
+                assert!(commitment.verify(&container, &msg).unwrap());
+                assert!(!commitment.verify(&container, &"other message").unwrap());
+                let mut wrong_tree = container.clone();
+                wrong_tree.merkle_path = vec![leaf_hash];
+                assert!(!commitment.verify(&wrong_tree, &msg).unwrap());
+            });
+    }
+
+    #[test]
+    fn test_key_path_only_encoding() {
+        use crate::strict_encoding::{strict_decode, strict_encode};
+
+        let tag = sha256::Hash::hash(b"TEST_TAG");
+        let internal_key =
+            xonly_key("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d");
+        let key_only = TaprootContainer::key_path_only(internal_key, &tag).unwrap();
+        assert_eq!(key_only.script_root, None);
+
+        let containers = vec![
+            key_only.clone(),
+            TaprootContainer::with(internal_key, sha256::Hash::default(), &tag).unwrap(),
+            TaprootContainer::with(internal_key, sha256::Hash::hash(b"script root"), &tag).unwrap(),
+        ];
+        containers.iter().for_each(|container| {
+            let encoded = strict_encode(&container.to_proof()).unwrap();
+            let proof: Proof = strict_decode(&encoded).unwrap();
+            assert_eq!(proof, container.to_proof());
+            assert_eq!(
+                TaprootContainer::reconstruct(&proof, &tag, &None).unwrap(),
+                *container
+            );
+        });
+
+        // Absent and all-zero roots are encoded differently
+        assert_ne!(
+            strict_encode(&containers[0].to_proof()).unwrap(),
+            strict_encode(&containers[1].to_proof()).unwrap()
+        );
+
+        // ... and produce different output keys
+        let msg = "test message";
+        let key_only_commitment = TaprootCommitment::embed_commit(&containers[0], &msg).unwrap();
+        let zero_root_commitment = TaprootCommitment::embed_commit(&containers[1], &msg).unwrap();
+        assert_eq!(
+            key_only_commitment.output_key().unwrap(),
+            taptweak(&*key_only_commitment.intermediate_key_commitment, None).unwrap()
+        );
+        assert_ne!(
+            key_only_commitment.output_key().unwrap(),
+            zero_root_commitment.output_key().unwrap()
+        );
+    }
+}
@@ -12,7 +12,8 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 use amplify::Wrapper;
-use bitcoin::{hashes::sha256, secp256k1, Transaction, TxOut};
+use bitcoin::hashes::{sha256, Hash};
+use bitcoin::{secp256k1, Transaction, TxOut};
 
 use super::{
     Container, Error, Proof, ScriptInfo, ScriptPubkeyComposition, TxoutCommitment, TxoutContainer,
@@ -28,16 +29,59 @@ pub struct TxContainer {
     pub tx: Transaction,
 }
 
+/// Computes index of the output which must carry LNPBP-3 commitment:
+/// `vout = (fee + protocol_factor) mod nouts`.
+///
+/// The rule takes no other transaction data (output values, scripts or the
+/// txid), so there are no ties to break: any two transactions with the same
+/// fee, protocol factor and number of outputs select the same index, and
+/// the index does not change when the commitment is embedded.
+///
+/// # Panics
+///
+/// Panics if the transaction has no outputs
 pub fn compute_lnpbp3_vout(tx: &Transaction, supplement: &TxSupplement) -> usize {
     compute_vout(supplement.fee, supplement.protocol_factor, tx)
 }
 
+/// Checks that the output with index `vout` is the one which must carry
+/// LNPBP-3 commitment according to [compute_lnpbp3_vout]. Returns `false` for
+/// transactions without outputs.
+pub fn verify_lnpbp3_vout(tx: &Transaction, supplement: &TxSupplement, vout: usize) -> bool {
+    !tx.output.is_empty() && compute_lnpbp3_vout(tx, supplement) == vout
+}
+
 fn compute_vout(fee: u64, entropy: u32, tx: &Transaction) -> usize {
     let nouts = tx.output.len() as u16;
     let vout = ((fee + (entropy as u64)) % (nouts as u64)) as u16;
     vout as usize
 }
 
+/// Deterministically selects transaction output which must carry the
+/// commitment using some protocol- or commitment-specific `entropy` (like
+/// the nonce shared between the committing party and verifiers). The index
+/// does not depend on the transaction contents except the number of outputs,
+/// so it remains the same once the commitment is embedded into the selected
+/// output:
+/// `vout = u64_le(SHA256(entropy)[..8]) mod nouts`
+///
+/// # Panics
+///
+/// Panics if the transaction has no outputs
+pub fn deterministic_output_index(tx: &Transaction, entropy: &[u8]) -> usize {
+    let nouts = tx.output.len() as u64;
+    let mut factor = [0u8; 8];
+    factor.copy_from_slice(&sha256::Hash::hash(entropy)[..8]);
+    (u64::from_le_bytes(factor) % nouts) as usize
+}
+
+/// Checks that the output with index `vout` is the one which must carry the
+/// commitment according to [deterministic_output_index]. Returns `false` for
+/// transactions without outputs.
+pub fn verify_output_index(tx: &Transaction, entropy: &[u8], vout: usize) -> bool {
+    !tx.output.is_empty() && deterministic_output_index(tx, entropy) == vout
+}
+
 fn get_mut_txout(fee: u64, entropy: u32, tx: &mut Transaction) -> &mut TxOut {
     let tx2 = tx.clone();
     &mut tx.output[compute_vout(fee, entropy, &tx2)]
@@ -134,6 +178,11 @@ where
         let mut tx = container.tx.clone();
         let fee = container.fee;
         let entropy = container.protocol_factor;
+        log_trace!(
+            "Committing into transaction output #{} out of {}",
+            compute_vout(fee, entropy, &tx),
+            tx.output.len()
+        );
 
         let txout_commitment =
             TxoutCommitment::embed_commit(&container.txout_container.clone(), msg)?;
@@ -149,8 +198,84 @@ mod test {
     use crate::bp::dbc::{ScriptInfo, ScriptPubkeyComposition, ScriptPubkeyContainer};
     use bitcoin::consensus::encode::deserialize;
     use bitcoin::hashes::hex::FromHex;
+    use bitcoin::Script;
     use std::str::FromStr;
 
+    fn gen_tx(nouts: usize) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![],
+            output: (0..nouts)
+                .map(|value| TxOut {
+                    value: value as u64,
+                    script_pubkey: Script::new(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_output_selection_stability() {
+        let tx = gen_tx(5);
+        let vout = deterministic_output_index(&tx, b"entropy");
+        assert!(vout < 5);
+        (0..10).for_each(|_| assert_eq!(deterministic_output_index(&tx, b"entropy"), vout));
+        assert!(verify_output_index(&tx, b"entropy", vout));
+        assert!(!verify_output_index(&tx, b"entropy", (vout + 1) % 5));
+
+        // Changing output contents must not affect selection
+        let mut changed = tx.clone();
+        changed.output[vout].value = 100500;
+        changed.output[vout].script_pubkey = Script::from(vec![0x6a]);
+        assert_eq!(deterministic_output_index(&changed, b"entropy"), vout);
+
+        assert_eq!(deterministic_output_index(&gen_tx(1), b"entropy"), 0);
+        assert!(!verify_output_index(&gen_tx(0), b"entropy", 0));
+    }
+
+    #[test]
+    fn test_lnpbp3_equal_fee_candidates() {
+        let supplement = TxSupplement {
+            protocol_factor: 7,
+            fee: 1000,
+            tag: sha256::Hash::hash(b"TEST_TAG"),
+        };
+        let first = gen_tx(4);
+        let mut second = gen_tx(4);
+        second.lock_time = 100500;
+        second.output.reverse();
+        second.output[0].script_pubkey = Script::from(vec![0x6a]);
+        assert_ne!(first.txid(), second.txid());
+
+        // (1000 + 7) mod 4
+        let vout = compute_lnpbp3_vout(&first, &supplement);
+        assert_eq!(vout, 3);
+        assert_eq!(compute_lnpbp3_vout(&second, &supplement), vout);
+        for tx in &[&first, &second] {
+            assert!(verify_lnpbp3_vout(tx, &supplement, vout));
+            (0..4)
+                .filter(|other| *other != vout)
+                .for_each(|other| assert!(!verify_lnpbp3_vout(tx, &supplement, other)));
+        }
+        assert!(!verify_lnpbp3_vout(&gen_tx(0), &supplement, 0));
+    }
+
+    #[test]
+    fn test_output_selection_distribution() {
+        for nouts in 2..6usize {
+            let tx = gen_tx(nouts);
+            let mut counts = vec![0usize; nouts];
+            (0u32..1200).for_each(|i| {
+                counts[deterministic_output_index(&tx, &i.to_le_bytes())] += 1;
+            });
+            let expected = 1200 / nouts;
+            counts.iter().for_each(|count| {
+                assert!(*count > expected * 3 / 4 && *count < expected * 5 / 4);
+            });
+        }
+    }
+
     #[test]
     fn test_ability_to_commit() {
         let tx = deserialize(Vec::from_hex(
@@ -88,6 +88,22 @@ wrapper!(
     derive = [PartialEq, Eq, Hash]
 );
 
+impl TxoutCommitment {
+    /// Builds transaction output for the `OP_RETURN` commitment, which is
+    /// always zero-valued. Fails with [Error::UnrecognizedScriptPubkey] if the
+    /// commitment is not an `OP_RETURN` script.
+    pub fn from_op_return(commitment: ScriptPubkeyCommitment) -> Result<Self, Error> {
+        if !commitment.is_op_return() {
+            Err(Error::UnrecognizedScriptPubkey)?
+        }
+        Ok(TxOut {
+            value: 0,
+            script_pubkey: commitment.into_inner().into_inner(),
+        }
+        .into())
+    }
+}
+
 impl<MSG> EmbedCommitVerify<MSG> for TxoutCommitment
 where
     MSG: AsRef<[u8]>,
@@ -95,7 +111,14 @@ where
     type Container = TxoutContainer;
     type Error = Error;
 
+    /// Fails with [Error::NonZeroOpReturnValue] for `OP_RETURN` containers
+    /// with non-zero value
     fn embed_commit(container: &Self::Container, msg: &MSG) -> Result<Self, Self::Error> {
+        if container.script_container.scriptpubkey_composition == ScriptPubkeyComposition::OpReturn
+            && container.value != 0
+        {
+            Err(Error::NonZeroOpReturnValue(container.value))?
+        }
         Ok(TxOut {
             value: container.value,
             script_pubkey: (**ScriptPubkeyCommitment::embed_commit(
@@ -106,4 +129,84 @@ where
         }
         .into())
     }
+
+    /// Fails with [Error::NonZeroOpReturnValue] if the output is an
+    /// `OP_RETURN` output with non-zero value, which burns the funds and is
+    /// non-standard
+    fn verify(&self, container: &Self::Container, msg: &MSG) -> Result<bool, Self::Error> {
+        if self.script_pubkey.is_op_return() && self.value != 0 {
+            Err(Error::NonZeroOpReturnValue(self.value))?
+        }
+        Ok(match Self::embed_commit(container, msg) {
+            Ok(commitment) => commitment == *self,
+            Err(_) => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::test::gen_secp_pubkeys;
+    use bitcoin::hashes::Hash;
+
+    fn container(value: u64) -> TxoutContainer {
+        TxoutContainer::construct(
+            &sha256::Hash::hash(b"TEST_TAG"),
+            value,
+            gen_secp_pubkeys(1)[0],
+            ScriptInfo::None,
+            ScriptPubkeyComposition::OpReturn,
+        )
+    }
+
+    #[test]
+    fn test_op_return_zero_value() {
+        let msg = "test message";
+        let container = container(0);
+        let commitment = TxoutCommitment::embed_commit(&container, &msg).unwrap();
+        assert_eq!(commitment.value, 0);
+        assert!(commitment.script_pubkey.is_op_return());
+        assert!(commitment.verify(&container, &msg).unwrap());
+        assert!(!commitment.verify(&container, &"other message").unwrap());
+
+        let script_commitment =
+            ScriptPubkeyCommitment::embed_commit(&container.script_container, &msg).unwrap();
+        assert_eq!(
+            TxoutCommitment::from_op_return(script_commitment).unwrap(),
+            commitment
+        );
+
+        let p2wpkh = ScriptPubkeyContainer::construct(
+            &sha256::Hash::hash(b"TEST_TAG"),
+            gen_secp_pubkeys(1)[0],
+            ScriptInfo::None,
+            ScriptPubkeyComposition::WPubkeyHash,
+        );
+        assert_eq!(
+            TxoutCommitment::from_op_return(
+                ScriptPubkeyCommitment::embed_commit(&p2wpkh, &msg).unwrap()
+            ),
+            Err(Error::UnrecognizedScriptPubkey)
+        );
+    }
+
+    #[test]
+    fn test_op_return_non_zero_value() {
+        let msg = "test message";
+        assert_eq!(
+            TxoutCommitment::embed_commit(&container(1000), &msg),
+            Err(Error::NonZeroOpReturnValue(1000))
+        );
+
+        let container = container(0);
+        let mut commitment = TxoutCommitment::embed_commit(&container, &msg)
+            .unwrap()
+            .into_inner();
+        commitment.value = 1;
+        assert_eq!(
+            TxoutCommitment::from_inner(commitment).verify(&container, &msg),
+            Err(Error::NonZeroOpReturnValue(1))
+        );
+    }
 }
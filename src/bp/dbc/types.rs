@@ -37,6 +37,9 @@ pub trait Container: Sized {
 pub struct Proof {
     pub pubkey: secp256k1::PublicKey,
     pub script_info: ScriptInfo,
+    /// Nonce mixed into the LNPBP-1 commitment to the `pubkey` (see
+    /// [super::LNPBP1Container::with_nonce]), if any
+    pub nonce: Option<[u8; 32]>,
 }
 
 impl From<secp256k1::PublicKey> for Proof {
@@ -44,6 +47,7 @@ impl From<secp256k1::PublicKey> for Proof {
         Self {
             pubkey,
             script_info: ScriptInfo::None,
+            nonce: None,
         }
     }
 }
@@ -54,7 +58,11 @@ impl From<secp256k1::PublicKey> for Proof {
 pub enum ScriptInfo {
     None,
     LockScript(LockScript),
-    Taproot(sha256::Hash),
+    /// Taproot script tree root; `None` for key-path-only outputs
+    Taproot(Option<sha256::Hash>),
+    /// Protocol-specific prefix which is pushed into `OP_RETURN` output
+    /// before the tweaked public key
+    OpReturnPrefix(Vec<u8>),
 }
 
 pub(super) mod strict_encoding {
@@ -70,6 +78,10 @@ pub(super) mod strict_encoding {
         None = 0,
         LockScript = 1,
         Taproot = 2,
+        /// Taproot output without script tree. Encoded with a distinct tag,
+        /// such that it can't be confused with an all-zero script root
+        TaprootKeyOnly = 3,
+        OpReturnPrefix = 4,
     }
     impl_enum_strict_encoding!(EncodingTag);
 
@@ -78,7 +90,13 @@ pub(super) mod strict_encoding {
             Ok(match self {
                 ScriptInfo::None => EncodingTag::None.strict_encode(&mut e)?,
                 ScriptInfo::LockScript(val) => strict_encode_list!(e; EncodingTag::LockScript, val),
-                ScriptInfo::Taproot(val) => strict_encode_list!(e; EncodingTag::Taproot, val),
+                ScriptInfo::Taproot(Some(val)) => {
+                    strict_encode_list!(e; EncodingTag::Taproot, val)
+                }
+                ScriptInfo::Taproot(None) => EncodingTag::TaprootKeyOnly.strict_encode(&mut e)?,
+                ScriptInfo::OpReturnPrefix(prefix) => {
+                    strict_encode_list!(e; EncodingTag::OpReturnPrefix, prefix)
+                }
             })
         }
     }
@@ -91,22 +109,51 @@ pub(super) mod strict_encoding {
                 EncodingTag::LockScript => {
                     ScriptInfo::LockScript(LockScript::strict_decode(&mut d)?)
                 }
-                EncodingTag::Taproot => ScriptInfo::Taproot(sha256::Hash::strict_decode(&mut d)?),
+                EncodingTag::Taproot => {
+                    ScriptInfo::Taproot(Some(sha256::Hash::strict_decode(&mut d)?))
+                }
+                EncodingTag::TaprootKeyOnly => ScriptInfo::Taproot(None),
+                EncodingTag::OpReturnPrefix => {
+                    ScriptInfo::OpReturnPrefix(Vec::<u8>::strict_decode(&mut d)?)
+                }
             })
         }
     }
 
+    /// Marker preceding the nonce in the encoded [Proof]. It is placed
+    /// instead of the script info tag, so proofs without nonce keep their
+    /// original encoding.
+    const NONCE_EXTENSION_TAG: u8 = 0xFF;
+
     impl StrictEncode for Proof {
         fn strict_encode<E: io::Write>(&self, mut e: E) -> Result<usize, strict_encoding::Error> {
-            Ok(strict_encode_list!(e; self.pubkey, self.script_info))
+            let mut len = self.pubkey.strict_encode(&mut e)?;
+            if let Some(nonce) = self.nonce {
+                len += NONCE_EXTENSION_TAG.strict_encode(&mut e)?;
+                e.write_all(&nonce)?;
+                len += nonce.len();
+            }
+            Ok(len + self.script_info.strict_encode(&mut e)?)
         }
     }
 
     impl StrictDecode for Proof {
         fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, strict_encoding::Error> {
+            let pubkey = secp256k1::PublicKey::strict_decode(&mut d)?;
+            let tag = u8::strict_decode(&mut d)?;
+            let (nonce, script_info) = if tag == NONCE_EXTENSION_TAG {
+                let mut nonce = [0u8; 32];
+                d.read_exact(&mut nonce)?;
+                (Some(nonce), ScriptInfo::strict_decode(&mut d)?)
+            } else {
+                // The tag we have read belongs to the script info
+                let script_info = ScriptInfo::strict_decode(io::Read::chain(&[tag][..], &mut d))?;
+                (None, script_info)
+            };
             Ok(Self {
-                pubkey: secp256k1::PublicKey::strict_decode(&mut d)?,
-                script_info: ScriptInfo::strict_decode(&mut d)?,
+                pubkey,
+                script_info,
+                nonce,
             })
         }
     }
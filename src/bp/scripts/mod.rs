@@ -14,10 +14,14 @@
 //mod error;
 mod pubkey_parser;
 mod script_pubkey;
+mod standardness;
+mod template;
 mod types;
 
 //pub use error::Error;
 pub use pubkey_parser::*;
 pub use script_pubkey::Error;
 pub use script_pubkey::*;
+pub use standardness::{StandardnessIssue, MAX_OPS_PER_SCRIPT, MAX_P2SH_SIGOPS};
+pub use template::{HashlockHash, ScriptTemplate, Timelock};
 pub use types::*;
@@ -12,12 +12,18 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 use super::LockScript;
+use bitcoin::blockdata::opcodes::all::{OP_CHECKSIG, OP_NUMEQUAL, OP_NUMEQUALVERIFY};
+use bitcoin::blockdata::script::{Builder, Instruction};
 use bitcoin::{secp256k1, PubkeyHash};
 use miniscript::miniscript::iter::PubkeyOrHash;
 use miniscript::{Miniscript, MiniscriptKey};
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
+/// `OP_CHECKSIGADD` opcode introduced by BIP-342 for tapscript multisigs
+/// (not known to the used version of rust-bitcoin)
+pub const OP_CHECKSIGADD: u8 = 0xba;
+
 /// Errors that may happen during LockScript parsing process
 #[derive(Debug, Display, Error)]
 #[display_from(Debug)]
@@ -27,6 +33,9 @@ pub enum PubkeyParseError {
 
     /// Miniscript-level error
     Miniscript(miniscript::Error),
+
+    /// Bitcoin script can't be parsed into a sequence of instructions
+    Script(bitcoin::blockdata::script::Error),
 }
 
 impl From<miniscript::Error> for PubkeyParseError {
@@ -53,48 +62,174 @@ impl LockScript {
 
     /// Returns tuple with two vectors: one for public keys and one for public
     /// key hashes present in the script; if any of the keys or hashes has more
-    /// than a single occurrence it returns all occurrences for each of them
+    /// than a single occurrence it returns all occurrences for each of them.
+    /// Tapscript multisigs are supported like in [LockScript::extract_pubkeys].
     pub fn extract_pubkeys_and_hashes(
         &self,
     ) -> Result<(Vec<secp256k1::PublicKey>, Vec<PubkeyHash>), PubkeyParseError> {
-        Miniscript::parse(&*self.clone())?
-            .iter_pubkeys_and_hashes()
-            .try_fold(
-                (Vec::<secp256k1::PublicKey>::new(), Vec::<PubkeyHash>::new()),
-                |(mut keys, mut hashes), item| {
-                    match item {
-                        PubkeyOrHash::HashedPubkey(hash) => hashes.push(hash.into()),
-                        PubkeyOrHash::PlainPubkey(key) => keys.push(key.key),
-                    }
-                    Ok((keys, hashes))
-                },
-            )
+        let miniscript = match Miniscript::parse(&*self.clone()) {
+            Ok(miniscript) => miniscript,
+            Err(err) => {
+                return self
+                    .extract_checksigadd_multisig()
+                    .map(|(_, keys)| (keys, vec![]))
+                    .ok_or_else(|| err.into())
+            }
+        };
+        miniscript.iter_pubkeys_and_hashes().try_fold(
+            (Vec::<secp256k1::PublicKey>::new(), Vec::<PubkeyHash>::new()),
+            |(mut keys, mut hashes), item| {
+                match item {
+                    PubkeyOrHash::HashedPubkey(hash) => hashes.push(hash.into()),
+                    PubkeyOrHash::PlainPubkey(key) => keys.push(key.key),
+                }
+                Ok((keys, hashes))
+            },
+        )
     }
 
     /// Returns all public keys found in the script; fails on public key hash.
     /// If the key present multiple times in the script it returns all
-    /// occurrences.
+    /// occurrences. Scripts which are not miniscripts are accepted if they
+    /// are tapscript multisigs (see [LockScript::extract_checksigadd_multisig]).
     pub fn extract_pubkeys(&self) -> Result<Vec<secp256k1::PublicKey>, PubkeyParseError> {
-        Miniscript::parse(&*self.clone())?
-            .iter_pubkeys_and_hashes()
-            .try_fold(
-                Vec::<secp256k1::PublicKey>::new(),
-                |mut keys, item| match item {
-                    PubkeyOrHash::HashedPubkey(hash) => Err(PubkeyParseError::PubkeyHash(hash)),
-                    PubkeyOrHash::PlainPubkey(key) => {
-                        keys.push(key.key);
-                        Ok(keys)
+        let miniscript = match Miniscript::parse(&*self.clone()) {
+            Ok(miniscript) => miniscript,
+            Err(err) => {
+                return self
+                    .extract_checksigadd_multisig()
+                    .map(|(_, keys)| keys)
+                    .ok_or_else(|| err.into())
+            }
+        };
+        miniscript.iter_pubkeys_and_hashes().try_fold(
+            Vec::<secp256k1::PublicKey>::new(),
+            |mut keys, item| match item {
+                PubkeyOrHash::HashedPubkey(hash) => Err(PubkeyParseError::PubkeyHash(hash)),
+                PubkeyOrHash::PlainPubkey(key) => {
+                    keys.push(key.key);
+                    Ok(keys)
+                }
+            },
+        )
+    }
+
+    /// Returns all public keys pushed into the script, in the order of their
+    /// appearance. Unlike [LockScript::extract_pubkeys] this does not require
+    /// the script to be a valid miniscript: it walks the raw script
+    /// instructions and collects every data push that parses as a valid
+    /// (compressed or uncompressed) public key, skipping other pushes. Fails
+    /// only if the script itself can't be parsed.
+    pub fn extract_pushed_pubkeys(&self) -> Result<Vec<bitcoin::PublicKey>, PubkeyParseError> {
+        self.iter(true)
+            .try_fold(vec![], |mut keys, instr| match instr {
+                Instruction::PushBytes(data) => {
+                    if let Ok(pubkey) = bitcoin::PublicKey::from_slice(data) {
+                        keys.push(pubkey);
                     }
-                },
-            )
+                    Ok(keys)
+                }
+                Instruction::Op(_) => Ok(keys),
+                Instruction::Error(err) => Err(PubkeyParseError::Script(err)),
+            })
+    }
+
+    /// Detects BIP-342 tapscript multisig of the form
+    /// `<key_1> OP_CHECKSIG <key_2> OP_CHECKSIGADD ... <key_n> OP_CHECKSIGADD <m> OP_NUMEQUAL`
+    /// (or `OP_NUMEQUALVERIFY`), where all keys are 32-byte x-only keys.
+    /// Returns the threshold `m` and the keys lifted to their even Y form,
+    /// or `None` if the script does not match the pattern.
+    pub fn extract_checksigadd_multisig(&self) -> Option<(usize, Vec<secp256k1::PublicKey>)> {
+        let instructions = self.iter(true).collect::<Vec<_>>();
+        if instructions.len() < 4 || instructions.len() % 2 != 0 {
+            return None;
+        }
+        let (key_part, tail) = instructions.split_at(instructions.len() - 2);
+
+        let threshold = match &tail[0] {
+            Instruction::Op(op) if (0x51..=0x60).contains(&op.into_u8()) => {
+                op.into_u8() as usize - 0x50
+            }
+            // Minimally-encoded script numbers above 16 and below 128
+            Instruction::PushBytes(data) if data.len() == 1 && data[0] < 0x80 => data[0] as usize,
+            _ => return None,
+        };
+        match &tail[1] {
+            Instruction::Op(op) if *op == OP_NUMEQUAL || *op == OP_NUMEQUALVERIFY => {}
+            _ => return None,
+        }
+
+        let keys = key_part
+            .chunks(2)
+            .enumerate()
+            .map(|(no, pair)| {
+                // The first key is checked with OP_CHECKSIG, the rest are
+                // checked with OP_CHECKSIGADD
+                let opcode = if no == 0 {
+                    OP_CHECKSIG.into_u8()
+                } else {
+                    OP_CHECKSIGADD
+                };
+                match (&pair[0], &pair[1]) {
+                    (Instruction::PushBytes(xonly), Instruction::Op(op))
+                        if xonly.len() == 32 && op.into_u8() == opcode =>
+                    {
+                        let mut key = [0x02u8; 33];
+                        key[1..].copy_from_slice(xonly);
+                        secp256k1::PublicKey::from_slice(&key).ok()
+                    }
+                    _ => None,
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if threshold == 0 || threshold > keys.len() {
+            return None;
+        }
+        Some((threshold, keys))
+    }
+
+    /// Replaces keys of the tapscript multisig (see
+    /// [LockScript::extract_checksigadd_multisig]) directly in the script
+    /// instructions, since such scripts are not miniscripts. Replacement keys
+    /// are pushed in x-only form; all other instructions are kept intact.
+    /// Returns `None` if the script is not a tapscript multisig.
+    fn replace_checksigadd_pubkeys(
+        &self,
+        processor: impl Fn(secp256k1::PublicKey) -> Option<secp256k1::PublicKey>,
+    ) -> Option<Self> {
+        self.extract_checksigadd_multisig()?;
+        let script = self
+            .iter(true)
+            .fold(Builder::new(), |builder, instr| match instr {
+                Instruction::PushBytes(xonly) if xonly.len() == 32 => {
+                    let mut key = [0x02u8; 33];
+                    key[1..].copy_from_slice(xonly);
+                    let key = secp256k1::PublicKey::from_slice(&key)
+                        .expect("keys are checked by extract_checksigadd_multisig");
+                    let key = processor(key).unwrap_or(key);
+                    builder.push_slice(&key.serialize()[1..])
+                }
+                Instruction::PushBytes(data) => builder.push_slice(data),
+                Instruction::Op(op) => builder.push_opcode(op),
+                Instruction::Error(_) => {
+                    unreachable!("script is parsed by extract_checksigadd_multisig")
+                }
+            })
+            .into_script();
+        Some(LockScript::from(script))
     }
 
     /// Replaces pubkeys using provided matching function; does not fail on
-    /// public key hashes.
+    /// public key hashes. Tapscript multisigs are supported like in
+    /// [LockScript::extract_pubkeys].
     pub fn replace_pubkeys(
         &self,
         processor: impl Fn(secp256k1::PublicKey) -> Option<secp256k1::PublicKey>,
     ) -> Result<Self, PubkeyParseError> {
+        if let Some(replaced) = self.replace_checksigadd_pubkeys(&processor) {
+            return Ok(replaced);
+        }
         let result = Miniscript::parse(&*self.clone())?.replace_pubkeys_and_hashes(
             &|item: PubkeyOrHash<bitcoin::PublicKey>| match item {
                 PubkeyOrHash::PlainPubkey(pubkey) => processor(pubkey.key).map(|key| {
@@ -110,12 +245,16 @@ impl LockScript {
     }
 
     /// Replaces public kes and public key hashes using provided matching
-    /// functions.
+    /// functions. Tapscript multisigs, which contain no key hashes, are
+    /// supported like in [LockScript::extract_pubkeys].
     pub fn replace_pubkeys_and_hashes(
         &self,
         key_processor: impl Fn(secp256k1::PublicKey) -> Option<secp256k1::PublicKey>,
         hash_processor: impl Fn(PubkeyHash) -> Option<PubkeyHash>,
     ) -> Result<Self, PubkeyParseError> {
+        if let Some(replaced) = self.replace_checksigadd_pubkeys(&key_processor) {
+            return Ok(replaced);
+        }
         let result = Miniscript::parse(&*self.clone())?.replace_pubkeys_and_hashes(
             &|item: PubkeyOrHash<bitcoin::PublicKey>| match item {
                 PubkeyOrHash::PlainPubkey(pubkey) => key_processor(pubkey.key).map(|key| {
@@ -138,7 +277,7 @@ pub(crate) mod test {
     use super::*;
     use crate::bp::test::*;
     use bitcoin::hashes::{hash160, sha256, Hash};
-    use bitcoin::{PubkeyHash, PublicKey};
+    use bitcoin::{PubkeyHash, PublicKey, Script};
     use std::collections::HashSet;
     use std::iter::FromIterator;
     use std::str::FromStr;
@@ -342,6 +481,141 @@ pub(crate) mod test {
         });
     }
 
+    #[test]
+    fn test_script_parse_pushed_pubkeys() {
+        use bitcoin::blockdata::opcodes::all::*;
+        use bitcoin::blockdata::script::Builder;
+
+        let compressed = gen_bitcoin_pubkeys(2, true);
+        let uncompressed = gen_bitcoin_pubkeys(1, false);
+        let script = LockScript::from(
+            Builder::new()
+                .push_key(&compressed[0])
+                .push_slice(&[0xDE, 0xAD, 0xBE, 0xEF])
+                .push_opcode(OP_DROP)
+                .push_key(&uncompressed[0])
+                // 33 bytes which are not a valid key
+                .push_slice(&[0x05u8; 33])
+                .push_int(2)
+                .push_key(&compressed[1])
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        assert_eq!(
+            script.extract_pushed_pubkeys().unwrap(),
+            vec![compressed[0], uncompressed[0], compressed[1]]
+        );
+        assert!(!script.extract_pushed_pubkeys().unwrap()[1].compressed);
+
+        no_keys_or_hashes_suite(|lockscript| {
+            assert_eq!(lockscript.extract_pushed_pubkeys().unwrap(), vec![]);
+        });
+
+        complex_suite(|lockscript, keys| {
+            let pushed: HashSet<secp256k1::PublicKey> = lockscript
+                .extract_pushed_pubkeys()
+                .unwrap()
+                .iter()
+                .map(|pk| pk.key)
+                .collect();
+            assert_eq!(pushed, HashSet::from_iter(keys));
+        });
+
+        // Truncated push must result in error
+        let script = LockScript::from(Script::from(vec![0x21u8, 0x02, 0x03]));
+        match script.extract_pushed_pubkeys() {
+            Err(PubkeyParseError::Script(_)) => (),
+            _ => panic!("truncated script must fail to parse"),
+        }
+    }
+
+    #[test]
+    fn test_script_parse_checksigadd_multisig() {
+        use bitcoin::blockdata::opcodes;
+        use bitcoin::blockdata::script::Builder;
+
+        // x-only keys are lifted into even Y form
+        let keys: Vec<secp256k1::PublicKey> = gen_secp_pubkeys(3)
+            .into_iter()
+            .map(|key| {
+                let mut even = key.serialize();
+                even[0] = 0x02;
+                secp256k1::PublicKey::from_slice(&even).unwrap()
+            })
+            .collect();
+        let tapscript =
+            |threshold: i64, first: u8| {
+                LockScript::from(
+                    keys.iter()
+                        .enumerate()
+                        .fold(Builder::new(), |builder, (no, key)| {
+                            builder.push_slice(&key.serialize()[1..]).push_opcode(
+                                opcodes::All::from(if no == 0 { first } else { OP_CHECKSIGADD }),
+                            )
+                        })
+                        .push_int(threshold)
+                        .push_opcode(OP_NUMEQUAL)
+                        .into_script(),
+                )
+            };
+
+        let lockscript = tapscript(2, OP_CHECKSIG.into_u8());
+        assert_eq!(
+            lockscript.extract_checksigadd_multisig(),
+            Some((2, keys.clone()))
+        );
+        assert_eq!(lockscript.extract_pubkeys().unwrap(), keys);
+        assert_eq!(
+            lockscript.extract_pubkeys_and_hashes().unwrap(),
+            (keys.clone(), vec![])
+        );
+        // Keys are replaced in place, keeping the script structure
+        assert_eq!(
+            lockscript.replace_pubkeys(|key| Some(key)).unwrap(),
+            lockscript
+        );
+        let replaced = lockscript
+            .replace_pubkeys_and_hashes(
+                |key| if key == keys[1] { Some(keys[0]) } else { None },
+                |_| panic!("tapscript multisig has no key hashes"),
+            )
+            .unwrap();
+        assert_eq!(
+            replaced.extract_checksigadd_multisig(),
+            Some((2, vec![keys[0], keys[0], keys[2]]))
+        );
+        assert_eq!(
+            lockscript.extract_pubkeyset().unwrap(),
+            HashSet::from_iter(keys.clone())
+        );
+
+        // Threshold exceeding number of keys
+        assert_eq!(
+            tapscript(4, OP_CHECKSIG.into_u8()).extract_checksigadd_multisig(),
+            None
+        );
+        // The first key must be checked with OP_CHECKSIG
+        let wrong = tapscript(2, OP_CHECKSIGADD);
+        assert_eq!(wrong.extract_checksigadd_multisig(), None);
+        match wrong.extract_pubkeys() {
+            Err(PubkeyParseError::Miniscript(_)) => (),
+            _ => panic!("non-miniscript script must fail to parse"),
+        }
+        // Legacy keys are not accepted
+        let legacy = LockScript::from(
+            Builder::new()
+                .push_key(&PublicKey {
+                    compressed: true,
+                    key: keys[0],
+                })
+                .push_opcode(OP_CHECKSIG)
+                .push_int(1)
+                .push_opcode(OP_NUMEQUAL)
+                .into_script(),
+        );
+        assert_eq!(legacy.extract_checksigadd_multisig(), None);
+    }
+
     #[test]
     fn test_script_parse_complex_script() {
         complex_suite(|lockscript, keys| {
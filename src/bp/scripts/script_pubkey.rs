@@ -18,7 +18,7 @@
 //! TxOut -> PubkeyScript -> Descriptor -> Structure -> Format
 //! ```
 
-use bitcoin::{blockdata::script::*, hash_types::*, hashes::Hash, secp256k1};
+use bitcoin::{blockdata::opcodes, blockdata::script::*, hash_types::*, hashes::Hash, secp256k1};
 use core::convert::TryFrom;
 
 use super::types::*;
@@ -118,15 +118,17 @@ pub enum Error {
     UnsupportedWitnessVersion,
 }
 
-impl TryFrom<PubkeyScript> for ScriptPubkeyDescriptor {
-    type Error = Error;
-    fn try_from(script_pubkey: PubkeyScript) -> Result<Self, Self::Error> {
+impl ScriptPubkeyDescriptor {
+    /// Detects standard `scriptPubkey` types without taking ownership of the
+    /// script; returns `Ok(None)` for non-standard scripts, including
+    /// `OP_RETURN` outputs which contain anything except data pushes
+    fn parse_standard(script_pubkey: &PubkeyScript) -> Result<Option<Self>, Error> {
         use bitcoin::blockdata::opcodes::all::*;
         use ScriptPubkeyDescriptor::*;
 
-        let script = &*script_pubkey;
+        let script = &**script_pubkey;
         let p = script.as_bytes();
-        Ok(match script {
+        Ok(Some(match script {
             s if s.is_p2pk() => {
                 let key = match p[0].into() {
                     OP_PUSHBYTES_65 => bitcoin::PublicKey::from_slice(&p[1..66]),
@@ -148,21 +150,48 @@ impl TryFrom<PubkeyScript> for ScriptPubkeyDescriptor {
             s if s.is_v0_p2wsh() => P2WSH(
                 WScriptHash::from_slice(&p[2..34]).expect("Reading hash from fixed slice failed"),
             ),
+            s if s.is_witness_program() && p[0] == OP_PUSHNUM_1.into_u8() && p.len() == 34 => {
+                // Taproot output keys are x-only, so we lift them to the
+                // even-Y point
+                let mut key = [0u8; 33];
+                key[0] = 0x02;
+                key[1..].copy_from_slice(&p[2..34]);
+                P2TR(secp256k1::PublicKey::from_slice(&key).map_err(|_| Error::InvalidKeyData)?)
+            }
             s if s.is_witness_program() => Err(Error::UnsupportedWitnessVersion)?,
-            s if s.is_op_return() => P2OR(
-                Script::from(p[1..].to_vec())
-                    .iter(false)
-                    .map(|instr| {
-                        if let Instruction::PushBytes(data) = instr {
-                            data.to_vec()
-                        } else {
-                            panic!("Rust bitcoin library broken in script parsing functionality")
-                        }
-                    })
-                    .collect(),
-            ),
-            _ => P2S(script_pubkey),
-        })
+            s if s.is_op_return() => {
+                let mut data = vec![];
+                for instr in Script::from(p[1..].to_vec()).iter(false) {
+                    match instr {
+                        Instruction::PushBytes(push) => data.push(push.to_vec()),
+                        // Opcodes (like `OP_1`) and truncated pushes after
+                        // `OP_RETURN` are valid, but such outputs can't be
+                        // represented as a list of data pushes
+                        _ => return Ok(None),
+                    }
+                }
+                P2OR(data)
+            }
+            _ => return Ok(None),
+        }))
+    }
+}
+
+impl TryFrom<PubkeyScript> for ScriptPubkeyDescriptor {
+    type Error = Error;
+    fn try_from(script_pubkey: PubkeyScript) -> Result<Self, Self::Error> {
+        Ok(Self::parse_standard(&script_pubkey)?
+            .unwrap_or(ScriptPubkeyDescriptor::P2S(script_pubkey)))
+    }
+}
+
+/// Detects the type of a borrowed `scriptPubkey`; the script data are cloned
+/// only for non-standard (`P2S`) scripts
+impl TryFrom<&PubkeyScript> for ScriptPubkeyDescriptor {
+    type Error = Error;
+    fn try_from(script_pubkey: &PubkeyScript) -> Result<Self, Self::Error> {
+        Ok(Self::parse_standard(script_pubkey)?
+            .unwrap_or_else(|| ScriptPubkeyDescriptor::P2S(script_pubkey.clone())))
     }
 }
 
@@ -175,15 +204,19 @@ impl From<ScriptPubkeyDescriptor> for PubkeyScript {
             P2PK(pubkey) => Builder::gen_p2pk(&pubkey).into_script(),
             P2PKH(pubkey_hash) => Builder::gen_p2pkh(&pubkey_hash).into_script(),
             P2SH(script_hash) => Builder::gen_p2sh(&script_hash).into_script(),
-            P2OR(data) => {
-                if data.len() > 1 {
-                    panic!("Underlying rust bitcoin library does not support multiple data in OP_RETURN")
-                }
-                Builder::gen_op_return(&data[0]).into_script()
-            }
+            P2OR(data) => data
+                .iter()
+                .fold(
+                    Builder::new().push_opcode(opcodes::all::OP_RETURN),
+                    |builder, push| builder.push_slice(push),
+                )
+                .into_script(),
             P2WPKH(wpubkey_hash) => Builder::gen_v0_p2wpkh(&wpubkey_hash).into_script(),
             P2WSH(wscript_hash) => Builder::gen_v0_p2wsh(&wscript_hash).into_script(),
-            P2TR(_) => unimplemented!(),
+            P2TR(pubkey) => Builder::new()
+                .push_int(1)
+                .push_slice(&pubkey.serialize()[1..])
+                .into_script(),
         })
     }
 }
@@ -206,3 +239,73 @@ impl From<ScriptPubkeyDescriptor> for ScriptPubkeyStructure {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::test::gen_secp_pubkeys;
+
+    #[test]
+    fn test_descriptor_roundtrip() {
+        use ScriptPubkeyDescriptor::*;
+        let pubkey = bitcoin::PublicKey {
+            compressed: true,
+            key: gen_secp_pubkeys(1)[0],
+        };
+        vec![
+            P2PK(pubkey),
+            P2PKH(pubkey.pubkey_hash()),
+            P2SH(ScriptHash::hash(b"script")),
+            P2WPKH(pubkey.wpubkey_hash()),
+            P2WSH(WScriptHash::hash(b"script")),
+            P2OR(vec![]),
+            P2OR(vec![vec![0xA5; 32]]),
+            P2OR(vec![vec![0x01, 0x02, 0x03], vec![0xA5; 40]]),
+        ]
+        .into_iter()
+        .for_each(|descr| {
+            let script = PubkeyScript::from(descr.clone());
+            assert_eq!(ScriptPubkeyDescriptor::try_from(&script).unwrap(), descr);
+            assert_eq!(ScriptPubkeyDescriptor::try_from(script).unwrap(), descr);
+        });
+
+        // Non-standard scripts are kept as they are
+        let script = PubkeyScript::from(Script::from(vec![0x51, 0x51, 0x87]));
+        assert_eq!(
+            ScriptPubkeyDescriptor::try_from(&script).unwrap(),
+            P2S(script.clone())
+        );
+        assert_eq!(
+            ScriptPubkeyDescriptor::try_from(script.clone()).unwrap(),
+            P2S(script)
+        );
+    }
+
+    #[test]
+    fn test_op_return_non_push() {
+        use ScriptPubkeyDescriptor::*;
+        vec![
+            // OP_RETURN OP_1
+            vec![0x6a, 0x51],
+            // OP_RETURN <push> OP_16
+            vec![0x6a, 0x01, 0xAA, 0x60],
+            // Push of 5 bytes truncated after 2 bytes
+            vec![0x6a, 0x05, 0x01, 0x02],
+            // OP_PUSHDATA1 without the length byte
+            vec![0x6a, 0x4c],
+        ]
+        .into_iter()
+        .for_each(|bytes| {
+            let script = PubkeyScript::from(Script::from(bytes));
+            assert!(script.is_op_return());
+            assert_eq!(
+                ScriptPubkeyDescriptor::try_from(&script).unwrap(),
+                P2S(script.clone())
+            );
+            assert_eq!(
+                ScriptPubkeyDescriptor::try_from(script.clone()).unwrap(),
+                P2S(script)
+            );
+        });
+    }
+}
@@ -0,0 +1,228 @@
+// LNP/BP Core Library implementing LNPBP specifications & standards
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Detection of [LockScript] properties preventing transactions spending it
+//! from being relayed by the nodes following Bitcoin Core policy
+
+use bitcoin::blockdata::opcodes::{self, all::*};
+use bitcoin::blockdata::script::{self, Instruction};
+
+use super::{LockScript, Strategy};
+
+/// Maximum number of non-push operations per script, according to the
+/// Bitcoin consensus rules
+pub const MAX_OPS_PER_SCRIPT: usize = 201;
+
+/// Maximum number of signature operations in a P2SH redeem script relayed by
+/// the nodes following Bitcoin Core standardness policy. The limit does not
+/// apply to the witness scripts, including ones nested into P2SH.
+pub const MAX_P2SH_SIGOPS: usize = 15;
+
+/// Maximum number of public keys in `OP_CHECKMULTISIG` operation, which is
+/// also the number of sigops it is counted for when the number of keys is
+/// not known
+const MAX_PUBKEYS_PER_MULTISIG: usize = 20;
+
+/// Problems found by [LockScript::standardness_check]
+#[derive(Clone, PartialEq, Eq, Debug, Display)]
+#[display_from(Debug)]
+pub enum StandardnessIssue {
+    /// Script can't be parsed or uses non-minimal push operations
+    InvalidEncoding(script::Error),
+
+    /// Script contains opcode disabled by the consensus rules (or
+    /// `OP_VERIF`/`OP_VERNOTIF`), which fails the script even in unexecuted
+    /// branches
+    DisabledOpcode(opcodes::All),
+
+    /// Script contains `OP_RETURN` at the given instruction position, making
+    /// the branch containing it unspendable
+    OpReturn(usize),
+
+    /// Number of non-push operations exceeds [MAX_OPS_PER_SCRIPT]
+    TooManyOpcodes(usize),
+
+    /// Number of signature operations in P2SH redeem script exceeds
+    /// [MAX_P2SH_SIGOPS]
+    TooManySigops(usize),
+}
+
+impl LockScript {
+    /// Enumerates properties of the script which will prevent transactions
+    /// spending it from being relayed (or mined, for the consensus-level
+    /// problems). Returns `Ok(())` if no problems were found.
+    ///
+    /// The `strategy` defines how the script is placed into the output; it
+    /// matters for the sigop limit, which applies only to P2SH redeem scripts
+    /// ([Strategy::LegacyHashed]). The script size is not checked, since its
+    /// limits depend on the type of the output containing the script, too.
+    pub fn standardness_check(&self, strategy: Strategy) -> Result<(), Vec<StandardnessIssue>> {
+        let mut issues = vec![];
+        let mut op_count = 0usize;
+        let mut sigops = 0usize;
+        let mut prev_op = None;
+
+        for (pos, instr) in self.iter(true).enumerate() {
+            let op = match instr {
+                Instruction::PushBytes(_) => {
+                    prev_op = None;
+                    continue;
+                }
+                Instruction::Op(op) => op,
+                Instruction::Error(err) => {
+                    issues.push(StandardnessIssue::InvalidEncoding(err));
+                    break;
+                }
+            };
+
+            if op.into_u8() > OP_PUSHNUM_16.into_u8() {
+                op_count += 1;
+            }
+            match op {
+                OP_CAT | OP_SUBSTR | OP_LEFT | OP_RIGHT | OP_INVERT | OP_AND | OP_OR | OP_XOR
+                | OP_2MUL | OP_2DIV | OP_MUL | OP_DIV | OP_MOD | OP_LSHIFT | OP_RSHIFT
+                | OP_VERIF | OP_VERNOTIF => issues.push(StandardnessIssue::DisabledOpcode(op)),
+                OP_RETURN => issues.push(StandardnessIssue::OpReturn(pos)),
+                OP_CHECKSIG | OP_CHECKSIGVERIFY => sigops += 1,
+                OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                    sigops += match prev_op {
+                        Some(prev)
+                            if prev.into_u8() >= OP_PUSHNUM_1.into_u8()
+                                && prev.into_u8() <= OP_PUSHNUM_16.into_u8() =>
+                        {
+                            (prev.into_u8() - OP_PUSHNUM_1.into_u8() + 1) as usize
+                        }
+                        _ => MAX_PUBKEYS_PER_MULTISIG,
+                    }
+                }
+                _ => {}
+            }
+            prev_op = Some(op);
+        }
+
+        if op_count > MAX_OPS_PER_SCRIPT {
+            issues.push(StandardnessIssue::TooManyOpcodes(op_count));
+        }
+        if strategy == Strategy::LegacyHashed && sigops > MAX_P2SH_SIGOPS {
+            issues.push(StandardnessIssue::TooManySigops(sigops));
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::test::gen_bitcoin_pubkeys;
+    use bitcoin::blockdata::script::Builder;
+
+    fn multisig(builder: Builder, threshold: i64, keys: &[bitcoin::PublicKey]) -> Builder {
+        keys.iter()
+            .fold(builder.push_int(threshold), |builder, key| {
+                builder.push_key(key)
+            })
+            .push_int(keys.len() as i64)
+            .push_opcode(OP_CHECKMULTISIG)
+    }
+
+    #[test]
+    fn test_clean_script() {
+        let keys = gen_bitcoin_pubkeys(3, true);
+        let script = LockScript::from(multisig(Builder::new(), 2, &keys).into_script());
+        assert_eq!(script.standardness_check(Strategy::LegacyHashed), Ok(()));
+
+        let script = LockScript::from(Builder::gen_p2pk(&keys[0]).into_script());
+        assert_eq!(script.standardness_check(Strategy::LegacyHashed), Ok(()));
+    }
+
+    #[test]
+    fn test_disabled_opcode() {
+        let keys = gen_bitcoin_pubkeys(1, true);
+        let script = LockScript::from(
+            Builder::new()
+                .push_opcode(OP_IF)
+                .push_slice(b"a")
+                .push_slice(b"b")
+                .push_opcode(OP_CAT)
+                .push_opcode(OP_DROP)
+                .push_opcode(OP_ENDIF)
+                .push_key(&keys[0])
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        assert_eq!(
+            script.standardness_check(Strategy::LegacyHashed),
+            Err(vec![StandardnessIssue::DisabledOpcode(OP_CAT)])
+        );
+
+        // OP_VERIF and OP_VERNOTIF fail the script even when not executed
+        [OP_VERIF, OP_VERNOTIF].iter().for_each(|op| {
+            let script = LockScript::from(
+                Builder::new()
+                    .push_int(0)
+                    .push_opcode(OP_IF)
+                    .push_opcode(*op)
+                    .push_opcode(OP_ENDIF)
+                    .push_key(&keys[0])
+                    .push_opcode(OP_CHECKSIG)
+                    .into_script(),
+            );
+            assert_eq!(
+                script.standardness_check(Strategy::WitnessV0),
+                Err(vec![StandardnessIssue::DisabledOpcode(*op)])
+            );
+        });
+    }
+
+    #[test]
+    fn test_op_return_and_limits() {
+        let script = LockScript::from(Builder::new().push_opcode(OP_RETURN).into_script());
+        assert_eq!(
+            script.standardness_check(Strategy::LegacyHashed),
+            Err(vec![StandardnessIssue::OpReturn(0)])
+        );
+
+        // Two multisigs with 16 keys each: 32 sigops
+        let keys = gen_bitcoin_pubkeys(16, true);
+        let builder = multisig(Builder::new(), 1, &keys).push_opcode(OP_VERIFY);
+        let script = LockScript::from(multisig(builder, 1, &keys).into_script());
+        assert_eq!(
+            script.standardness_check(Strategy::LegacyHashed),
+            Err(vec![StandardnessIssue::TooManySigops(32)])
+        );
+        // Sigop limit applies only to P2SH redeem scripts
+        assert_eq!(script.standardness_check(Strategy::WitnessV0), Ok(()));
+        assert_eq!(
+            script.standardness_check(Strategy::WitnessScriptHash),
+            Ok(())
+        );
+
+        let script = LockScript::from(
+            (0..=MAX_OPS_PER_SCRIPT)
+                .fold(Builder::new(), |builder, _| builder.push_opcode(OP_NOP))
+                .push_opcode(OP_PUSHNUM_1)
+                .into_script(),
+        );
+        assert_eq!(
+            script.standardness_check(Strategy::LegacyHashed),
+            Err(vec![StandardnessIssue::TooManyOpcodes(
+                MAX_OPS_PER_SCRIPT + 1
+            )])
+        );
+    }
+}
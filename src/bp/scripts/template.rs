@@ -0,0 +1,332 @@
+// LNP/BP Core Library implementing LNPBP specifications & standards
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Detection of standard script templates within [LockScript]
+
+use bitcoin::blockdata::opcodes::{self, all::*};
+use bitcoin::blockdata::script::{read_scriptint, Instruction};
+
+use super::LockScript;
+
+/// Hash functions which may be used in hashlock conditions
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub enum HashlockHash {
+    /// `OP_SHA256`
+    Sha256,
+    /// `OP_HASH256` (double SHA256)
+    Hash256,
+    /// `OP_RIPEMD160`
+    Ripemd160,
+    /// `OP_HASH160` (RIPEMD160 of SHA256)
+    Hash160,
+}
+
+impl HashlockHash {
+    /// Length of the digest produced by the hash function, in bytes
+    pub fn digest_len(self) -> usize {
+        match self {
+            HashlockHash::Sha256 | HashlockHash::Hash256 => 32,
+            HashlockHash::Ripemd160 | HashlockHash::Hash160 => 20,
+        }
+    }
+
+    fn from_opcode(op: opcodes::All) -> Option<Self> {
+        match op {
+            OP_SHA256 => Some(HashlockHash::Sha256),
+            OP_HASH256 => Some(HashlockHash::Hash256),
+            OP_RIPEMD160 => Some(HashlockHash::Ripemd160),
+            OP_HASH160 => Some(HashlockHash::Hash160),
+            _ => None,
+        }
+    }
+}
+
+/// Timelock condition enforced by `OP_CHECKSEQUENCEVERIFY` or
+/// `OP_CHECKLOCKTIMEVERIFY`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub enum Timelock {
+    /// Relative timelock (`OP_CHECKSEQUENCEVERIFY`, BIP-112)
+    Relative(u32),
+    /// Absolute timelock (`OP_CHECKLOCKTIMEVERIFY`, BIP-65)
+    Absolute(u32),
+}
+
+/// Standard script templates which may be recognized inside [LockScript]
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub enum ScriptTemplate {
+    /// `<pubkey> OP_CHECKSIG`
+    SinglePubkey(bitcoin::PublicKey),
+
+    /// `<m> <pubkey>... <n> OP_CHECKMULTISIG`
+    Multisig {
+        threshold: u8,
+        pubkeys: Vec<bitcoin::PublicKey>,
+    },
+
+    /// `[OP_SIZE <len> OP_EQUALVERIFY] <hash op> <digest> OP_EQUAL`
+    Hashlock {
+        hash_type: HashlockHash,
+        digest: Vec<u8>,
+    },
+
+    /// `<n> OP_CHECKSEQUENCEVERIFY` or `<n> OP_CHECKLOCKTIMEVERIFY`
+    Timelock(Timelock),
+
+    /// BIP-199 hashed timelock contract:
+    /// ```text
+    /// OP_IF
+    ///     <hash op> <digest> OP_EQUALVERIFY <recipient>
+    /// OP_ELSE
+    ///     <n> OP_CHECKSEQUENCEVERIFY|OP_CHECKLOCKTIMEVERIFY OP_DROP <sender>
+    /// OP_ENDIF
+    /// OP_CHECKSIG
+    /// ```
+    Htlc {
+        hash_type: HashlockHash,
+        digest: Vec<u8>,
+        recipient: bitcoin::PublicKey,
+        timelock: Timelock,
+        sender: bitcoin::PublicKey,
+    },
+}
+
+impl LockScript {
+    /// Tries to match the script against one of the known standard templates
+    /// ([ScriptTemplate]); returns `None` if the script is non-standard or
+    /// can't be parsed
+    pub fn detect_template(&self) -> Option<ScriptTemplate> {
+        let instructions = self
+            .iter(true)
+            .map(|instr| match instr {
+                Instruction::Error(_) => None,
+                instr => Some(instr),
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        match &instructions[..] {
+            [key, Instruction::Op(OP_CHECKSIG)] => {
+                Some(ScriptTemplate::SinglePubkey(parse_pubkey(key)?))
+            }
+
+            [threshold, keys @ .., total, Instruction::Op(OP_CHECKMULTISIG)] => {
+                let threshold = parse_num(threshold)?;
+                let total = parse_num(total)?;
+                if threshold < 1 || total as usize != keys.len() || threshold > total {
+                    return None;
+                }
+                let pubkeys = keys.iter().map(parse_pubkey).collect::<Option<Vec<_>>>()?;
+                Some(ScriptTemplate::Multisig {
+                    threshold: threshold as u8,
+                    pubkeys,
+                })
+            }
+
+            [Instruction::Op(OP_SIZE), len, Instruction::Op(OP_EQUALVERIFY), Instruction::Op(op), Instruction::PushBytes(digest), Instruction::Op(OP_EQUAL)] =>
+            {
+                let hash_type = parse_hashlock(*op, digest)?;
+                if parse_num(len)? != hash_type.digest_len() as i64 {
+                    return None;
+                }
+                Some(ScriptTemplate::Hashlock {
+                    hash_type,
+                    digest: digest.to_vec(),
+                })
+            }
+
+            [Instruction::Op(op), Instruction::PushBytes(digest), Instruction::Op(OP_EQUAL)] => {
+                Some(ScriptTemplate::Hashlock {
+                    hash_type: parse_hashlock(*op, digest)?,
+                    digest: digest.to_vec(),
+                })
+            }
+
+            [value, Instruction::Op(op)] => {
+                Some(ScriptTemplate::Timelock(parse_timelock(value, *op)?))
+            }
+
+            [Instruction::Op(OP_IF), Instruction::Op(op), Instruction::PushBytes(digest), Instruction::Op(OP_EQUALVERIFY), recipient, Instruction::Op(OP_ELSE), value, Instruction::Op(timelock_op), Instruction::Op(OP_DROP), sender, Instruction::Op(OP_ENDIF), Instruction::Op(OP_CHECKSIG)] => {
+                Some(ScriptTemplate::Htlc {
+                    hash_type: parse_hashlock(*op, digest)?,
+                    digest: digest.to_vec(),
+                    recipient: parse_pubkey(recipient)?,
+                    timelock: parse_timelock(value, *timelock_op)?,
+                    sender: parse_pubkey(sender)?,
+                })
+            }
+
+            _ => None,
+        }
+    }
+}
+
+fn parse_pubkey(instr: &Instruction) -> Option<bitcoin::PublicKey> {
+    match instr {
+        Instruction::PushBytes(data) => bitcoin::PublicKey::from_slice(data).ok(),
+        _ => None,
+    }
+}
+
+fn parse_num(instr: &Instruction) -> Option<i64> {
+    match instr {
+        Instruction::PushBytes(data) => read_scriptint(data).ok(),
+        Instruction::Op(op)
+            if op.into_u8() >= OP_PUSHNUM_1.into_u8()
+                && op.into_u8() <= OP_PUSHNUM_16.into_u8() =>
+        {
+            Some((op.into_u8() - OP_PUSHNUM_1.into_u8() + 1) as i64)
+        }
+        _ => None,
+    }
+}
+
+fn parse_hashlock(op: opcodes::All, digest: &[u8]) -> Option<HashlockHash> {
+    HashlockHash::from_opcode(op).filter(|hash_type| hash_type.digest_len() == digest.len())
+}
+
+fn parse_timelock(value: &Instruction, op: opcodes::All) -> Option<Timelock> {
+    let value = parse_num(value)?;
+    if value < 0 || value > u32::MAX as i64 {
+        return None;
+    }
+    match op {
+        OP_CSV => Some(Timelock::Relative(value as u32)),
+        OP_CLTV => Some(Timelock::Absolute(value as u32)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::test::gen_bitcoin_pubkeys;
+    use bitcoin::blockdata::script::Builder;
+    use bitcoin::hashes::{sha256, Hash};
+
+    #[test]
+    fn test_multisig_2_of_3() {
+        let keys = gen_bitcoin_pubkeys(3, true);
+        let script = LockScript::from(
+            keys.iter()
+                .fold(Builder::new().push_int(2), |builder, key| {
+                    builder.push_key(key)
+                })
+                .push_int(3)
+                .push_opcode(OP_CHECKMULTISIG)
+                .into_script(),
+        );
+        assert_eq!(
+            script.detect_template(),
+            Some(ScriptTemplate::Multisig {
+                threshold: 2,
+                pubkeys: keys.clone(),
+            })
+        );
+
+        // Key count mismatch must not be recognized
+        let script = LockScript::from(
+            keys.iter()
+                .fold(Builder::new().push_int(2), |builder, key| {
+                    builder.push_key(key)
+                })
+                .push_int(4)
+                .push_opcode(OP_CHECKMULTISIG)
+                .into_script(),
+        );
+        assert_eq!(script.detect_template(), None);
+    }
+
+    #[test]
+    fn test_htlc() {
+        let keys = gen_bitcoin_pubkeys(2, true);
+        let digest = sha256::Hash::hash(b"preimage");
+        let script = LockScript::from(
+            Builder::new()
+                .push_opcode(OP_IF)
+                .push_opcode(OP_SHA256)
+                .push_slice(&digest[..])
+                .push_opcode(OP_EQUALVERIFY)
+                .push_key(&keys[0])
+                .push_opcode(OP_ELSE)
+                .push_int(144)
+                .push_opcode(OP_CSV)
+                .push_opcode(OP_DROP)
+                .push_key(&keys[1])
+                .push_opcode(OP_ENDIF)
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+        assert_eq!(
+            script.detect_template(),
+            Some(ScriptTemplate::Htlc {
+                hash_type: HashlockHash::Sha256,
+                digest: digest[..].to_vec(),
+                recipient: keys[0],
+                timelock: Timelock::Relative(144),
+                sender: keys[1],
+            })
+        );
+    }
+
+    #[test]
+    fn test_simple_templates() {
+        let key = gen_bitcoin_pubkeys(1, true)[0];
+        let digest = sha256::Hash::hash(b"preimage");
+
+        let script = LockScript::from(Builder::gen_p2pk(&key).into_script());
+        assert_eq!(
+            script.detect_template(),
+            Some(ScriptTemplate::SinglePubkey(key))
+        );
+
+        let script = LockScript::from(
+            Builder::new()
+                .push_opcode(OP_SIZE)
+                .push_int(32)
+                .push_opcode(OP_EQUALVERIFY)
+                .push_opcode(OP_SHA256)
+                .push_slice(&digest[..])
+                .push_opcode(OP_EQUAL)
+                .into_script(),
+        );
+        assert_eq!(
+            script.detect_template(),
+            Some(ScriptTemplate::Hashlock {
+                hash_type: HashlockHash::Sha256,
+                digest: digest[..].to_vec(),
+            })
+        );
+
+        let script = LockScript::from(
+            Builder::new()
+                .push_int(650000)
+                .push_opcode(OP_CLTV)
+                .into_script(),
+        );
+        assert_eq!(
+            script.detect_template(),
+            Some(ScriptTemplate::Timelock(Timelock::Absolute(650000)))
+        );
+
+        let script = LockScript::from(
+            Builder::new()
+                .push_opcode(OP_HASH160)
+                .push_slice(&digest[..])
+                .push_opcode(OP_EQUAL)
+                .into_script(),
+        );
+        assert_eq!(script.detect_template(), None);
+    }
+}
@@ -90,13 +90,18 @@
 //!
 
 use amplify::Wrapper;
+use bitcoin::hashes::hex::{self, FromHex, ToHex};
 use bitcoin::{
     blockdata::{opcodes, opcodes::All, script::*},
     secp256k1, ScriptHash, WPubkeyHash, WScriptHash,
 };
 use core::convert::TryFrom;
+use core::fmt;
+use core::str::FromStr;
 
+use crate::bp::tagged256::tagged256hash;
 use crate::strict_encoding;
+use crate::SECP256K1;
 
 wrapper!(
     LockScript,
@@ -121,6 +126,71 @@ wrapper!(
     derive = [Default, PartialEq, Eq, PartialOrd, Ord, Hash]
 );
 
+impl strict_encoding::Strategy for PubkeyScript {
+    type Strategy = strict_encoding::strategies::Wrapped;
+}
+
+/// Errors parsing hex-encoded script types
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display_from(Debug)]
+pub enum ScriptParseError {
+    /// The provided string is not a valid hex encoding
+    #[derive_from]
+    Hex(hex::Error),
+
+    /// Hex-encoded data can't be parsed as a sequence of script instructions
+    #[derive_from]
+    Script(bitcoin::blockdata::script::Error),
+}
+
+macro_rules! impl_script_hex {
+    ($type:ident) => {
+        impl fmt::Display for $type {
+            /// Formats script as lowercase hex string
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.as_inner().as_bytes().to_hex())
+            }
+        }
+
+        impl FromStr for $type {
+            type Err = ScriptParseError;
+
+            /// Parses hex-encoded script, failing if the decoded bytes can't
+            /// be parsed as a sequence of script instructions
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let script = Script::from(Vec::<u8>::from_hex(s)?);
+                check_instructions(&script)?;
+                Ok($type::from(script))
+            }
+        }
+    };
+}
+
+impl_script_hex!(LockScript);
+impl_script_hex!(PubkeyScript);
+
+/// Checks that the script can be parsed into a sequence of instructions, i.e.
+/// does not contain truncated data pushes
+fn check_instructions(script: &Script) -> Result<(), bitcoin::blockdata::script::Error> {
+    for instr in script.iter(false) {
+        if let Instruction::Error(err) = instr {
+            Err(err)?
+        }
+    }
+    Ok(())
+}
+
+impl LockScript {
+    /// Wraps the script after checking that it is well-formed, i.e. can be
+    /// parsed into a sequence of instructions without truncated data pushes.
+    /// Unlike [LockScript::from_inner] guarantees that the commitment code
+    /// will be able to iterate over the script instructions.
+    pub fn parse(script: Script) -> Result<Self, bitcoin::blockdata::script::Error> {
+        check_instructions(&script)?;
+        Ok(Self::from_inner(script))
+    }
+}
+
 wrapper!(
     SigScript,
     Script,
@@ -368,10 +438,39 @@ pub enum Strategy {
     /// in transaction input to store the original [LockScript] or the public key
     WitnessV0,
 
-    /// Will be used for Taproot
+    /// We produce **P2TR** output committing to the public key tweaked
+    /// according to BIP-341 with an empty script tree (see [taptweak])
     WitnessV1Taproot,
 }
 
+/// Tag used for computing BIP-341 taproot output key tweaking factor
+const TAPTWEAK_TAG: &'static str = "TapTweak";
+
+/// Computes taproot output key from the internal key and script tree root
+/// according to BIP-341: `Q = P + H_TapTweak(x(P) || script_root) * G`.
+///
+/// Since taproot keys are x-only, internal keys with odd Y coordinate are
+/// negated before tweaking. Absent script root denotes key-path-only output
+/// (no script tree), in which case only `x(P)` is hashed.
+pub fn taptweak(
+    internal_key: &secp256k1::PublicKey,
+    script_root: Option<&bitcoin::hashes::sha256::Hash>,
+) -> Result<secp256k1::PublicKey, secp256k1::Error> {
+    let mut key = internal_key.clone();
+    if key.serialize()[0] == 0x03 {
+        key.negate_assign(&SECP256K1);
+    }
+
+    let mut data = key.serialize()[1..].to_vec();
+    if let Some(script_root) = script_root {
+        data.extend(&script_root[..]);
+    }
+    let factor = tagged256hash(TAPTWEAK_TAG, data);
+    key.add_exp_assign(&SECP256K1, &factor[..])?;
+
+    Ok(key)
+}
+
 /// Errors that happens during [ConversionStrategy::deduce] process
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display, Error)]
 #[display_from(Debug)]
@@ -385,6 +484,10 @@ pub enum StrategyError {
     /// Here we support only version 0 and 1 of the witness, otherwise this
     /// error is returned
     UnsupportedWitnessVersion(WitnessVersion),
+
+    /// BIP-341 tweak of the public key resulted in an invalid taproot output
+    /// key (see [taptweak]); the probability of this is negligible
+    InvalidTaprootTweak,
 }
 
 impl Strategy {
@@ -519,21 +622,26 @@ impl ScriptSet {
 /// Script set generation from public key or a given [LockScript] (with
 /// [TapScript] support planned for the future).
 pub trait GenerateScripts {
-    fn gen_scripts(&self, strategy: Strategy) -> ScriptSet {
-        ScriptSet {
-            pubkey_script: self.gen_script_pubkey(strategy),
+    fn gen_scripts(&self, strategy: Strategy) -> Result<ScriptSet, StrategyError> {
+        Ok(ScriptSet {
+            pubkey_script: self.gen_script_pubkey(strategy)?,
             sig_script: self.gen_sig_script(strategy),
             witness_script: self.gen_witness(strategy),
-        }
+        })
     }
-    fn gen_script_pubkey(&self, strategy: Strategy) -> PubkeyScript;
+    /// Generates `scriptPubkey` for the given strategy. Fails with
+    /// [StrategyError::UnsupportedWitnessVersion] if the strategy is not
+    /// supported for the source data, or with
+    /// [StrategyError::InvalidTaprootTweak] if the taproot output key can't
+    /// be computed.
+    fn gen_script_pubkey(&self, strategy: Strategy) -> Result<PubkeyScript, StrategyError>;
     fn gen_sig_script(&self, strategy: Strategy) -> SigScript;
     fn gen_witness(&self, strategy: Strategy) -> Option<Witness>;
 }
 
 impl GenerateScripts for LockScript {
-    fn gen_script_pubkey(&self, strategy: Strategy) -> PubkeyScript {
-        match strategy {
+    fn gen_script_pubkey(&self, strategy: Strategy) -> Result<PubkeyScript, StrategyError> {
+        Ok(match strategy {
             Strategy::Exposed => self.as_inner().into(),
             Strategy::LegacyHashed => Builder::gen_p2sh(&self.script_hash()).into_script().into(),
             Strategy::WitnessV0 => Builder::gen_v0_p2wsh(&self.wscript_hash())
@@ -544,13 +652,15 @@ impl GenerateScripts for LockScript {
                 // be generated from `LockScript` and will require
                 // `TapScript` source
                 let redeem_script =
-                    LockScript::from(self.gen_script_pubkey(Strategy::WitnessV0).to_inner());
+                    LockScript::from(Builder::gen_v0_p2wsh(&self.wscript_hash()).into_script());
                 Builder::gen_p2sh(&redeem_script.script_hash())
                     .into_script()
                     .into()
             }
-            Strategy::WitnessV1Taproot => unimplemented!(),
-        }
+            Strategy::WitnessV1Taproot => {
+                Err(StrategyError::UnsupportedWitnessVersion(WitnessVersion::V1))?
+            }
+        })
     }
 
     fn gen_sig_script(&self, strategy: Strategy) -> SigScript {
@@ -567,7 +677,7 @@ impl GenerateScripts for LockScript {
                 // be generated from `LockScript` and will require
                 // `TapScript` source
                 let redeem_script =
-                    LockScript::from(self.gen_script_pubkey(Strategy::WitnessV0).to_inner());
+                    LockScript::from(Builder::gen_v0_p2wsh(&self.wscript_hash()).into_script());
                 Builder::new()
                     .push_slice(redeem_script.as_bytes())
                     .into_script()
@@ -592,23 +702,44 @@ impl GenerateScripts for LockScript {
     }
 }
 
+/// Segwit v0 outputs with uncompressed keys are unspendable (BIP-143), so we
+/// always use compressed key serialization here
+fn gen_v0_p2wpkh(key: &secp256k1::PublicKey) -> Script {
+    Builder::gen_v0_p2wpkh(
+        &bitcoin::PublicKey {
+            compressed: true,
+            key: *key,
+        }
+        .wpubkey_hash(),
+    )
+    .into_script()
+}
+
 impl GenerateScripts for bitcoin::PublicKey {
-    fn gen_script_pubkey(&self, strategy: Strategy) -> PubkeyScript {
-        match strategy {
+    fn gen_script_pubkey(&self, strategy: Strategy) -> Result<PubkeyScript, StrategyError> {
+        Ok(match strategy {
             Strategy::Exposed => Builder::gen_p2pk(self).into_script().into(),
             Strategy::LegacyHashed => Builder::gen_p2pkh(&self.pubkey_hash()).into_script().into(),
-            Strategy::WitnessV0 => Builder::gen_v0_p2wpkh(&self.wpubkey_hash())
-                .into_script()
-                .into(),
+            Strategy::WitnessV0 => gen_v0_p2wpkh(&self.key).into(),
             Strategy::WitnessScriptHash => {
                 // TODO: Support tapscript P2SH-P2TR scheme here
-                let redeem_script = self.gen_script_pubkey(Strategy::WitnessV0);
-                Builder::gen_p2sh(&redeem_script.script_hash())
+                Builder::gen_p2sh(&gen_v0_p2wpkh(&self.key).script_hash())
                     .into_script()
                     .into()
             }
-            Strategy::WitnessV1Taproot => unimplemented!(),
-        }
+            // Key-path-only taproot output: the key is used as BIP-341
+            // internal key and tweaked with an empty script tree, so the
+            // output is spendable only with a signature for the tweaked key
+            Strategy::WitnessV1Taproot => {
+                let output_key =
+                    taptweak(&self.key, None).map_err(|_| StrategyError::InvalidTaprootTweak)?;
+                Builder::new()
+                    .push_int(1)
+                    .push_slice(&output_key.serialize()[1..])
+                    .into_script()
+                    .into()
+            }
+        })
     }
     fn gen_sig_script(&self, strategy: Strategy) -> SigScript {
         match strategy {
@@ -621,8 +752,7 @@ impl GenerateScripts for bitcoin::PublicKey {
                 .into(),
             Strategy::WitnessScriptHash => {
                 // TODO: Support tapscript P2SH-P2TR scheme here
-                let redeem_script =
-                    LockScript::from(self.gen_script_pubkey(Strategy::WitnessV0).into_inner());
+                let redeem_script = LockScript::from(gen_v0_p2wpkh(&self.key));
                 Builder::new()
                     .push_slice(redeem_script.as_bytes())
                     .into_script()
@@ -639,16 +769,18 @@ impl GenerateScripts for bitcoin::PublicKey {
         match strategy {
             Strategy::Exposed | Strategy::LegacyHashed => None,
             Strategy::WitnessV0 | Strategy::WitnessScriptHash => {
-                Some(Witness::from_inner(vec![self.to_bytes()]))
+                Some(Witness::from_inner(vec![self.key.serialize().to_vec()]))
             }
-            Strategy::WitnessV1Taproot => unimplemented!(),
+            // Key-path spending witness contains just a signature, which will
+            // be added later
+            Strategy::WitnessV1Taproot => Some(Witness::default()),
         }
     }
 }
 
 impl GenerateScripts for secp256k1::PublicKey {
     #[inline]
-    fn gen_script_pubkey(&self, strategy: Strategy) -> PubkeyScript {
+    fn gen_script_pubkey(&self, strategy: Strategy) -> Result<PubkeyScript, StrategyError> {
         bitcoin::PublicKey {
             compressed: true,
             key: self.clone(),
@@ -672,3 +804,190 @@ impl GenerateScripts for secp256k1::PublicKey {
         .gen_witness(strategy)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_script_hex_roundtrip() {
+        assert_eq!(LockScript::from_str("").unwrap(), LockScript::default());
+        assert_eq!(LockScript::default().to_string(), "");
+
+        let hex = "76a914b8332d502a529571c6af4be66399cd33379071c588ac";
+        let script = PubkeyScript::from_str(hex).unwrap();
+        assert!(script.is_p2pkh());
+        assert_eq!(script.to_string(), hex);
+        assert_eq!(PubkeyScript::from_str(&hex.to_uppercase()).unwrap(), script);
+
+        let lockscript = LockScript::from(
+            Builder::new()
+                .push_int(2)
+                .push_opcode(opcodes::all::OP_CSV)
+                .into_script(),
+        );
+        assert_eq!(
+            LockScript::from_str(&lockscript.to_string()).unwrap(),
+            lockscript
+        );
+    }
+
+    #[test]
+    fn test_script_hex_errors() {
+        // Odd number of characters
+        match LockScript::from_str("76a") {
+            Err(ScriptParseError::Hex(_)) => (),
+            _ => panic!("odd-length hex must fail"),
+        }
+        // Non-hex characters
+        match PubkeyScript::from_str("zz") {
+            Err(ScriptParseError::Hex(_)) => (),
+            _ => panic!("non-hex characters must fail"),
+        }
+        // Push of 20 bytes with only 2 bytes of data
+        match PubkeyScript::from_str("14dead") {
+            Err(ScriptParseError::Script(_)) => (),
+            _ => panic!("truncated push must fail"),
+        }
+    }
+
+    #[test]
+    fn test_lockscript_parse() {
+        let script = Builder::new()
+            .push_slice(&[0xAB; 20])
+            .push_opcode(opcodes::all::OP_EQUAL)
+            .into_script();
+        assert_eq!(
+            LockScript::parse(script.clone()).unwrap(),
+            LockScript::from_inner(script)
+        );
+        assert_eq!(
+            LockScript::parse(Script::new()).unwrap(),
+            LockScript::default()
+        );
+
+        // Push of 20 bytes with only 2 bytes of data
+        assert_eq!(
+            LockScript::parse(Script::from(vec![0x14, 0xde, 0xad])),
+            Err(bitcoin::blockdata::script::Error::EarlyEndOfScript)
+        );
+        // OP_PUSHDATA1 without the length byte
+        assert_eq!(
+            LockScript::parse(Script::from(vec![0x51, 0x4c])),
+            Err(bitcoin::blockdata::script::Error::EarlyEndOfScript)
+        );
+    }
+
+    #[test]
+    fn test_p2wpkh_bip173_vector() {
+        // Test vector from BIP-173
+        let key = secp256k1::PublicKey::from_slice(
+            &Vec::<u8>::from_hex(
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let expected = "0014751e76e8199196d454941c45d1b3a323f1433bd6";
+
+        assert_eq!(
+            key.gen_script_pubkey(Strategy::WitnessV0)
+                .unwrap()
+                .as_bytes()
+                .to_hex(),
+            expected
+        );
+        // Uncompressed flag must not affect segwit v0 output
+        let uncompressed = bitcoin::PublicKey {
+            compressed: false,
+            key,
+        };
+        assert_eq!(
+            uncompressed
+                .gen_script_pubkey(Strategy::WitnessV0)
+                .unwrap()
+                .as_bytes()
+                .to_hex(),
+            expected
+        );
+        assert_eq!(
+            uncompressed.gen_witness(Strategy::WitnessV0),
+            Some(Witness::from_inner(vec![key.serialize().to_vec()]))
+        );
+    }
+
+    #[test]
+    fn test_p2tr_bip341_vector() {
+        // Key-path-only output from BIP-341 wallet test vectors
+        let internal_key = secp256k1::PublicKey::from_slice(
+            &Vec::<u8>::from_hex(
+                "02d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let expected = "512053a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343";
+
+        assert_eq!(
+            internal_key
+                .gen_script_pubkey(Strategy::WitnessV1Taproot)
+                .unwrap()
+                .as_bytes()
+                .to_hex(),
+            expected
+        );
+        // Internal key is x-only, so the key with odd Y produces the same
+        // output
+        let mut negated = internal_key;
+        negated.negate_assign(&SECP256K1);
+        assert_eq!(
+            negated
+                .gen_script_pubkey(Strategy::WitnessV1Taproot)
+                .unwrap()
+                .as_bytes()
+                .to_hex(),
+            expected
+        );
+        assert_eq!(
+            taptweak(&internal_key, None).unwrap().serialize()[1..].to_hex(),
+            expected[4..]
+        );
+
+        // Taproot outputs can't be generated from lock scripts without the
+        // script tree
+        assert_eq!(
+            LockScript::from(Script::from(vec![0x51]))
+                .gen_script_pubkey(Strategy::WitnessV1Taproot),
+            Err(StrategyError::UnsupportedWitnessVersion(WitnessVersion::V1))
+        );
+    }
+
+    #[test]
+    fn test_script_wrappers_inner() {
+        let script = Script::from(vec![0x51, 0x87]);
+
+        let lockscript = LockScript::from_inner(script.clone());
+        // Borrowing does not copy the script data
+        assert!(std::ptr::eq(lockscript.as_inner(), &*lockscript));
+        assert_eq!(lockscript.as_inner(), &script);
+        assert_eq!(
+            lockscript.as_inner().as_bytes().as_ptr(),
+            lockscript.as_bytes().as_ptr()
+        );
+        // Moving out of the wrapper keeps the same allocation
+        let ptr = lockscript.as_bytes().as_ptr();
+        let inner = lockscript.into_inner();
+        assert_eq!(inner.as_bytes().as_ptr(), ptr);
+        assert_eq!(inner, script);
+
+        let pubkey_script = PubkeyScript::from_inner(inner);
+        assert!(std::ptr::eq(pubkey_script.as_inner(), &*pubkey_script));
+        assert_eq!(pubkey_script.as_bytes().as_ptr(), ptr);
+        let inner = pubkey_script.into_inner();
+        assert_eq!(inner.as_bytes().as_ptr(), ptr);
+        assert_eq!(
+            LockScript::from(inner.clone()),
+            LockScript::from_inner(inner)
+        );
+    }
+}
@@ -22,6 +22,13 @@ pub enum Error {
     CommitmentError(dbc::Error),
     ResolverError,
     ResolverLying,
+
+    /// Seal was already closed by the commitment in another transaction
+    /// output
+    SealAlreadyClosed {
+        seal: bitcoin::OutPoint,
+        closed_by: bitcoin::OutPoint,
+    },
 }
 
 impl From<dbc::Error> for Error {
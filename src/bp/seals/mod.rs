@@ -12,11 +12,13 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 mod error;
+mod registry;
 mod tx_graph;
 mod txout_seal;
 mod txout_witness;
 
 pub use error::Error;
+pub use registry::SealRegistry;
 pub use tx_graph::{SpendingStatus, TxGraph};
-pub use txout_seal::{TxResolve, TxoutSeal};
+pub use txout_seal::{OutpointMessage, TxResolve, TxoutSeal};
 pub use txout_witness::{InnerWitness, OuterWitness, Witness};
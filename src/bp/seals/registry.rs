@@ -0,0 +1,207 @@
+// LNP/BP Rust Library
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+use amplify::Wrapper;
+use bitcoin::OutPoint;
+use std::collections::BTreeMap;
+
+use super::{Error, Witness};
+use crate::bp::dbc::{compute_lnpbp3_vout, TxSupplement};
+
+/// In-memory registry of the closed transaction output seals, mapping each
+/// seal definition to the outpoint holding the commitment which has closed
+/// it. Used by client-side validation to enforce the single use of the
+/// seals.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SealRegistry {
+    closed: BTreeMap<OutPoint, OutPoint>,
+}
+
+impl SealRegistry {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers closing of the `seal` by the commitment placed into the
+    /// `commitment` output. Fails with [Error::SealAlreadyClosed] if the seal
+    /// was already closed, leaving the registry unchanged.
+    pub fn close(&mut self, seal: OutPoint, commitment: OutPoint) -> Result<(), Error> {
+        if let Some(closed_by) = self.closed.get(&seal) {
+            Err(Error::SealAlreadyClosed {
+                seal,
+                closed_by: *closed_by,
+            })?
+        }
+        self.closed.insert(seal, commitment);
+        Ok(())
+    }
+
+    /// Registers closing of the `seal` by the witness transaction, which must
+    /// spend the seal; the commitment outpoint is located with LNPBP-3 rules
+    /// (see [compute_lnpbp3_vout]) using the provided `supplement`
+    pub fn close_with_witness(
+        &mut self,
+        seal: OutPoint,
+        witness: &Witness,
+        supplement: &TxSupplement,
+    ) -> Result<OutPoint, Error> {
+        let tx = witness.0.as_inner();
+        if tx.output.is_empty() || !tx.input.iter().any(|txin| txin.previous_output == seal) {
+            Err(Error::InvalidSealDefinition)?
+        }
+        let commitment = OutPoint {
+            txid: tx.txid(),
+            vout: compute_lnpbp3_vout(tx, supplement) as u32,
+        };
+        self.close(seal, commitment)?;
+        Ok(commitment)
+    }
+
+    /// Returns outpoint with the commitment which has closed the seal, or
+    /// `None` if the seal is not known to be closed
+    #[inline]
+    pub fn closed_by(&self, seal: &OutPoint) -> Option<&OutPoint> {
+        self.closed.get(seal)
+    }
+
+    #[inline]
+    pub fn is_closed(&self, seal: &OutPoint) -> bool {
+        self.closed.contains_key(seal)
+    }
+
+    /// Returns number of the closed seals
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.closed.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.closed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::dbc::{Proof, TxCommitment};
+    use crate::bp::test::gen_secp_pubkeys;
+    use bitcoin::hashes::{sha256, sha256d, Hash};
+    use bitcoin::{Script, Transaction, TxIn, TxOut, Txid};
+
+    fn outpoint(no: u8, vout: u32) -> OutPoint {
+        OutPoint {
+            txid: Txid::from_inner(sha256d::Hash::hash(&[no]).into_inner()),
+            vout,
+        }
+    }
+
+    #[test]
+    fn test_close_once() {
+        let mut registry = SealRegistry::new();
+        assert!(registry.is_empty());
+
+        let seal = outpoint(1, 0);
+        let commitment = outpoint(2, 3);
+        assert!(!registry.is_closed(&seal));
+        registry.close(seal, commitment).unwrap();
+        assert!(registry.is_closed(&seal));
+        assert_eq!(registry.closed_by(&seal), Some(&commitment));
+        assert_eq!(registry.len(), 1);
+
+        // Other outputs of the same transaction are different seals
+        registry.close(outpoint(1, 1), commitment).unwrap();
+        assert_eq!(registry.len(), 2);
+    }
+
+    #[test]
+    fn test_close_twice() {
+        let mut registry = SealRegistry::new();
+        let seal = outpoint(1, 0);
+        let first = outpoint(2, 0);
+        registry.close(seal, first).unwrap();
+
+        assert_eq!(
+            registry.close(seal, outpoint(3, 0)),
+            Err(Error::SealAlreadyClosed {
+                seal,
+                closed_by: first
+            })
+        );
+        assert_eq!(
+            registry.close(seal, first),
+            Err(Error::SealAlreadyClosed {
+                seal,
+                closed_by: first
+            })
+        );
+        assert_eq!(registry.closed_by(&seal), Some(&first));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn test_close_with_witness() {
+        let seal = outpoint(1, 0);
+        let tx = Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: seal,
+                script_sig: Script::new(),
+                sequence: 0xFFFFFFFF,
+                witness: vec![],
+            }],
+            output: (0..3)
+                .map(|value| TxOut {
+                    value,
+                    script_pubkey: Script::new(),
+                })
+                .collect(),
+        };
+        let witness = Witness(
+            TxCommitment::from_inner(tx.clone()),
+            Proof::from(gen_secp_pubkeys(1)[0]),
+        );
+        let supplement = TxSupplement {
+            protocol_factor: 1,
+            fee: 1000,
+            tag: sha256::Hash::hash(b"TEST_TAG"),
+        };
+
+        let mut registry = SealRegistry::new();
+        assert_eq!(
+            registry.close_with_witness(outpoint(1, 1), &witness, &supplement),
+            Err(Error::InvalidSealDefinition)
+        );
+        let commitment = registry
+            .close_with_witness(seal, &witness, &supplement)
+            .unwrap();
+        // (1000 + 1) mod 3
+        assert_eq!(
+            commitment,
+            OutPoint {
+                txid: tx.txid(),
+                vout: 2
+            }
+        );
+        assert_eq!(registry.closed_by(&seal), Some(&commitment));
+        assert_eq!(
+            registry.close_with_witness(seal, &witness, &supplement),
+            Err(Error::SealAlreadyClosed {
+                seal,
+                closed_by: commitment
+            })
+        );
+    }
+}
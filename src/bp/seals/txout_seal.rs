@@ -20,6 +20,45 @@ use crate::bp::ShortId;
 use crate::commit_verify::EmbedCommitVerify;
 use crate::single_use_seals::{Message, SealMedium, SealStatus, SingleUseSeal};
 
+/// Byte representation of the seal definition (transaction outpoint), which
+/// allows committing to it directly with any commitment scheme taking
+/// `AsRef<[u8]>` messages. Bytes follow the outpoint consensus serialization:
+/// 32 bytes of the txid in its internal byte order (the hash bytes as they
+/// are stored in transactions, i.e. reversed relative to the hex form shown
+/// by block explorers), followed by the output index as a 4-byte
+/// little-endian integer.
+///
+/// [bitcoin::Txid] already implements `AsRef<[u8]>` returning the same 32
+/// bytes in internal byte order, so it can be committed to as is.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct OutpointMessage {
+    outpoint: OutPoint,
+    data: Vec<u8>,
+}
+
+impl OutpointMessage {
+    #[inline]
+    pub fn outpoint(&self) -> OutPoint {
+        self.outpoint
+    }
+}
+
+impl From<OutPoint> for OutpointMessage {
+    fn from(outpoint: OutPoint) -> Self {
+        let mut data = outpoint.txid[..].to_vec();
+        data.extend(&outpoint.vout.to_le_bytes());
+        Self { outpoint, data }
+    }
+}
+
+impl AsRef<[u8]> for OutpointMessage {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.data
+    }
+}
+
 pub struct TxoutSeal<'a, RESOLVER>
 where
     RESOLVER: TxResolve,
@@ -118,3 +157,42 @@ pub trait TxResolve {
     fn tx_container(&self, outpoint: OutPoint) -> Result<TxContainer, Self::Error>;
     fn tx_and_data(&self, outpoint: OutPoint) -> Result<(Transaction, TxSupplement), Self::Error>;
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp::dbc::{LNPBP1Commitment, LNPBP1Container};
+    use crate::bp::test::gen_secp_pubkeys;
+    use bitcoin::hashes::{hex::FromHex, sha256, Hash};
+    use bitcoin::Txid;
+
+    #[test]
+    fn test_outpoint_message() {
+        let txid =
+            Txid::from_hex("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")
+                .unwrap();
+        // Txid is shown in the reversed byte order
+        let txid_bytes =
+            Vec::<u8>::from_hex("efcdab8967452301efcdab8967452301efcdab8967452301efcdab8967452301")
+                .unwrap();
+        assert_eq!(txid.as_ref(), &txid_bytes[..]);
+
+        let outpoint = OutPoint::new(txid, 0x01020304);
+        let msg = OutpointMessage::from(outpoint);
+        assert_eq!(msg.outpoint(), outpoint);
+        assert_eq!(msg.as_ref().len(), 36);
+        assert_eq!(&msg.as_ref()[..32], &txid_bytes[..]);
+        assert_eq!(&msg.as_ref()[32..], &[0x04, 0x03, 0x02, 0x01]);
+        assert_eq!(msg.as_ref(), &bitcoin::consensus::serialize(&outpoint)[..]);
+
+        // Both can be committed to directly, and outputs with the same txid
+        // produce different commitments
+        let container =
+            LNPBP1Container::new(gen_secp_pubkeys(1)[0], sha256::Hash::hash(b"TEST_TAG"));
+        let commitment = LNPBP1Commitment::embed_commit(&container, &msg).unwrap();
+        assert!(commitment.verify(&container, &msg).unwrap());
+        let other = OutpointMessage::from(OutPoint::new(txid, 0));
+        assert!(!commitment.verify(&container, &other).unwrap());
+        assert!(!commitment.verify(&container, &txid).unwrap());
+    }
+}
@@ -32,9 +32,91 @@ pub fn tagged256hash(tag: &str, msg: Vec<u8>) -> TaggedHash {
     sha256::Hash::from_engine(engine).into()
 }
 
+/// Message which is domain-separated with a BIP-Schnorr tagged hash before
+/// being committed to. Exposes the tagged hash digest (and not the original
+/// data) as its byte representation, so commitments produced for different
+/// protocols (tags) are not interchangeable even for identical payloads.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct TaggedMessage {
+    tag: &'static str,
+    data: Vec<u8>,
+    digest: TaggedHash,
+}
+
+impl TaggedMessage {
+    pub fn new(tag: &'static str, data: Vec<u8>) -> Self {
+        let digest = tagged256hash(tag, data.clone());
+        Self { tag, data, digest }
+    }
+
+    #[inline]
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    #[inline]
+    pub fn digest(&self) -> TaggedHash {
+        self.digest
+    }
+}
+
+impl AsRef<[u8]> for TaggedMessage {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.digest[..]
+    }
+}
+
 #[macro_export]
 macro_rules! hashed_tag {
     ($prefix:expr, $tag:expr) => {
         const TAG: &'static str = concat!($prefix, $tag);
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::commit_verify::CommitVerify;
+    use bitcoin::hashes::hex::FromHex;
+
+    #[test]
+    fn test_tagged_message() {
+        let msg = TaggedMessage::new("LNPBP/test", b"message".to_vec());
+        assert_eq!(msg.tag(), "LNPBP/test");
+        assert_eq!(msg.data(), b"message");
+        assert_eq!(
+            msg.as_ref(),
+            &Vec::from_hex("164d5d659529c2846a5e84c15118c80eb2d3da13713c2d8675e498c402311b7c")
+                .unwrap()[..]
+        );
+
+        let msg = TaggedMessage::new("TapLeaf", vec![]);
+        assert_eq!(
+            msg.as_ref(),
+            &Vec::from_hex("5212c288a377d1f8164962a5a13429f9ba6a7b84e59776a52c6637df2106facb")
+                .unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn test_tagged_message_domain_separation() {
+        let data = b"identical payload".to_vec();
+        let msg1 = TaggedMessage::new("LNPBP/first", data.clone());
+        let msg2 = TaggedMessage::new("LNPBP/second", data.clone());
+        assert_ne!(msg1.digest(), msg2.digest());
+
+        let commitment1 = sha256::Hash::commit(&msg1);
+        let commitment2 = sha256::Hash::commit(&msg2);
+        assert_ne!(commitment1, commitment2);
+        assert_ne!(commitment1, sha256::Hash::commit(&data));
+        assert!(commitment1.verify(&msg1));
+        assert!(!commitment1.verify(&msg2));
+    }
+}
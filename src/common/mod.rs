@@ -13,6 +13,26 @@
 
 //! Common data types, structures and functions for LNPBPs
 
+/// Logs trace-level message with the `log` crate when the `log` feature is
+/// enabled; expands to nothing otherwise. Must not be used for logging any
+/// secret data (private keys, tweaking factors, nonces or committed messages).
+macro_rules! log_trace {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        trace!($($arg)*);
+    }};
+}
+
+/// Logs debug-level message with the `log` crate when the `log` feature is
+/// enabled; expands to nothing otherwise. The same restrictions as for
+/// [log_trace] apply.
+macro_rules! log_debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        debug!($($arg)*);
+    }};
+}
+
 mod bipolar;
 pub mod data_format;
 pub mod internet;
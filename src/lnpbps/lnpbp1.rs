@@ -41,10 +41,7 @@ pub fn lnpbp1_commit(
     message: &[u8],
 ) -> Result<Commitment, secp256k1::Error> {
     let commitment = LNPBP1Commitment::embed_commit(
-        &LNPBP1Container {
-            pubkey: pubkey.clone(),
-            tag: protocol_tag.clone(),
-        },
+        &LNPBP1Container::new(pubkey.clone(), protocol_tag.clone()),
         &message,
     )?;
     Ok(Commitment {
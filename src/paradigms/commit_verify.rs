@@ -18,6 +18,13 @@
 /// Trait for commit-verify scheme. A message for the commitment may be any
 /// structure that can be represented as a byte array (i.e. implements
 /// `AsRef<[u8]>`).
+///
+/// Apart from byte vectors and strings this includes fixed-size arrays (like
+/// `[u8; 32]`) and all hash types from `bitcoin_hashes` (`sha256::Hash`,
+/// `sha256d::Hash`, `Hmac<T>` etc), which are committed to using their
+/// internal byte order, i.e. the order in which the hash function outputs its
+/// digest. For `sha256d::Hash`-based types (like `Txid`) this is the reverse of
+/// the byte order used for their hex display.
 pub trait CommitVerify<MSG>
 where
     Self: Eq + Sized,
@@ -105,6 +112,79 @@ where
             Err(_) => false,
         })
     }
+
+    /// Creates a commitment with [EmbedCommitVerify::embed_commit] and
+    /// immediately verifies it against the same container and message,
+    /// returning the commitment only if the verification succeeds. Helps to
+    /// detect buggy container/message combinations early, before the
+    /// commitment is published.
+    fn commit_reveal(
+        container: &Self::Container,
+        msg: &MSG,
+    ) -> Result<Self, CommitRevealError<Self::Error>> {
+        let commitment =
+            Self::embed_commit(container, msg).map_err(CommitRevealError::Commitment)?;
+        match commitment.verify(container, msg) {
+            Ok(true) => Ok(commitment),
+            Ok(false) => Err(CommitRevealError::VerificationFailed),
+            Err(err) => Err(CommitRevealError::Commitment(err)),
+        }
+    }
+}
+
+/// Errors returned by [EmbedCommitVerify::commit_reveal]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CommitRevealError<E>
+where
+    E: std::error::Error,
+{
+    /// Error happened during commitment or verification procedure
+    Commitment(E),
+    /// Commitment was created, but it does not pass the verification against
+    /// the same container and message
+    VerificationFailed,
+}
+
+impl<E> std::fmt::Display for CommitRevealError<E>
+where
+    E: std::error::Error,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommitRevealError::Commitment(err) => write!(f, "Commitment error: {}", err),
+            CommitRevealError::VerificationFailed => {
+                f.write_str("Commitment does not pass verification")
+            }
+        }
+    }
+}
+
+impl<E> std::error::Error for CommitRevealError<E>
+where
+    E: std::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CommitRevealError::Commitment(err) => Some(err),
+            CommitRevealError::VerificationFailed => None,
+        }
+    }
+}
+
+/// Compares two byte strings in time which depends only on their length, but
+/// not on their content. Commitments which are produced by tweaking keys (see
+/// LNPBP-1 and LNPBP-2) are derived from the secret data, and a plain `==`
+/// comparison stops at the first differing byte, so the time it takes may
+/// leak information about the position of the mismatch. The lengths of the
+/// compared values are not considered secret.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
 }
 
 #[cfg(test)]
@@ -115,7 +195,7 @@ pub(crate) mod test {
     use core::hash::Hash;
     use std::collections::HashSet;
 
-    #[derive(Debug, Display, Error)]
+    #[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
     #[display_from(Debug)]
     struct Error;
     #[derive(Clone, PartialEq, Eq, Debug, Hash)]
@@ -145,6 +225,28 @@ pub(crate) mod test {
         }
     }
 
+    /// Commitment which never passes verification
+    #[derive(Clone, PartialEq, Eq, Debug, Hash)]
+    struct BrokenVec(Vec<u8>);
+    impl<T> EmbedCommitVerify<T> for BrokenVec
+    where
+        T: AsRef<[u8]>,
+    {
+        type Container = DummyVec;
+        type Error = Error;
+
+        fn embed_commit(container: &Self::Container, msg: &T) -> Result<Self, Self::Error> {
+            if msg.as_ref().is_empty() {
+                Err(Error)?
+            }
+            Ok(BrokenVec(container.0.clone()))
+        }
+
+        fn verify(&self, _: &Self::Container, _: &T) -> Result<bool, Self::Error> {
+            Ok(false)
+        }
+    }
+
     /// All of these messages MUST produce different commitments, otherwise the
     /// commitment algorithm is not collision-resistant
     pub(crate) fn gen_messages() -> Vec<Vec<u8>> {
@@ -261,4 +363,80 @@ pub(crate) mod test {
     fn test_embed_commit() {
         embed_commit_verify_suite::<Vec<u8>, DummyVec>(gen_messages(), &DummyVec(vec![]));
     }
+
+    #[test]
+    fn test_hash_messages() {
+        use bitcoin::hashes::{sha256, sha256d, Hash, HashEngine, Hmac, HmacEngine};
+
+        let sha = sha256::Hash::hash(b"test");
+        assert_eq!(AsRef::<[u8]>::as_ref(&sha).len(), 32);
+        assert_eq!(AsRef::<[u8]>::as_ref(&sha), &sha.into_inner()[..]);
+
+        let shad = sha256d::Hash::hash(b"test");
+        assert_eq!(AsRef::<[u8]>::as_ref(&shad).len(), 32);
+        assert_eq!(AsRef::<[u8]>::as_ref(&shad), &shad.into_inner()[..]);
+        // Internal byte order is the reverse of hex display order
+        let mut displayed = Vec::from_hex(&shad.to_string()).unwrap();
+        displayed.reverse();
+        assert_eq!(AsRef::<[u8]>::as_ref(&shad), &displayed[..]);
+
+        let mut engine = HmacEngine::<sha256::Hash>::new(b"key");
+        engine.input(b"test");
+        let hmac = Hmac::<sha256::Hash>::from_engine(engine);
+        assert_eq!(AsRef::<[u8]>::as_ref(&hmac).len(), 32);
+        assert_eq!(AsRef::<[u8]>::as_ref(&hmac), &hmac.into_inner()[..]);
+
+        let array = [0xA5u8; 32];
+        assert_eq!(AsRef::<[u8]>::as_ref(&array), &array[..]);
+
+        commit_verify_suite::<sha256::Hash, DummyHashCommitment>(
+            gen_messages()
+                .iter()
+                .map(|msg| sha256::Hash::hash(msg))
+                .collect(),
+        );
+        commit_verify_suite::<sha256d::Hash, DummyHashCommitment>(
+            gen_messages()
+                .iter()
+                .map(|msg| sha256d::Hash::hash(msg))
+                .collect(),
+        );
+        commit_verify_suite::<[u8; 32], DummyHashCommitment>(
+            (0u8..10).map(|byte| [byte; 32]).collect(),
+        );
+    }
+
+    #[test]
+    fn test_commit_reveal() {
+        let container = DummyVec(vec![0xDE, 0xAD]);
+        gen_messages().iter().for_each(|msg| {
+            let commitment = DummyVec::commit_reveal(&container, msg).unwrap();
+            assert_eq!(commitment, DummyVec::embed_commit(&container, msg).unwrap());
+        });
+
+        assert_eq!(
+            BrokenVec::commit_reveal(&container, &b"test".to_vec()),
+            Err(CommitRevealError::VerificationFailed)
+        );
+        assert_eq!(
+            BrokenVec::commit_reveal(&container, &b"".to_vec()),
+            Err(CommitRevealError::Commitment(Error))
+        );
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"", b""));
+        assert!(constant_time_eq(b"\x00", b"\x00"));
+        assert!(constant_time_eq(b"test message", b"test message"));
+        assert!(!constant_time_eq(b"test message", b"test messagf"));
+        assert!(!constant_time_eq(b"test message", b"uest message"));
+        assert!(!constant_time_eq(b"test", b"test message"));
+        assert!(!constant_time_eq(b"", b"\x00"));
+        gen_messages().iter().enumerate().for_each(|(i, a)| {
+            gen_messages().iter().enumerate().for_each(|(j, b)| {
+                assert_eq!(constant_time_eq(a, b), i == j);
+            })
+        });
+    }
 }
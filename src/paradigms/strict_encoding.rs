@@ -11,7 +11,9 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+use bitcoin::hashes::{sha256d, Hash};
 use core::ops::Range;
+use std::collections::BTreeMap;
 use std::fmt::{self, Display, Formatter};
 use std::io;
 
@@ -54,6 +56,20 @@ pub trait StrictDecode: Sized {
     fn strict_decode<D: io::Read>(d: D) -> Result<Self, Self::Error>;
 }
 
+/// Computing hashes of the strict encoded data
+pub trait StrictHash: StrictEncode {
+    /// Computes double SHA256 hash of the strict encoding of the data. The
+    /// data are encoded directly into the hash engine, so no intermediary
+    /// buffer for the encoded data is allocated.
+    fn strict_hash(&self) -> Result<sha256d::Hash, Self::Error> {
+        let mut engine = sha256d::Hash::engine();
+        self.strict_encode(&mut engine)?;
+        Ok(sha256d::Hash::from_engine(engine))
+    }
+}
+
+impl<T> StrictHash for T where T: StrictEncode {}
+
 /// Convenience method for strict encoding of data structures implementing
 /// [StrictEncode] into a byte vector. To support this method a
 /// type must implement `From<strict_encode::Error>` for an error type
@@ -89,6 +105,63 @@ where
     }
 }
 
+/// Decodes `BTreeMap` checking that the number of its items does not exceed
+/// `max_items` before decoding any of the items; fails with
+/// [Error::ExceedMaxItems] otherwise. Used for decoding collections which
+/// size must be limited below the generic `u16` bound, such that data from
+/// untrusted sources claiming huge number of items are rejected early.
+pub fn strict_decode_map_bounded<K, V, D>(
+    mut d: D,
+    max_items: usize,
+) -> Result<BTreeMap<K, V>, V::Error>
+where
+    D: io::Read,
+    K: StrictDecode + Ord + Clone,
+    V: StrictDecode + Clone,
+    K::Error: From<Error>,
+    V::Error: From<Error> + From<K::Error>,
+{
+    let len = usize::strict_decode(&mut d)?;
+    if len > max_items {
+        Err(Error::ExceedMaxItems(len))?;
+    }
+    let mut map = BTreeMap::<K, V>::new();
+    for _ in 0..len {
+        let key = K::strict_decode(&mut d)?;
+        // Keys must be strictly increasing, otherwise different
+        // encodings would produce the same map
+        if map.keys().next_back().map(|last| *last >= key) == Some(true) {
+            Err(Error::NonCanonicalOrder(
+                std::any::type_name::<BTreeMap<K, V>>().to_string(),
+            ))?;
+        }
+        let val = V::strict_decode(&mut d)?;
+        map.insert(key, val);
+    }
+    Ok(map)
+}
+
+/// Decodes `Vec` checking that the number of its items does not exceed
+/// `max_items` before allocating memory and decoding any of the items; fails
+/// with [Error::ExceedMaxItems] otherwise. Counterpart of
+/// [strict_decode_map_bounded] for the non-keyed collections.
+pub fn strict_decode_vec_bounded<T, D>(mut d: D, max_items: usize) -> Result<Vec<T>, T::Error>
+where
+    D: io::Read,
+    T: StrictDecode,
+    T::Error: From<Error>,
+{
+    let len = usize::strict_decode(&mut d)?;
+    if len > max_items {
+        Err(Error::ExceedMaxItems(len))?;
+    }
+    let mut data = Vec::<T>::with_capacity(len);
+    for _ in 0..len {
+        data.push(T::strict_decode(&mut d)?);
+    }
+    Ok(data)
+}
+
 /// Possible errors during strict encoding and decoding process
 #[derive(Debug, From, Error)]
 pub enum Error {
@@ -130,6 +203,10 @@ pub enum Error {
     /// A repeated value found during set collection deserialization
     RepeatedValue(String),
 
+    /// Keys of an ordered collection were not serialized in strictly
+    /// increasing order, i.e. the data are not in their canonical form
+    NonCanonicalOrder(String),
+
     /// Returned by the convenience method [strict_decode] if not all
     /// provided data were consumed during decoding process
     DataNotEntirelyConsumed,
@@ -188,6 +265,11 @@ impl Display for Error {
                 "A repeated value {} found during set collection deserialization",
                 value
             ),
+            NonCanonicalOrder(collection) => write!(
+                f,
+                "Keys of {} collection are not in strictly increasing order",
+                collection
+            ),
             DataNotEntirelyConsumed => write!(
                 f,
                 "Data were not consumed entirely during strict decoding procedure"
@@ -817,15 +899,8 @@ mod compositional_types {
         V::Error: From<Error> + From<K::Error>,
     {
         type Error = V::Error;
-        fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, Self::Error> {
-            let len = usize::strict_decode(&mut d)?;
-            let mut map = BTreeMap::<K, V>::new();
-            for _ in 0..len {
-                let key = K::strict_decode(&mut d)?;
-                let val = V::strict_decode(&mut d)?;
-                map.insert(key, val);
-            }
-            Ok(map)
+        fn strict_decode<D: io::Read>(d: D) -> Result<Self, Self::Error> {
+            super::strict_decode_map_bounded(d, std::u16::MAX as usize)
         }
     }
 
@@ -865,6 +940,7 @@ mod compositional_types {
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::collections::BTreeMap;
 
     fn gen_strings() -> Vec<&'static str> {
         vec![
@@ -888,6 +964,81 @@ mod test {
         })
     }
 
+    #[test]
+    fn test_btreemap_canonical_order() {
+        let map: BTreeMap<u8, u8> = bmap! { 1u8 => 0xAAu8, 2u8 => 0xBBu8 };
+        let encoded = strict_encode(&map).unwrap();
+        assert_eq!(encoded, vec![2u8, 0, 1, 0xAA, 2, 0xBB]);
+        assert_eq!(strict_decode::<BTreeMap<u8, u8>>(&encoded).unwrap(), map);
+
+        // Out-of-order keys
+        match strict_decode::<BTreeMap<u8, u8>>(&vec![2u8, 0, 2, 0xBB, 1, 0xAA]) {
+            Err(Error::NonCanonicalOrder(_)) => {}
+            _ => panic!("Out-of-order map keys must not be decoded"),
+        }
+        // Repeated keys
+        match strict_decode::<BTreeMap<u8, u8>>(&vec![2u8, 0, 1, 0xAA, 1, 0xBB]) {
+            Err(Error::NonCanonicalOrder(_)) => {}
+            _ => panic!("Repeated map keys must not be decoded"),
+        }
+    }
+
+    #[test]
+    fn test_strict_hash() {
+        gen_strings().into_iter().for_each(|s| {
+            assert_eq!(
+                s.strict_hash().unwrap(),
+                sha256d::Hash::hash(&strict_encode(&s).unwrap())
+            );
+        });
+
+        let map: BTreeMap<u8, u8> = bmap! { 1u8 => 0xAAu8, 2u8 => 0xBBu8 };
+        assert_eq!(
+            map.strict_hash().unwrap(),
+            sha256d::Hash::hash(&strict_encode(&map).unwrap())
+        );
+        assert_ne!(map.strict_hash().unwrap(), "".strict_hash().unwrap());
+    }
+
+    #[test]
+    fn test_btreemap_bounded() {
+        let map: BTreeMap<u8, u8> = bmap! { 1u8 => 0xAAu8, 2u8 => 0xBBu8 };
+        let encoded = strict_encode(&map).unwrap();
+        assert_eq!(
+            strict_decode_map_bounded::<u8, u8, _>(&encoded[..], 2).unwrap(),
+            map
+        );
+        match strict_decode_map_bounded::<u8, u8, _>(&encoded[..], 1) {
+            Err(Error::ExceedMaxItems(2)) => {}
+            _ => panic!("Map exceeding item limit must not be decoded"),
+        }
+
+        // Crafted length prefix without any data must be rejected before
+        // attempting to read the items
+        match strict_decode_map_bounded::<u8, u8, _>(&[0xFFu8, 0xFF][..], 16) {
+            Err(Error::ExceedMaxItems(0xFFFF)) => {}
+            _ => panic!("Oversized length prefix must be rejected"),
+        }
+    }
+
+    #[test]
+    fn test_vec_bounded() {
+        let vec = vec![0xAAu8, 0xBB, 0xCC];
+        let encoded = strict_encode(&vec).unwrap();
+        assert_eq!(
+            strict_decode_vec_bounded::<u8, _>(&encoded[..], 3).unwrap(),
+            vec
+        );
+        match strict_decode_vec_bounded::<u8, _>(&encoded[..], 2) {
+            Err(Error::ExceedMaxItems(3)) => {}
+            _ => panic!("Vec exceeding item limit must not be decoded"),
+        }
+        match strict_decode_vec_bounded::<u8, _>(&[0xFFu8, 0xFF][..], 16) {
+            Err(Error::ExceedMaxItems(0xFFFF)) => {}
+            _ => panic!("Oversized length prefix must be rejected"),
+        }
+    }
+
     #[test]
     #[should_panic(expected = "DataNotEntirelyConsumed")]
     fn test_consumation() {
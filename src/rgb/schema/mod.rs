@@ -11,25 +11,33 @@
 // along with this software.
 // If not, see <https://opensource.org/licenses/MIT>.
 
+mod names;
 mod nodes;
 mod schema;
 pub mod script;
 mod state;
 mod types;
+mod verifier;
 
 pub(self) use super::vm;
+pub use names::TypeNames;
 pub use nodes::{
-    AssignmentsType, GenesisSchema, MetadataStructure, SealsStructure, TransitionSchema,
+    merge_seals, GenesisSchema, GenesisSchemaBuilder, MergeError, MetadataStructure,
+    OccurencesChange, SchemaBuilderError, SchemaDiff, SchemaExtension, SchemaValidationError,
+    SealsStructure, StructureDiff, StructureViolation, TransitionSchema, ValidationOperation,
+    MAX_SCHEMA_ITEMS, MAX_STRUCTURE_ITEMS,
 };
-pub use schema::{FieldType, Schema, SchemaId, TransitionType};
+pub use schema::{decode_schema, Schema, SchemaCatalog, SchemaId, MAX_CATALOG_SCHEMATA};
 pub use script::{
-    AssignmentAbi, AssignmentAction, GenesisAbi, GenesisAction, SimplicityScript, TransitionAbi,
-    TransitionAction,
+    Abi, AssignmentAbi, AssignmentAction, GenesisAbi, GenesisAction, SchemaScript,
+    SimplicityScript, TransitionAbi, TransitionAction,
 };
 pub use state::{DataFormat, DiscreteFiniteFieldFormat, StateFormat, StateSchema, StateType};
 pub use types::{
-    elliptic_curve, Bits, DigestAlgorithm, EllipticCurve, Occurences, OccurrencesError,
+    elliptic_curve, AssignmentsType, Bits, DigestAlgorithm, EllipticCurve, FieldType, Occurences,
+    OccurrencesError, OccurrencesMismatch, OccurrencesParseError, TransitionType,
 };
+pub use verifier::{SchemaVerifier, SealPoint, VerifierError};
 
 #[cfg(test)]
 pub(crate) use schema::test;
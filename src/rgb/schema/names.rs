@@ -0,0 +1,164 @@
+// LNP/BP Rust Library
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Human-readable names of the schema field and assignment types, used for
+//! presenting schema data and validation failures to the users. Names are not
+//! a part of the schema commitment and are not strict-encoded.
+
+use std::collections::BTreeMap;
+
+use super::{AssignmentsType, FieldType};
+use crate::rgb::validation::Failure;
+
+/// Registry of human-readable names for [FieldType] and [AssignmentsType]
+/// values. Types without registered names are presented by their numbers,
+/// like `field 3` or `assignment 1`.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TypeNames {
+    pub fields: BTreeMap<FieldType, String>,
+    pub assignments: BTreeMap<AssignmentsType, String>,
+}
+
+impl TypeNames {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers name for the metadata field type, replacing the previous
+    /// one, if any
+    pub fn with_field(mut self, field_type: FieldType, name: &str) -> Self {
+        self.fields.insert(field_type, name.to_string());
+        self
+    }
+
+    /// Registers name for the assignment type, replacing the previous one, if
+    /// any
+    pub fn with_assignment(mut self, assignment_type: AssignmentsType, name: &str) -> Self {
+        self.assignments.insert(assignment_type, name.to_string());
+        self
+    }
+
+    /// Returns registered name of the metadata field type, or `field <type>`
+    /// if the name is not known
+    pub fn field_name(&self, field_type: FieldType) -> String {
+        self.fields
+            .get(&field_type)
+            .cloned()
+            .unwrap_or_else(|| format!("field {}", field_type.0))
+    }
+
+    /// Returns registered name of the assignment type, or
+    /// `assignment <type>` if the name is not known
+    pub fn assignment_name(&self, assignment_type: AssignmentsType) -> String {
+        self.assignments
+            .get(&assignment_type)
+            .cloned()
+            .unwrap_or_else(|| format!("assignment {}", assignment_type.0))
+    }
+
+    /// Describes validation failure using the registered type names. Failures
+    /// which do not refer to field or assignment types are presented by their
+    /// [std::fmt::Display] implementation.
+    pub fn describe_failure(&self, failure: &Failure) -> String {
+        match failure {
+            Failure::SchemaUnknownFieldType(node_id, field_type) => format!(
+                "node {} contains unknown {}",
+                node_id,
+                self.field_name(*field_type)
+            ),
+            Failure::SchemaUnknownAssignmentType(node_id, assignment_type) => format!(
+                "node {} contains unknown {}",
+                node_id,
+                self.assignment_name(*assignment_type)
+            ),
+            Failure::SchemaMetaOccurencesError(node_id, field_type, err) => format!(
+                "node {} has wrong number of {} values: {}",
+                node_id,
+                self.field_name(*field_type),
+                err
+            ),
+            Failure::SchemaAncestorsOccurencesError(node_id, assignment_type, err) => format!(
+                "node {} closes wrong number of {} seals: {}",
+                node_id,
+                self.assignment_name(*assignment_type),
+                err
+            ),
+            Failure::SchemaSealsOccurencesError(node_id, assignment_type, err) => format!(
+                "node {} defines wrong number of {} seals: {}",
+                node_id,
+                self.assignment_name(*assignment_type),
+                err
+            ),
+            _ => failure.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rgb::schema::OccurrencesError;
+    use crate::rgb::NodeId;
+    use bitcoin::hashes::Hash;
+
+    #[test]
+    fn test_type_names() {
+        let names = TypeNames::new()
+            .with_field(FieldType(3), "ticker")
+            .with_assignment(AssignmentsType(1), "assets");
+        assert_eq!(names.field_name(FieldType(3)), "ticker");
+        assert_eq!(names.field_name(FieldType(4)), "field 4");
+        assert_eq!(names.assignment_name(AssignmentsType(1)), "assets");
+        assert_eq!(names.assignment_name(AssignmentsType(0)), "assignment 0");
+        assert_eq!(
+            names
+                .clone()
+                .with_field(FieldType(3), "symbol")
+                .field_name(FieldType(3)),
+            "symbol"
+        );
+
+        let node_id = NodeId::hash(b"node");
+        let described =
+            names.describe_failure(&Failure::SchemaUnknownFieldType(node_id, FieldType(3)));
+        assert!(described.contains("ticker"));
+        assert!(!described.contains("field 3"));
+        assert!(names
+            .describe_failure(&Failure::SchemaUnknownFieldType(node_id, FieldType(4)))
+            .contains("field 4"));
+
+        let err = OccurrencesError {
+            min: 1,
+            max: 1,
+            found: 2,
+        };
+        let described = names.describe_failure(&Failure::SchemaSealsOccurencesError(
+            node_id,
+            AssignmentsType(1),
+            err,
+        ));
+        assert!(described.contains("assets"));
+        assert!(described.contains(&err.to_string()));
+        assert!(TypeNames::default()
+            .describe_failure(&Failure::SchemaAncestorsOccurencesError(
+                node_id,
+                AssignmentsType(1),
+                err,
+            ))
+            .contains("assignment 1"));
+
+        let failure = Failure::SchemaDeniedScriptExtension(node_id);
+        assert_eq!(names.describe_failure(&failure), failure.to_string());
+    }
+}
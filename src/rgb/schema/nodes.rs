@@ -12,44 +12,630 @@
 // If not, see <https://opensource.org/licenses/MIT>.
 
 use std::collections::BTreeMap;
+use std::fmt;
 use std::io;
 
-use super::{FieldType, GenesisAbi, Occurences, TransitionAbi};
+use super::{
+    AssignmentsType, FieldType, GenesisAbi, Occurences, OccurrencesError, SchemaScript,
+    TransitionAbi,
+};
+
+/// Maximum number of entries in [MetadataStructure] and [SealsStructure]
+/// which are accepted during strict decoding of the schema
+pub const MAX_STRUCTURE_ITEMS: usize = 1024;
+
+/// Maximum combined number of entries in all [MetadataStructure] and
+/// [SealsStructure] of a single node schema ([GenesisSchema] or
+/// [TransitionSchema]) which are accepted during strict decoding
+pub const MAX_SCHEMA_ITEMS: usize = 1536;
 
-pub type AssignmentsType = usize; // Here we can use usize since encoding/decoding makes sure that it's u16
 pub type MetadataStructure = BTreeMap<FieldType, Occurences<u16>>;
 pub type SealsStructure = BTreeMap<AssignmentsType, Occurences<u16>>;
 
 #[derive(Clone, PartialEq, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[display_from(Debug)]
 pub struct GenesisSchema {
     pub metadata: MetadataStructure,
     pub defines: SealsStructure,
     pub abi: GenesisAbi,
+    pub extension: Option<SchemaExtension>,
 }
 
 #[derive(Clone, PartialEq, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[display_from(Debug)]
 pub struct TransitionSchema {
     pub metadata: MetadataStructure,
     pub closes: SealsStructure,
     pub defines: SealsStructure,
     pub abi: TransitionAbi,
+    pub extension: Option<SchemaExtension>,
+}
+
+/// Extension data of [GenesisSchema] and [TransitionSchema]
+#[derive(Clone, PartialEq, Eq, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
+#[display_from(Debug)]
+pub enum SchemaExtension {
+    /// Extended scripting information (like ABI extensions)
+    Script(SchemaScript),
+    /// Non-critical extension unknown to the current implementation with its
+    /// feature byte and data, which are kept to re-encode the schema without
+    /// changes
+    Unknown(u8, Vec<u8>),
+}
+
+/// Single violation of the node structure defined by the schema
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StructureViolation {
+    /// Metadata field type is not defined by the node schema
+    UnknownFieldType(FieldType),
+    /// Number of metadata field values does not match the schema
+    MetadataOccurences(FieldType, OccurrencesError),
+    /// Closed seal type is not allowed to be closed by the node schema
+    UnknownClosedSealType(AssignmentsType),
+    /// Number of closed seals does not match the schema
+    ClosedSealsOccurences(AssignmentsType, OccurrencesError),
+    /// Defined seal type is not allowed to be defined by the node schema
+    UnknownDefinedSealType(AssignmentsType),
+    /// Number of defined seals does not match the schema
+    DefinedSealsOccurences(AssignmentsType, OccurrencesError),
+}
+
+impl fmt::Display for StructureViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn occurrences(
+            f: &mut fmt::Formatter<'_>,
+            what: &str,
+            ty: u16,
+            err: &OccurrencesError,
+        ) -> fmt::Result {
+            write!(
+                f,
+                "{} type {} occurs {} times, while the schema requires from {} to {}",
+                what, ty, err.found, err.min, err.max
+            )
+        }
+
+        match self {
+            StructureViolation::UnknownFieldType(ty) => write!(
+                f,
+                "metadata field type {} is not defined by the schema",
+                ty.0
+            ),
+            StructureViolation::MetadataOccurences(ty, err) => {
+                occurrences(f, "metadata field", ty.0, err)
+            }
+            StructureViolation::UnknownClosedSealType(ty) => write!(
+                f,
+                "closing seals of type {} is not allowed by the schema",
+                ty.0
+            ),
+            StructureViolation::ClosedSealsOccurences(ty, err) => {
+                occurrences(f, "closed seal", ty.0, err)
+            }
+            StructureViolation::UnknownDefinedSealType(ty) => write!(
+                f,
+                "defining seals of type {} is not allowed by the schema",
+                ty.0
+            ),
+            StructureViolation::DefinedSealsOccurences(ty, err) => {
+                occurrences(f, "defined seal", ty.0, err)
+            }
+        }
+    }
+}
+
+/// Validation procedure which has reported [SchemaValidationError]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display_from(Debug)]
+pub enum ValidationOperation {
+    /// Genesis metadata validation with [GenesisSchema::validate_metadata]
+    GenesisMetadata,
+    /// Genesis seal definitions validation with
+    /// [GenesisSchema::validate_seals]
+    GenesisSeals,
+    /// State transition validation with [TransitionSchema::validate]
+    Transition,
+}
+
+/// Aggregated list of all node structure violations found during validation
+#[derive(Clone, PartialEq, Eq, Debug, Error)]
+pub struct SchemaValidationError {
+    /// Validation procedure which has found the violations
+    pub operation: ValidationOperation,
+    pub violations: Vec<StructureViolation>,
+}
+
+impl fmt::Display for SchemaValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.operation {
+            ValidationOperation::GenesisMetadata => f.write_str("genesis metadata")?,
+            ValidationOperation::GenesisSeals => f.write_str("genesis seals")?,
+            ValidationOperation::Transition => f.write_str("state transition")?,
+        }
+        f.write_str(" does not match the schema: ")?;
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the provided number of items against the occurrence rules of the
+/// structure, collecting violations for items which are not defined by the
+/// structure or have wrong number of occurrences
+fn check_structure<K>(
+    structure: &BTreeMap<K, Occurences<u16>>,
+    provided: &BTreeMap<K, u16>,
+    unknown: impl Fn(K) -> StructureViolation,
+    mismatch: impl Fn(K, OccurrencesError) -> StructureViolation,
+    violations: &mut Vec<StructureViolation>,
+) where
+    K: Ord + Copy,
+{
+    provided
+        .keys()
+        .filter(|key| !structure.contains_key(key))
+        .for_each(|key| violations.push(unknown(*key)));
+
+    for (key, occ) in structure {
+        let count = provided.get(key).copied().unwrap_or(0);
+        if let Err(err) = occ.check(count) {
+            violations.push(mismatch(*key, err));
+        }
+    }
+}
+
+impl GenesisSchema {
+    /// Checks number of values provided for each of metadata fields against
+    /// the schema, reporting all found violations
+    pub fn validate_metadata(
+        &self,
+        provided: &BTreeMap<FieldType, u16>,
+    ) -> Result<(), SchemaValidationError> {
+        let mut violations = vec![];
+        check_structure(
+            &self.metadata,
+            provided,
+            StructureViolation::UnknownFieldType,
+            StructureViolation::MetadataOccurences,
+            &mut violations,
+        );
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(SchemaValidationError {
+                operation: ValidationOperation::GenesisMetadata,
+                violations,
+            })
+        }
+    }
+
+    /// Checks number of seals defined by the genesis for each of the
+    /// assignment types against the schema, reporting all found violations
+    pub fn validate_seals(
+        &self,
+        provided: &BTreeMap<AssignmentsType, u16>,
+    ) -> Result<(), SchemaValidationError> {
+        let mut violations = vec![];
+        check_structure(
+            &self.defines,
+            provided,
+            StructureViolation::UnknownDefinedSealType,
+            StructureViolation::DefinedSealsOccurences,
+            &mut violations,
+        );
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(SchemaValidationError {
+                operation: ValidationOperation::GenesisSeals,
+                violations,
+            })
+        }
+    }
+}
+
+/// Errors happening during construction of the node schema with
+/// [GenesisSchemaBuilder]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Error)]
+#[display_from(Debug)]
+pub enum SchemaBuilderError {
+    /// Metadata field type was declared more than once
+    DuplicateFieldType(FieldType),
+    /// Seal type was defined more than once
+    DuplicateSealType(AssignmentsType),
+}
+
+/// Builder for [GenesisSchema] allowing to declare metadata fields and seal
+/// definitions one by one instead of constructing the maps manually.
+/// Duplicated declarations are reported by [GenesisSchemaBuilder::build].
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct GenesisSchemaBuilder {
+    metadata: MetadataStructure,
+    defines: SealsStructure,
+    abi: GenesisAbi,
+    extension: Option<SchemaExtension>,
+    error: Option<SchemaBuilderError>,
+}
+
+impl GenesisSchemaBuilder {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares metadata field of the given type with the allowed number of
+    /// occurrences
+    pub fn add_metadata(mut self, field_type: FieldType, occurences: Occurences<u16>) -> Self {
+        if self.metadata.insert(field_type, occurences).is_some() {
+            self.error
+                .get_or_insert(SchemaBuilderError::DuplicateFieldType(field_type));
+        }
+        self
+    }
+
+    /// Declares seal type defined by the genesis with the allowed number of
+    /// occurrences
+    pub fn define_seal(mut self, seal_type: AssignmentsType, occurences: Occurences<u16>) -> Self {
+        if self.defines.insert(seal_type, occurences).is_some() {
+            self.error
+                .get_or_insert(SchemaBuilderError::DuplicateSealType(seal_type));
+        }
+        self
+    }
+
+    /// Sets genesis ABI, replacing any previously set one
+    #[inline]
+    pub fn abi(mut self, abi: GenesisAbi) -> Self {
+        self.abi = abi;
+        self
+    }
+
+    /// Sets extended scripting information, replacing any previously set one
+    #[inline]
+    pub fn script(mut self, script: SchemaScript) -> Self {
+        self.extension = Some(SchemaExtension::Script(script));
+        self
+    }
+
+    /// Constructs [GenesisSchema]; fails with the first duplicated
+    /// declaration, if any
+    pub fn build(self) -> Result<GenesisSchema, SchemaBuilderError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        Ok(GenesisSchema {
+            metadata: self.metadata,
+            defines: self.defines,
+            abi: self.abi,
+            extension: self.extension,
+        })
+    }
+}
+
+impl TransitionSchema {
+    /// Checks number of metadata field values, closed and defined seals of
+    /// the state transition against the schema, reporting all found
+    /// violations
+    pub fn validate(
+        &self,
+        metadata: &BTreeMap<FieldType, u16>,
+        closes: &BTreeMap<AssignmentsType, u16>,
+        defines: &BTreeMap<AssignmentsType, u16>,
+    ) -> Result<(), SchemaValidationError> {
+        let mut violations = vec![];
+        check_structure(
+            &self.metadata,
+            metadata,
+            StructureViolation::UnknownFieldType,
+            StructureViolation::MetadataOccurences,
+            &mut violations,
+        );
+        check_structure(
+            &self.closes,
+            closes,
+            StructureViolation::UnknownClosedSealType,
+            StructureViolation::ClosedSealsOccurences,
+            &mut violations,
+        );
+        check_structure(
+            &self.defines,
+            defines,
+            StructureViolation::UnknownDefinedSealType,
+            StructureViolation::DefinedSealsOccurences,
+            &mut violations,
+        );
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(SchemaValidationError {
+                operation: ValidationOperation::Transition,
+                violations,
+            })
+        }
+    }
+}
+
+/// Error merging schema structures with [merge_seals]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display, Error)]
+#[display_from(Debug)]
+pub enum MergeError {
+    /// The same seal type is defined in both structures with different
+    /// occurrence bounds
+    ConflictingSeal {
+        seal_type: AssignmentsType,
+        first: Occurences<u16>,
+        second: Occurences<u16>,
+    },
+}
+
+/// Unions two seal structures, e.g. ones coming from reusable schema
+/// fragments. Seal types present in both structures must have the same
+/// minimal and maximal number of occurrences, otherwise
+/// [MergeError::ConflictingSeal] is returned. If the same bounds are defined
+/// with different variants (like `Once` and `OnceOrUpTo(Some(1))`), the
+/// variant from the first structure is kept.
+pub fn merge_seals(a: &SealsStructure, b: &SealsStructure) -> Result<SealsStructure, MergeError> {
+    let mut merged = a.clone();
+    for (seal_type, occurences) in b {
+        match merged.get(seal_type) {
+            Some(existing)
+                if existing.min_value() != occurences.min_value()
+                    || existing.max_value() != occurences.max_value() =>
+            {
+                Err(MergeError::ConflictingSeal {
+                    seal_type: *seal_type,
+                    first: *existing,
+                    second: *occurences,
+                })?
+            }
+            Some(_) => {}
+            None => {
+                merged.insert(*seal_type, *occurences);
+            }
+        }
+    }
+    Ok(merged)
+}
+
+/// Change of the occurrence bounds for some schema structure item
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Display)]
+#[display_from(Debug)]
+pub struct OccurencesChange {
+    pub from: Occurences<u16>,
+    pub to: Occurences<u16>,
+}
+
+impl OccurencesChange {
+    /// Detects whether the new bounds are more restrictive than the old
+    /// ones, i.e. some data valid under the old schema will be rejected by
+    /// the new one
+    pub fn is_tightened(&self) -> bool {
+        self.to.min_value() > self.from.min_value() || self.to.max_value() < self.from.max_value()
+    }
+}
+
+/// Differences between two versions of the same schema structure
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StructureDiff<K>
+where
+    K: Ord,
+{
+    /// Items present only in the new structure
+    pub added: BTreeMap<K, Occurences<u16>>,
+    /// Items present only in the old structure
+    pub removed: BTreeMap<K, Occurences<u16>>,
+    /// Items present in both structures with different occurrence bounds
+    pub changed: BTreeMap<K, OccurencesChange>,
+}
+
+impl<K> StructureDiff<K>
+where
+    K: Ord + Copy,
+{
+    fn with(old: &BTreeMap<K, Occurences<u16>>, new: &BTreeMap<K, Occurences<u16>>) -> Self {
+        let mut diff = Self {
+            added: BTreeMap::new(),
+            removed: BTreeMap::new(),
+            changed: BTreeMap::new(),
+        };
+        for (key, occ) in old {
+            match new.get(key) {
+                None => {
+                    diff.removed.insert(*key, *occ);
+                }
+                Some(new_occ) if new_occ != occ => {
+                    diff.changed.insert(
+                        *key,
+                        OccurencesChange {
+                            from: *occ,
+                            to: *new_occ,
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+        for (key, occ) in new {
+            if !old.contains_key(key) {
+                diff.added.insert(*key, *occ);
+            }
+        }
+        diff
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Differences between two versions of the node schema, reported by
+/// [GenesisSchema::diff] and [TransitionSchema::diff]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SchemaDiff {
+    pub metadata: StructureDiff<FieldType>,
+    /// Always empty for the genesis schema
+    pub closes: StructureDiff<AssignmentsType>,
+    pub defines: StructureDiff<AssignmentsType>,
+    pub abi_changed: bool,
+    pub extension_changed: bool,
+}
+
+impl SchemaDiff {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.metadata.is_empty()
+            && self.closes.is_empty()
+            && self.defines.is_empty()
+            && !self.abi_changed
+            && !self.extension_changed
+    }
+}
+
+impl GenesisSchema {
+    /// Reports changes made to the schema in its `other` (newer) version
+    pub fn diff(&self, other: &Self) -> SchemaDiff {
+        SchemaDiff {
+            metadata: StructureDiff::with(&self.metadata, &other.metadata),
+            closes: StructureDiff::with(&BTreeMap::new(), &BTreeMap::new()),
+            defines: StructureDiff::with(&self.defines, &other.defines),
+            abi_changed: self.abi != other.abi,
+            extension_changed: self.extension != other.extension,
+        }
+    }
+}
+
+impl TransitionSchema {
+    /// Reports changes made to the schema in its `other` (newer) version
+    pub fn diff(&self, other: &Self) -> SchemaDiff {
+        SchemaDiff {
+            metadata: StructureDiff::with(&self.metadata, &other.metadata),
+            closes: StructureDiff::with(&self.closes, &other.closes),
+            defines: StructureDiff::with(&self.defines, &other.defines),
+            abi_changed: self.abi != other.abi,
+            extension_changed: self.extension != other.extension,
+        }
+    }
 }
 
 mod strict_encoding {
     use super::*;
-    use crate::strict_encoding::{Error, StrictDecode, StrictEncode};
+    use crate::strict_encoding::{strict_decode_map_bounded, Error, StrictDecode, StrictEncode};
+    use amplify::Wrapper;
+
+    /// Extension byte string of the node schema starts with a feature byte
+    /// defining the type of the extension data which follows. Empty byte
+    /// string means there are no extensions.
+    const EXTENSION_SCRIPT: u8 = 0x01;
+    /// Feature bytes with this bit set denote extensions which can't be
+    /// ignored by the decoders not aware of them. Unknown extensions without
+    /// this bit are skipped.
+    const EXTENSION_CRITICAL_FLAG: u8 = 0x80;
+
+    /// Genesis and state transition schema encodings start with a version
+    /// byte defining the layout of the data which follow; this is the only
+    /// version known to the current implementation
+    const ENCODING_VERSION_0: u8 = 0x00;
+
+    fn unsupported_version(version: u8) -> Error {
+        Error::UnsupportedDataStructure(format!("Unknown schema node encoding version {}", version))
+    }
+
+    /// Decodes metadata or seals structure within the remaining budget of
+    /// [MAX_SCHEMA_ITEMS], which is reduced by the number of decoded entries
+    fn decode_structure<K, D>(
+        d: D,
+        budget: &mut usize,
+    ) -> Result<BTreeMap<K, Occurences<u16>>, Error>
+    where
+        D: io::Read,
+        K: StrictDecode<Error = Error> + Ord + Clone,
+    {
+        let structure = strict_decode_map_bounded(d, MAX_STRUCTURE_ITEMS.min(*budget))?;
+        *budget -= structure.len();
+        Ok(structure)
+    }
+
+    fn encode_extension<E: io::Write>(
+        extension: &Option<SchemaExtension>,
+        e: E,
+    ) -> Result<usize, Error> {
+        let data = match extension {
+            Some(SchemaExtension::Script(script)) => {
+                let mut data = vec![EXTENSION_SCRIPT];
+                data.extend(script.as_inner());
+                data
+            }
+            // Feature bytes of the known or critical extensions would be
+            // decoded into a different extension
+            Some(SchemaExtension::Unknown(feature, _))
+                if *feature == EXTENSION_SCRIPT || feature & EXTENSION_CRITICAL_FLAG != 0 =>
+            {
+                Err(Error::UnsupportedDataStructure(format!(
+                    "Schema extension feature {:#04x} can't be used for unknown extensions",
+                    feature
+                )))?
+            }
+            Some(SchemaExtension::Unknown(feature, extension_data)) => {
+                let mut data = vec![*feature];
+                data.extend(extension_data);
+                data
+            }
+            None => vec![],
+        };
+        data.strict_encode(e)
+    }
+
+    fn decode_extension<D: io::Read>(d: D) -> Result<Option<SchemaExtension>, Error> {
+        let data = Vec::<u8>::strict_decode(d)?;
+        match data.split_first() {
+            None => Ok(None),
+            Some((&EXTENSION_SCRIPT, script)) => Ok(Some(SchemaExtension::Script(
+                SchemaScript::from_inner(script.to_vec()),
+            ))),
+            Some((feature, _)) if feature & EXTENSION_CRITICAL_FLAG != 0 => {
+                Err(Error::UnsupportedDataStructure(format!(
+                    "Unknown critical schema extension {:#04x}",
+                    feature
+                )))
+            }
+            // Unknown non-critical extensions are kept as they are, so the
+            // schema is re-encoded into the same bytes
+            Some((feature, extension_data)) => Ok(Some(SchemaExtension::Unknown(
+                *feature,
+                extension_data.to_vec(),
+            ))),
+        }
+    }
 
     impl StrictEncode for GenesisSchema {
         type Error = Error;
 
         fn strict_encode<E: io::Write>(&self, mut e: E) -> Result<usize, Error> {
-            self.metadata.strict_encode(&mut e)?;
-            self.defines.strict_encode(&mut e)?;
-            self.abi.strict_encode(&mut e)?;
-            // We keep this parameter for future script extended info (like ABI)
-            Vec::<u8>::new().strict_encode(&mut e)
+            let mut len = ENCODING_VERSION_0.strict_encode(&mut e)?;
+            len += self.metadata.strict_encode(&mut e)?;
+            len += self.defines.strict_encode(&mut e)?;
+            len += self.abi.strict_encode(&mut e)?;
+            Ok(len + encode_extension(&self.extension, &mut e)?)
         }
     }
 
@@ -57,19 +643,17 @@ mod strict_encoding {
         type Error = Error;
 
         fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, Error> {
-            let me = Self {
-                metadata: MetadataStructure::strict_decode(&mut d)?,
-                defines: SealsStructure::strict_decode(&mut d)?,
-                abi: GenesisAbi::strict_decode(&mut d)?,
-            };
-            // We keep this parameter for future script extended info (like ABI)
-            let script = Vec::<u8>::strict_decode(&mut d)?;
-            if !script.is_empty() {
-                Err(Error::UnsupportedDataStructure(
-                    "Scripting information is not yet supported".to_string(),
-                ))
-            } else {
-                Ok(me)
+            match u8::strict_decode(&mut d)? {
+                ENCODING_VERSION_0 => {
+                    let mut budget = MAX_SCHEMA_ITEMS;
+                    Ok(Self {
+                        metadata: decode_structure(&mut d, &mut budget)?,
+                        defines: decode_structure(&mut d, &mut budget)?,
+                        abi: GenesisAbi::strict_decode(&mut d)?,
+                        extension: decode_extension(&mut d)?,
+                    })
+                }
+                version => Err(unsupported_version(version)),
             }
         }
     }
@@ -78,12 +662,12 @@ mod strict_encoding {
         type Error = Error;
 
         fn strict_encode<E: io::Write>(&self, mut e: E) -> Result<usize, Error> {
-            self.metadata.strict_encode(&mut e)?;
-            self.closes.strict_encode(&mut e)?;
-            self.defines.strict_encode(&mut e)?;
-            self.abi.strict_encode(&mut e)?;
-            // We keep this parameter for future script extended info (like ABI)
-            Vec::<u8>::new().strict_encode(&mut e)
+            let mut len = ENCODING_VERSION_0.strict_encode(&mut e)?;
+            len += self.metadata.strict_encode(&mut e)?;
+            len += self.closes.strict_encode(&mut e)?;
+            len += self.defines.strict_encode(&mut e)?;
+            len += self.abi.strict_encode(&mut e)?;
+            Ok(len + encode_extension(&self.extension, &mut e)?)
         }
     }
 
@@ -91,21 +675,707 @@ mod strict_encoding {
         type Error = Error;
 
         fn strict_decode<D: io::Read>(mut d: D) -> Result<Self, Error> {
-            let me = Self {
-                metadata: MetadataStructure::strict_decode(&mut d)?,
-                closes: SealsStructure::strict_decode(&mut d)?,
-                defines: SealsStructure::strict_decode(&mut d)?,
-                abi: TransitionAbi::strict_decode(&mut d)?,
+            match u8::strict_decode(&mut d)? {
+                ENCODING_VERSION_0 => {
+                    let mut budget = MAX_SCHEMA_ITEMS;
+                    Ok(Self {
+                        metadata: decode_structure(&mut d, &mut budget)?,
+                        closes: decode_structure(&mut d, &mut budget)?,
+                        defines: decode_structure(&mut d, &mut budget)?,
+                        abi: TransitionAbi::strict_decode(&mut d)?,
+                        extension: decode_extension(&mut d)?,
+                    })
+                }
+                version => Err(unsupported_version(version)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use amplify::Wrapper;
+
+    fn genesis_schema() -> GenesisSchema {
+        GenesisSchema {
+            metadata: bmap! {
+                FieldType(0) => Occurences::Once,
+                FieldType(1) => Occurences::NoneOrOnce,
+                FieldType(2) => Occurences::OnceOrUpTo(Some(3))
+            },
+            defines: bmap! {},
+            abi: bmap! {},
+            extension: None,
+        }
+    }
+
+    #[test]
+    fn test_genesis_schema_builder() {
+        let schema = GenesisSchemaBuilder::new()
+            .add_metadata(FieldType(0), Occurences::Once)
+            .add_metadata(FieldType(2), Occurences::OnceOrUpTo(Some(3)))
+            .add_metadata(FieldType(1), Occurences::NoneOrOnce)
+            .build()
+            .unwrap();
+        assert_eq!(schema, genesis_schema());
+
+        let schema = GenesisSchemaBuilder::new()
+            .define_seal(AssignmentsType(1), Occurences::NoneOrUpTo(None))
+            .abi(bmap! {})
+            .script(SchemaScript::from_inner(vec![0x01]))
+            .build()
+            .unwrap();
+        assert_eq!(
+            schema,
+            GenesisSchema {
+                metadata: bmap! {},
+                defines: bmap! { AssignmentsType(1) => Occurences::NoneOrUpTo(None) },
+                abi: bmap! {},
+                extension: Some(SchemaExtension::Script(SchemaScript::from_inner(vec![
+                    0x01
+                ]))),
+            }
+        );
+
+        assert_eq!(
+            GenesisSchemaBuilder::new()
+                .add_metadata(FieldType(0), Occurences::Once)
+                .define_seal(AssignmentsType(0), Occurences::Once)
+                .add_metadata(FieldType(0), Occurences::NoneOrOnce)
+                .define_seal(AssignmentsType(0), Occurences::NoneOrOnce)
+                .build(),
+            Err(SchemaBuilderError::DuplicateFieldType(FieldType(0)))
+        );
+        assert_eq!(
+            GenesisSchemaBuilder::new()
+                .define_seal(AssignmentsType(3), Occurences::Once)
+                .define_seal(AssignmentsType(3), Occurences::Once)
+                .build(),
+            Err(SchemaBuilderError::DuplicateSealType(AssignmentsType(3)))
+        );
+    }
+
+    #[test]
+    fn test_genesis_metadata_valid() {
+        let schema = genesis_schema();
+        schema
+            .validate_metadata(&bmap! { FieldType(0) => 1u16, FieldType(2) => 3u16 })
+            .unwrap();
+        schema
+            .validate_metadata(
+                &bmap! { FieldType(0) => 1u16, FieldType(1) => 1u16, FieldType(2) => 1u16 },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_genesis_metadata_missing_fields() {
+        let schema = genesis_schema();
+        let err = schema
+            .validate_metadata(&bmap! { FieldType(1) => 1u16 })
+            .unwrap_err();
+        assert_eq!(
+            err.violations,
+            vec![
+                StructureViolation::MetadataOccurences(
+                    FieldType(0),
+                    OccurrencesError {
+                        min: 1,
+                        max: 1,
+                        found: 0
+                    }
+                ),
+                StructureViolation::MetadataOccurences(
+                    FieldType(2),
+                    OccurrencesError {
+                        min: 1,
+                        max: 3,
+                        found: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_genesis_metadata_surplus_fields() {
+        let schema = genesis_schema();
+        let err = schema
+            .validate_metadata(&bmap! {
+                FieldType(0) => 2u16,
+                FieldType(2) => 1u16,
+                FieldType(5) => 1u16,
+                FieldType(7) => 0u16
+            })
+            .unwrap_err();
+        assert_eq!(
+            err.violations,
+            vec![
+                StructureViolation::UnknownFieldType(FieldType(5)),
+                StructureViolation::UnknownFieldType(FieldType(7)),
+                StructureViolation::MetadataOccurences(
+                    FieldType(0),
+                    OccurrencesError {
+                        min: 1,
+                        max: 1,
+                        found: 2
+                    }
+                ),
+            ]
+        );
+    }
+
+    fn transition_schema() -> TransitionSchema {
+        TransitionSchema {
+            metadata: bmap! {
+                FieldType(0) => Occurences::NoneOrOnce
+            },
+            closes: bmap! {
+                AssignmentsType(0) => Occurences::OnceOrUpTo(None)
+            },
+            defines: bmap! {
+                AssignmentsType(0) => Occurences::NoneOrUpTo(None),
+                AssignmentsType(1) => Occurences::Once
+            },
+            abi: bmap! {},
+            extension: None,
+        }
+    }
+
+    #[test]
+    fn test_genesis_diff_added_field() {
+        let old = genesis_schema();
+        assert!(old.diff(&old).is_empty());
+
+        let mut new = genesis_schema();
+        new.metadata
+            .insert(FieldType(3), Occurences::NoneOrUpTo(None));
+        let diff = old.diff(&new);
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.metadata.added,
+            bmap! { FieldType(3) => Occurences::NoneOrUpTo(None) }
+        );
+        assert!(diff.metadata.removed.is_empty());
+        assert!(diff.metadata.changed.is_empty());
+        assert!(diff.defines.is_empty());
+
+        // Reverse diff reports the same field as removed
+        let diff = new.diff(&old);
+        assert_eq!(
+            diff.metadata.removed,
+            bmap! { FieldType(3) => Occurences::NoneOrUpTo(None) }
+        );
+        assert!(diff.metadata.added.is_empty());
+    }
+
+    #[test]
+    fn test_transition_diff_tightened_bound() {
+        let old = transition_schema();
+        let mut new = transition_schema();
+        new.defines
+            .insert(AssignmentsType(0), Occurences::NoneOrUpTo(Some(5)));
+        new.metadata
+            .insert(FieldType(0), Occurences::NoneOrUpTo(None));
+
+        let diff = old.diff(&new);
+        assert!(diff.closes.is_empty());
+        assert!(!diff.abi_changed && !diff.extension_changed);
+
+        let change = diff.defines.changed[&AssignmentsType(0)];
+        assert_eq!(
+            change,
+            OccurencesChange {
+                from: Occurences::NoneOrUpTo(None),
+                to: Occurences::NoneOrUpTo(Some(5)),
+            }
+        );
+        assert!(change.is_tightened());
+
+        // Relaxed bound is reported as a change, but not as a tightened one
+        let change = diff.metadata.changed[&FieldType(0)];
+        assert_eq!(change.from, Occurences::NoneOrOnce);
+        assert!(!change.is_tightened());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_serde_json_roundtrip() {
+        let mut genesis = genesis_schema();
+        genesis.extension = Some(SchemaExtension::Script(SchemaScript::from_inner(vec![
+            0xde, 0xad,
+        ])));
+        let json = serde_json::to_string(&genesis).unwrap();
+        assert!(json.contains(r#""0":"1""#));
+        assert!(json.contains(r#""1":"0..1""#));
+        assert!(json.contains(r#""2":"1..3""#));
+        assert!(json.contains(r#""dead""#));
+        assert_eq!(
+            serde_json::from_str::<GenesisSchema>(&json).unwrap(),
+            genesis
+        );
+
+        let transition = transition_schema();
+        let json = serde_json::to_string(&transition).unwrap();
+        assert!(json.contains(r#""closes":{"0":"1..*"}"#));
+        assert!(json.contains(r#""defines":{"0":"*","1":"1"}"#));
+        assert_eq!(
+            serde_json::from_str::<TransitionSchema>(&json).unwrap(),
+            transition
+        );
+
+        let json = r#"{"metadata":{"0":"0..2"},"closes":{},"defines":{"5":"1..65535"},"abi":{},"extension":null}"#;
+        let transition = serde_json::from_str::<TransitionSchema>(json).unwrap();
+        assert_eq!(
+            transition.metadata,
+            bmap! { FieldType(0) => Occurences::NoneOrUpTo(Some(2)) }
+        );
+        assert_eq!(
+            transition.defines,
+            bmap! { AssignmentsType(5) => Occurences::OnceOrUpTo(None) }
+        );
+        assert!(serde_json::from_str::<TransitionSchema>(
+            r#"{"metadata":{"0":"2..1"},"closes":{},"defines":{},"abi":{},"extension":null}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_transition_valid() {
+        transition_schema()
+            .validate(
+                &bmap! {},
+                &bmap! { AssignmentsType(0) => 2u16 },
+                &bmap! { AssignmentsType(0) => 5u16, AssignmentsType(1) => 1u16 },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_transition_closes_unknown_seal() {
+        let err = transition_schema()
+            .validate(
+                &bmap! { FieldType(0) => 2u16 },
+                &bmap! { AssignmentsType(0) => 1u16, AssignmentsType(1) => 1u16 },
+                &bmap! {},
+            )
+            .unwrap_err();
+        assert_eq!(
+            err.violations,
+            vec![
+                StructureViolation::MetadataOccurences(
+                    FieldType(0),
+                    OccurrencesError {
+                        min: 0,
+                        max: 1,
+                        found: 2
+                    }
+                ),
+                StructureViolation::UnknownClosedSealType(AssignmentsType(1)),
+                StructureViolation::DefinedSealsOccurences(
+                    AssignmentsType(1),
+                    OccurrencesError {
+                        min: 1,
+                        max: 1,
+                        found: 0
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validation_error_message() {
+        let err = genesis_schema()
+            .validate_metadata(&bmap! { FieldType(0) => 1u16, FieldType(5) => 1u16 })
+            .unwrap_err();
+        assert_eq!(err.operation, ValidationOperation::GenesisMetadata);
+        assert_eq!(
+            err.to_string(),
+            "genesis metadata does not match the schema: \
+             metadata field type 5 is not defined by the schema; \
+             metadata field type 2 occurs 0 times, while the schema requires from 1 to 3"
+        );
+
+        let err = transition_schema()
+            .validate(
+                &bmap! {},
+                &bmap! { AssignmentsType(0) => 1u16, AssignmentsType(7) => 1u16 },
+                &bmap! { AssignmentsType(1) => 2u16 },
+            )
+            .unwrap_err();
+        assert_eq!(err.operation, ValidationOperation::Transition);
+        let message = err.to_string();
+        assert!(message.starts_with("state transition"));
+        assert!(message.contains("closing seals of type 7"));
+        assert!(message.contains("defined seal type 1 occurs 2 times"));
+    }
+
+    #[test]
+    fn test_script_encoding() {
+        use crate::strict_encoding::{strict_decode, strict_encode};
+
+        let mut genesis = genesis_schema();
+        let encoded = strict_encode(&genesis).unwrap();
+        // Absent script is serialized as an empty byte string
+        assert_eq!(encoded[encoded.len() - 2..], [0u8, 0u8]);
+        assert_eq!(strict_decode::<GenesisSchema>(&encoded).unwrap(), genesis);
+
+        genesis.extension = Some(SchemaExtension::Script(SchemaScript::from_inner(vec![
+            0xde, 0xad, 0xbe, 0xef,
+        ])));
+        let encoded = strict_encode(&genesis).unwrap();
+        assert_eq!(
+            encoded[encoded.len() - 7..],
+            [5u8, 0u8, 0x01, 0xde, 0xad, 0xbe, 0xef]
+        );
+        assert_eq!(strict_decode::<GenesisSchema>(&encoded).unwrap(), genesis);
+
+        let mut transition = transition_schema();
+        let encoded = strict_encode(&transition).unwrap();
+        assert_eq!(
+            strict_decode::<TransitionSchema>(&encoded).unwrap(),
+            transition
+        );
+        transition.extension = Some(SchemaExtension::Script(SchemaScript::from_inner(vec![
+            0x01,
+        ])));
+        let encoded = strict_encode(&transition).unwrap();
+        assert_eq!(
+            strict_decode::<TransitionSchema>(&encoded).unwrap(),
+            transition
+        );
+    }
+
+    #[test]
+    fn test_encoding_version() {
+        use crate::strict_encoding::{strict_decode, strict_encode, Error};
+
+        // Version 0 genesis schema with a single metadata field occuring once
+        // and no seals, ABI or extensions
+        let encoded = vec![
+            0x00u8, 0x01, 0x00, 0x00, 0x00, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ];
+        let genesis = GenesisSchema {
+            metadata: bmap! { FieldType(0) => Occurences::Once },
+            defines: bmap! {},
+            abi: bmap! {},
+            extension: None,
+        };
+        assert_eq!(strict_decode::<GenesisSchema>(&encoded).unwrap(), genesis);
+        assert_eq!(strict_encode(&genesis).unwrap(), encoded);
+
+        let mut unknown = encoded.clone();
+        unknown[0] = 0x01;
+        match strict_decode::<GenesisSchema>(&unknown) {
+            Err(Error::UnsupportedDataStructure(_)) => {}
+            _ => panic!("Unknown encoding version must not be decoded"),
+        }
+
+        let transition = transition_schema();
+        let mut encoded = strict_encode(&transition).unwrap();
+        assert_eq!(encoded[0], 0x00);
+        assert_eq!(
+            strict_decode::<TransitionSchema>(&encoded).unwrap(),
+            transition
+        );
+        encoded[0] = 0xFF;
+        match strict_decode::<TransitionSchema>(&encoded) {
+            Err(Error::UnsupportedDataStructure(_)) => {}
+            _ => panic!("Unknown encoding version must not be decoded"),
+        }
+    }
+
+    #[test]
+    fn test_unknown_extensions() {
+        use crate::strict_encoding::{strict_decode, strict_encode, Error};
+
+        let genesis = genesis_schema();
+        let mut encoded = strict_encode(&genesis).unwrap();
+        // Replacing empty extension data with unknown extensions
+        encoded.truncate(encoded.len() - 2);
+
+        let mut skippable = encoded.clone();
+        skippable.extend(&[4u8, 0u8, 0x02, 0xAA, 0xBB, 0xCC]);
+        let decoded = strict_decode::<GenesisSchema>(&skippable).unwrap();
+        assert_eq!(
+            decoded.extension,
+            Some(SchemaExtension::Unknown(0x02, vec![0xAA, 0xBB, 0xCC]))
+        );
+        assert_eq!(
+            GenesisSchema {
+                extension: None,
+                ..decoded.clone()
+            },
+            genesis
+        );
+        // Unknown extension is re-encoded without changes
+        assert_eq!(strict_encode(&decoded).unwrap(), skippable);
+
+        let mut critical = encoded.clone();
+        critical.extend(&[4u8, 0u8, 0x82, 0xAA, 0xBB, 0xCC]);
+        match strict_decode::<GenesisSchema>(&critical) {
+            Err(Error::UnsupportedDataStructure(_)) => {}
+            _ => panic!("Unknown critical extension must not be decoded"),
+        }
+
+        let transition = transition_schema();
+        let mut encoded = strict_encode(&transition).unwrap();
+        encoded.truncate(encoded.len() - 2);
+        encoded.extend(&[1u8, 0u8, 0x7F]);
+        let decoded = strict_decode::<TransitionSchema>(&encoded).unwrap();
+        assert_eq!(
+            decoded.extension,
+            Some(SchemaExtension::Unknown(0x7F, vec![]))
+        );
+        assert_eq!(strict_encode(&decoded).unwrap(), encoded);
+        assert!(!transition.diff(&decoded).is_empty());
+
+        // Unknown extensions can't use feature bytes of the known or critical
+        // ones, since they would be decoded differently
+        for feature in &[0x01u8, 0x82] {
+            let transition = TransitionSchema {
+                extension: Some(SchemaExtension::Unknown(*feature, vec![0xAA])),
+                ..transition_schema()
             };
-            // We keep this parameter for future script extended info (like ABI)
-            let script = Vec::<u8>::strict_decode(&mut d)?;
-            if !script.is_empty() {
-                Err(Error::UnsupportedDataStructure(
-                    "Scripting information is not yet supported".to_string(),
-                ))
-            } else {
-                Ok(me)
+            match strict_encode(&transition) {
+                Err(Error::UnsupportedDataStructure(_)) => {}
+                _ => panic!("Extension with reserved feature byte must not be encoded"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_metadata_structure_canonical_order() {
+        use crate::strict_encoding::{strict_decode, Error};
+
+        // Two entries with field types 1 and 0 (in this order), both having
+        // `Occurences::Once` (tag 0x01 followed by u64 zero)
+        let mut encoded = vec![2u8, 0u8];
+        encoded.extend(&[1u8, 0u8, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+        encoded.extend(&[0u8, 0u8, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+        match strict_decode::<MetadataStructure>(&encoded) {
+            Err(Error::NonCanonicalOrder(_)) => {}
+            _ => panic!("Non-canonical metadata structure must not be decoded"),
+        }
+
+        // The same entries in canonical order
+        let mut encoded = vec![2u8, 0u8];
+        encoded.extend(&[0u8, 0u8, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+        encoded.extend(&[1u8, 0u8, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(
+            strict_decode::<MetadataStructure>(&encoded).unwrap(),
+            bmap! { FieldType(0) => Occurences::Once, FieldType(1) => Occurences::Once }
+        );
+    }
+
+    #[test]
+    fn test_seals_structure_canonical_order() {
+        use crate::strict_encoding::{strict_decode, strict_encode, Error};
+
+        // Seals structure with entries of the given types, all having
+        // `Occurences::Once`
+        let structure = |types: &[u8]| {
+            let mut data = vec![types.len() as u8, 0u8];
+            for ty in types {
+                data.extend(&[*ty, 0u8, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]);
+            }
+            data
+        };
+        // Version 0 transition schema without metadata, ABI and extensions
+        let transition = |closes: &[u8], defines: &[u8]| {
+            let mut data = vec![0x00u8, 0, 0];
+            data.extend(structure(closes));
+            data.extend(structure(defines));
+            data.extend(&[0u8, 0, 0, 0]);
+            data
+        };
+
+        let canonical = transition(&[0, 1], &[0, 2]);
+        let expected = TransitionSchema {
+            metadata: bmap! {},
+            closes: bmap! {
+                AssignmentsType(0) => Occurences::Once,
+                AssignmentsType(1) => Occurences::Once
+            },
+            defines: bmap! {
+                AssignmentsType(0) => Occurences::Once,
+                AssignmentsType(2) => Occurences::Once
+            },
+            abi: bmap! {},
+            extension: None,
+        };
+        assert_eq!(
+            strict_decode::<TransitionSchema>(&canonical).unwrap(),
+            expected
+        );
+        assert_eq!(strict_encode(&expected).unwrap(), canonical);
+
+        for encoded in &[
+            transition(&[1, 0], &[0, 2]),
+            transition(&[0, 1], &[2, 0]),
+            // Repeated keys are not canonical either
+            transition(&[0, 0], &[0, 2]),
+            transition(&[0, 1], &[2, 2]),
+        ] {
+            match strict_decode::<TransitionSchema>(encoded) {
+                Err(Error::NonCanonicalOrder(_)) => {}
+                _ => panic!("Non-canonical seals structure must not be decoded"),
             }
         }
+
+        // Genesis schema defines are decoded with the same rules
+        let mut genesis = vec![0x00u8, 0, 0];
+        genesis.extend(structure(&[3, 1]));
+        genesis.extend(&[0u8, 0, 0, 0]);
+        match strict_decode::<GenesisSchema>(&genesis) {
+            Err(Error::NonCanonicalOrder(_)) => {}
+            _ => panic!("Non-canonical seals structure must not be decoded"),
+        }
+    }
+
+    #[test]
+    fn test_structure_items_limit() {
+        use crate::strict_encoding::{strict_decode, Error};
+
+        // Metadata structure claiming 0x0500 entries with no data following
+        match strict_decode::<GenesisSchema>(&vec![0x00u8, 0x00, 0x05]) {
+            Err(Error::ExceedMaxItems(0x0500)) => {}
+            _ => panic!("Oversized metadata structure must be rejected"),
+        }
+        // Empty metadata followed by oversized closed seals structure
+        match strict_decode::<TransitionSchema>(&vec![0x00u8, 0x00, 0x00, 0xFF, 0xFF]) {
+            Err(Error::ExceedMaxItems(0xFFFF)) => {}
+            _ => panic!("Oversized seals structure must be rejected"),
+        }
+        // Structure within the limit which lacks the data fails with I/O error
+        match strict_decode::<GenesisSchema>(&vec![0x00u8, 0x00, 0x04]) {
+            Err(Error::Io(_)) => {}
+            _ => panic!("Truncated data must fail"),
+        }
+    }
+
+    #[test]
+    fn test_schema_items_limit() {
+        use crate::strict_encoding::{strict_decode, strict_encode, Error};
+
+        let metadata: MetadataStructure = (0..1000u16)
+            .map(|ty| (FieldType(ty), Occurences::Once))
+            .collect();
+        let seals: SealsStructure = (0..1000u16)
+            .map(|ty| (AssignmentsType(ty), Occurences::Once))
+            .collect();
+        let few_seals: SealsStructure = (0..500u16)
+            .map(|ty| (AssignmentsType(ty), Occurences::Once))
+            .collect();
+
+        // Each of the structures is within MAX_STRUCTURE_ITEMS, but together
+        // they exceed MAX_SCHEMA_ITEMS
+        let genesis = GenesisSchema {
+            metadata: metadata.clone(),
+            defines: seals.clone(),
+            abi: bmap! {},
+            extension: None,
+        };
+        match strict_decode::<GenesisSchema>(&strict_encode(&genesis).unwrap()) {
+            Err(Error::ExceedMaxItems(1000)) => {}
+            _ => panic!("Schema exceeding aggregate item limit must be rejected"),
+        }
+
+        let transition = TransitionSchema {
+            metadata: bmap! {},
+            closes: few_seals.clone(),
+            defines: seals.clone(),
+            abi: bmap! {},
+            extension: None,
+        };
+        match strict_decode::<TransitionSchema>(&strict_encode(&transition).unwrap()) {
+            Err(Error::ExceedMaxItems(1000)) => {}
+            _ => panic!("Schema exceeding aggregate item limit must be rejected"),
+        }
+
+        // Exactly at the aggregate limit
+        let transition = TransitionSchema {
+            metadata: metadata.clone(),
+            closes: few_seals.clone(),
+            defines: bmap! { AssignmentsType(0) => Occurences::Once },
+            abi: bmap! {},
+            extension: None,
+        };
+        match strict_decode::<TransitionSchema>(&strict_encode(&transition).unwrap()) {
+            Err(Error::ExceedMaxItems(1)) => {}
+            _ => panic!("Schema exceeding aggregate item limit must be rejected"),
+        }
+        let transition = TransitionSchema {
+            metadata,
+            closes: few_seals,
+            defines: bmap! {},
+            abi: bmap! {},
+            extension: None,
+        };
+        assert_eq!(
+            strict_decode::<TransitionSchema>(&strict_encode(&transition).unwrap()).unwrap(),
+            transition
+        );
+    }
+
+    #[test]
+    fn test_merge_seals() {
+        let a: SealsStructure = bmap! {
+            AssignmentsType(0) => Occurences::Once,
+            AssignmentsType(1) => Occurences::NoneOrUpTo(None)
+        };
+        let b: SealsStructure = bmap! {
+            AssignmentsType(1) => Occurences::NoneOrUpTo(None),
+            AssignmentsType(2) => Occurences::NoneOrOnce
+        };
+        assert_eq!(
+            merge_seals(&a, &b).unwrap(),
+            bmap! {
+                AssignmentsType(0) => Occurences::Once,
+                AssignmentsType(1) => Occurences::NoneOrUpTo(None),
+                AssignmentsType(2) => Occurences::NoneOrOnce
+            }
+        );
+        assert_eq!(merge_seals(&a, &bmap! {}).unwrap(), a);
+
+        let b: SealsStructure = bmap! {
+            AssignmentsType(0) => Occurences::NoneOrOnce
+        };
+        assert_eq!(
+            merge_seals(&a, &b),
+            Err(MergeError::ConflictingSeal {
+                seal_type: AssignmentsType(0),
+                first: Occurences::Once,
+                second: Occurences::NoneOrOnce,
+            })
+        );
+
+        // Equivalent bounds defined with different variants
+        let b: SealsStructure = bmap! {
+            AssignmentsType(0) => Occurences::OnceOrUpTo(Some(1)),
+            AssignmentsType(1) => Occurences::NoneOrUpTo(Some(u16::MAX))
+        };
+        assert_eq!(merge_seals(&a, &b).unwrap(), a);
+        assert_eq!(
+            merge_seals(&b, &a).unwrap(),
+            bmap! {
+                AssignmentsType(0) => Occurences::OnceOrUpTo(Some(1)),
+                AssignmentsType(1) => Occurences::NoneOrUpTo(Some(u16::MAX))
+            }
+        );
+        let b: SealsStructure = bmap! {
+            AssignmentsType(0) => Occurences::OnceOrUpTo(Some(2))
+        };
+        assert_eq!(
+            merge_seals(&a, &b),
+            Err(MergeError::ConflictingSeal {
+                seal_type: AssignmentsType(0),
+                first: Occurences::Once,
+                second: Occurences::OnceOrUpTo(Some(2)),
+            })
+        );
     }
 }
@@ -14,16 +14,15 @@
 use std::collections::BTreeMap;
 use std::io;
 
+use amplify::Wrapper;
 use bitcoin::hashes::{sha256t, Hash};
 
 use super::{
-    vm, AssignmentsType, DataFormat, GenesisSchema, SimplicityScript, StateSchema, TransitionSchema,
+    vm, AssignmentsType, DataFormat, FieldType, GenesisSchema, SimplicityScript, StateSchema,
+    TransitionSchema, TransitionType, TypeNames,
 };
 use crate::client_side_validation::{commit_strategy, CommitEncodeWithStrategy, ConsensusCommit};
 
-pub type FieldType = usize; // Here we can use usize since encoding/decoding makes sure that it's u16
-pub type TransitionType = usize; // Here we can use usize since encoding/decoding makes sure that it's u16
-
 static MIDSTATE_SHEMA_ID: [u8; 32] = [
     25, 205, 224, 91, 171, 217, 131, 31, 140, 104, 5, 155, 127, 82, 14, 81, 58, 245, 79, 165, 114,
     243, 110, 60, 133, 174, 103, 187, 103, 230, 9, 106,
@@ -36,20 +35,78 @@ tagged_hash!(
     doc = "Commitment-based schema identifier used for committing to the schema type"
 );
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, Debug)]
 pub struct Schema {
     pub field_types: BTreeMap<FieldType, DataFormat>,
     pub assignment_types: BTreeMap<AssignmentsType, StateSchema>,
     pub genesis: GenesisSchema,
     pub transitions: BTreeMap<TransitionType, TransitionSchema>,
+    /// Human-readable names of the schema types; they are not committed to
+    /// and are not strict-encoded, so decoded schemata have no names
+    pub(super) type_names: TypeNames,
+}
+
+/// Schemata are compared by the committed data only, so type names do not
+/// affect equality, the same way they do not affect [Schema::schema_id]
+impl PartialEq for Schema {
+    fn eq(&self, other: &Self) -> bool {
+        self.field_types == other.field_types
+            && self.assignment_types == other.assignment_types
+            && self.genesis == other.genesis
+            && self.transitions == other.transitions
+    }
 }
 
 impl Schema {
+    /// Constructs schema without type names; use [Schema::with_type_names]
+    /// to attach them
+    pub fn new(
+        field_types: BTreeMap<FieldType, DataFormat>,
+        assignment_types: BTreeMap<AssignmentsType, StateSchema>,
+        genesis: GenesisSchema,
+        transitions: BTreeMap<TransitionType, TransitionSchema>,
+    ) -> Self {
+        Self {
+            field_types,
+            assignment_types,
+            genesis,
+            transitions,
+            type_names: TypeNames::default(),
+        }
+    }
+
     #[inline]
     pub fn schema_id(&self) -> SchemaId {
         self.clone().consensus_commit()
     }
 
+    /// Returns schema for the state transitions of the given type, or `None`
+    /// if the type is not defined by the schema
+    #[inline]
+    pub fn transition_schema(&self, ty: TransitionType) -> Option<&TransitionSchema> {
+        self.transitions.get(&ty)
+    }
+
+    /// Attaches human-readable type names to the schema; the schema id is
+    /// not affected
+    #[inline]
+    pub fn with_type_names(self, type_names: TypeNames) -> Self {
+        Self { type_names, ..self }
+    }
+
+    /// Returns human-readable type names attached to the schema
+    #[inline]
+    pub fn type_names(&self) -> &TypeNames {
+        &self.type_names
+    }
+
+    /// Describes validation failure using type names attached to the schema
+    /// (see [TypeNames::describe_failure])
+    #[inline]
+    pub fn describe_failure(&self, failure: &crate::rgb::validation::Failure) -> String {
+        self.type_names.describe_failure(failure)
+    }
+
     // TODO: Change with the adoption of Simplicity
     #[inline]
     pub fn scripts(&self) -> SimplicityScript {
@@ -57,6 +114,27 @@ impl Schema {
     }
 }
 
+/// Decodes schema from untrusted data, like the data received from the
+/// network or read from the disk. Guaranteed to return an error and never
+/// panic on malformed or truncated input. Fails with
+/// [crate::strict_encoding::Error::DataNotEntirelyConsumed] if some data are
+/// left after the schema is decoded.
+pub fn decode_schema(bytes: &[u8]) -> Result<Schema, crate::strict_encoding::Error> {
+    crate::strict_encoding::strict_decode(&bytes)
+}
+
+/// Maximum number of schemata within a single [SchemaCatalog]; catalogs
+/// claiming more schemata are rejected during decoding before allocating
+/// any memory for them
+pub const MAX_CATALOG_SCHEMATA: usize = 256;
+
+wrapper!(
+    SchemaCatalog,
+    Vec<Schema>,
+    doc = "List of schemata distributed together, like a schema registry export",
+    derive = [Default, PartialEq]
+);
+
 impl ConsensusCommit for Schema {
     type Commitment = SchemaId;
 }
@@ -92,6 +170,27 @@ mod strict_encoding {
         }
     }
 
+    impl StrictEncode for SchemaCatalog {
+        type Error = Error;
+
+        fn strict_encode<E: io::Write>(&self, e: E) -> Result<usize, Self::Error> {
+            if self.as_inner().len() > MAX_CATALOG_SCHEMATA {
+                Err(Error::ExceedMaxItems(self.as_inner().len()))?
+            }
+            self.as_inner().strict_encode(e)
+        }
+    }
+
+    impl StrictDecode for SchemaCatalog {
+        type Error = Error;
+
+        fn strict_decode<D: io::Read>(d: D) -> Result<Self, Self::Error> {
+            Ok(Self::from_inner(
+                crate::strict_encoding::strict_decode_vec_bounded(d, MAX_CATALOG_SCHEMATA)?,
+            ))
+        }
+    }
+
     impl StrictEncode for Schema {
         type Error = Error;
 
@@ -116,6 +215,7 @@ mod strict_encoding {
                 assignment_types: BTreeMap::strict_decode(&mut d)?,
                 genesis: GenesisSchema::strict_decode(&mut d)?,
                 transitions: BTreeMap::strict_decode(&mut d)?,
+                type_names: TypeNames::default(),
             };
             // We keep this parameter for future script extended info (like ABI)
             let script = Vec::<u8>::strict_decode(&mut d)?;
@@ -159,7 +259,7 @@ mod _validation {
                     &self.genesis.defines,
                 ),
                 Some(type_id) => {
-                    let transition_type = match self.transitions.get(&type_id) {
+                    let transition_type = match self.transition_schema(type_id) {
                         None => {
                             return validation::Status::with_failure(
                                 validation::Failure::SchemaUnknownTransitionType(node_id, type_id),
@@ -225,7 +325,7 @@ mod _validation {
                 let field = self.field_types.get(field_type_id)
                     .expect("If the field were absent, the schema would not be able to pass the internal validation and we would not reach this point");
                 for data in set {
-                    status += field.validate(*field_type_id, &data);
+                    status += field.validate((*field_type_id).into(), &data);
                 }
             }
 
@@ -314,16 +414,18 @@ mod _validation {
                     None => {}
                     Some(AssignmentsVariant::Declarative(set)) => {
                         set.into_iter().for_each(|data| {
-                            status += assignment.validate(&node_id, *assignment_type_id, data)
+                            status +=
+                                assignment.validate(&node_id, (*assignment_type_id).into(), data)
                         })
                     }
                     Some(AssignmentsVariant::DiscreteFiniteField(set)) => {
                         set.into_iter().for_each(|data| {
-                            status += assignment.validate(&node_id, *assignment_type_id, data)
+                            status +=
+                                assignment.validate(&node_id, (*assignment_type_id).into(), data)
                         })
                     }
                     Some(AssignmentsVariant::CustomData(set)) => set.into_iter().for_each(|data| {
-                        status += assignment.validate(&node_id, *assignment_type_id, data)
+                        status += assignment.validate(&node_id, (*assignment_type_id).into(), data)
                     }),
                 };
             }
@@ -501,23 +603,23 @@ pub(crate) mod test {
     use crate::strict_encoding::*;
 
     pub(crate) fn schema() -> Schema {
-        const FIELD_TICKER: usize = 0;
-        const FIELD_NAME: usize = 1;
-        const FIELD_DESCRIPTION: usize = 2;
-        const FIELD_TOTAL_SUPPLY: usize = 3;
-        const FIELD_ISSUED_SUPPLY: usize = 4;
-        const FIELD_DUST_LIMIT: usize = 5;
-        const FIELD_PRECISION: usize = 6;
-        const FIELD_PRUNE_PROOF: usize = 7;
-        const FIELD_TIMESTAMP: usize = 8;
-
-        const ASSIGNMENT_ISSUE: usize = 0;
-        const ASSIGNMENT_ASSETS: usize = 1;
-        const ASSIGNMENT_PRUNE: usize = 2;
-
-        const TRANSITION_ISSUE: usize = 0;
-        const TRANSITION_TRANSFER: usize = 1;
-        const TRANSITION_PRUNE: usize = 2;
+        const FIELD_TICKER: FieldType = FieldType(0);
+        const FIELD_NAME: FieldType = FieldType(1);
+        const FIELD_DESCRIPTION: FieldType = FieldType(2);
+        const FIELD_TOTAL_SUPPLY: FieldType = FieldType(3);
+        const FIELD_ISSUED_SUPPLY: FieldType = FieldType(4);
+        const FIELD_DUST_LIMIT: FieldType = FieldType(5);
+        const FIELD_PRECISION: FieldType = FieldType(6);
+        const FIELD_PRUNE_PROOF: FieldType = FieldType(7);
+        const FIELD_TIMESTAMP: FieldType = FieldType(8);
+
+        const ASSIGNMENT_ISSUE: AssignmentsType = AssignmentsType(0);
+        const ASSIGNMENT_ASSETS: AssignmentsType = AssignmentsType(1);
+        const ASSIGNMENT_PRUNE: AssignmentsType = AssignmentsType(2);
+
+        const TRANSITION_ISSUE: TransitionType = TransitionType(0);
+        const TRANSITION_TRANSFER: TransitionType = TransitionType(1);
+        const TRANSITION_PRUNE: TransitionType = TransitionType(2);
 
         Schema {
             field_types: bmap! {
@@ -568,6 +670,7 @@ pub(crate) mod test {
                     ASSIGNMENT_PRUNE => Occurences::NoneOrUpTo(None)
                 },
                 abi: bmap! {},
+                extension: None,
             },
             transitions: bmap! {
                 TRANSITION_ISSUE => TransitionSchema {
@@ -582,7 +685,8 @@ pub(crate) mod test {
                         ASSIGNMENT_PRUNE => Occurences::NoneOrUpTo(None),
                         ASSIGNMENT_ASSETS => Occurences::NoneOrUpTo(None)
                     },
-                abi: bmap! {}
+                abi: bmap! {},
+                extension: None
                 },
                 TRANSITION_TRANSFER => TransitionSchema {
                     metadata: bmap! {},
@@ -592,7 +696,8 @@ pub(crate) mod test {
                     defines: bmap! {
                         ASSIGNMENT_ASSETS => Occurences::NoneOrUpTo(None)
                     },
-                    abi: bmap! {}
+                    abi: bmap! {},
+                    extension: None
                 },
                 TRANSITION_PRUNE => TransitionSchema {
                     metadata: bmap! {
@@ -606,9 +711,11 @@ pub(crate) mod test {
                         ASSIGNMENT_PRUNE => Occurences::NoneOrUpTo(None),
                         ASSIGNMENT_ASSETS => Occurences::NoneOrUpTo(None)
                     },
-                    abi: bmap! {}
+                    abi: bmap! {},
+                    extension: None
                 }
             },
+            type_names: TypeNames::default(),
         }
     }
 
@@ -623,23 +730,141 @@ pub(crate) mod test {
             255, 255, 255, 255, 6, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 18, 0, 0, 0, 0, 0, 0, 0, 7, 0,
             5, 255, 255, 8, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255,
             255, 3, 0, 0, 0, 0, 1, 0, 0, 255, 2, 1, 0, 1, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255,
-            255, 255, 255, 255, 255, 255, 1, 0, 0, 255, 1, 2, 0, 0, 1, 0, 0, 255, 3, 8, 0, 0, 0, 1,
-            0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 3, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 6, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 254, 255,
-            255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 1, 0, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0,
-            1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0,
-            254, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
-            0, 0, 0, 1, 0, 1, 0, 255, 255, 255, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 254, 255, 255, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 1, 0, 7, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 1, 0,
-            255, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 255, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 1, 0,
-            254, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0,
+            255, 255, 255, 255, 255, 255, 1, 0, 0, 255, 1, 2, 0, 0, 1, 0, 0, 255, 3, 0, 8, 0, 0, 0,
+            1, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 3, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 6, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 3,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 254,
+            255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 1, 0, 4, 0, 1, 0, 0, 0, 0, 0, 0,
+            0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            1, 0, 0, 0, 0, 1, 0, 1, 0, 255, 255, 255, 0, 0, 0, 0, 0, 0, 1, 0, 1, 0, 254, 255, 255,
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 1, 0, 7, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 2,
+            0, 1, 0, 255, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 255, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0,
+            1, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 2, 0, 254, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0,
         ];
         assert_eq!(encoded, encoded_standard);
 
         let decoded = Schema::strict_decode(&encoded[..]).unwrap();
         assert_eq!(decoded, schema);
     }
+
+    #[test]
+    fn test_schema_id_roundtrip() {
+        let schema = schema();
+        let id = schema.schema_id();
+
+        let encoded = strict_encode(&schema).unwrap();
+        let decoded = Schema::strict_decode(&encoded[..]).unwrap();
+        assert_eq!(decoded.schema_id(), id);
+        assert_eq!(strict_encode(&decoded).unwrap(), encoded);
+
+        // Any change to the schema must change its id
+        let mut changed = schema.clone();
+        changed.transitions.remove(&TransitionType(2));
+        assert_ne!(changed.schema_id(), id);
+    }
+
+    #[test]
+    fn test_transition_schema() {
+        let schema = schema();
+        let transfer = schema.transition_schema(TransitionType(1)).unwrap();
+        assert_eq!(transfer, &schema.transitions[&TransitionType(1)]);
+        assert_eq!(transfer.abi, bmap! {});
+        assert!(schema.transition_schema(TransitionType(0)).is_some());
+        assert!(schema.transition_schema(TransitionType(3)).is_none());
+        assert!(schema
+            .transition_schema(TransitionType(std::u16::MAX))
+            .is_none());
+    }
+
+    #[test]
+    fn test_schema_type_names() {
+        let schema = schema();
+        let id = schema.schema_id();
+        let encoded = strict_encode(&schema).unwrap();
+        let node_id = crate::rgb::NodeId::hash(b"node");
+        let failure =
+            crate::rgb::validation::Failure::SchemaUnknownFieldType(node_id, FieldType(0));
+        assert!(schema.describe_failure(&failure).contains("field 0"));
+
+        let named = schema.with_type_names(
+            TypeNames::new()
+                .with_field(FieldType(0), "ticker")
+                .with_assignment(AssignmentsType(1), "assets"),
+        );
+        assert!(named.describe_failure(&failure).contains("ticker"));
+        assert!(!named.describe_failure(&failure).contains("field 0"));
+
+        // Names are not a part of the schema commitment and equality
+        assert_eq!(named.schema_id(), id);
+        assert_eq!(strict_encode(&named).unwrap(), encoded);
+        let decoded = Schema::strict_decode(&encoded[..]).unwrap();
+        assert_eq!(decoded.type_names(), &TypeNames::default());
+        assert_eq!(decoded, named);
+        assert_ne!(named.type_names(), decoded.type_names());
+    }
+
+    #[test]
+    fn test_catalog_encoding() {
+        let mut other = schema();
+        other.transitions.clear();
+        let catalog = SchemaCatalog::from_inner(vec![schema(), other]);
+        let encoded = strict_encode(&catalog).unwrap();
+        let decoded: SchemaCatalog = strict_decode(&encoded).unwrap();
+        assert_eq!(decoded, catalog);
+        assert_eq!(decoded.len(), 2);
+
+        let empty: SchemaCatalog = strict_decode(&[0u8, 0]).unwrap();
+        assert_eq!(empty, SchemaCatalog::default());
+    }
+
+    #[test]
+    fn test_catalog_oversized() {
+        let catalog = SchemaCatalog::from_inner(vec![schema(); MAX_CATALOG_SCHEMATA + 1]);
+        match strict_encode(&catalog) {
+            Err(Error::ExceedMaxItems(len)) if len == MAX_CATALOG_SCHEMATA + 1 => {}
+            _ => panic!("Oversized catalog must not be encoded"),
+        }
+
+        // Count prefix claiming too many schemata must be rejected before
+        // reading any of them
+        let count = (MAX_CATALOG_SCHEMATA + 1) as u16;
+        match strict_decode::<SchemaCatalog>(&count.to_le_bytes()) {
+            Err(Error::ExceedMaxItems(len)) if len == MAX_CATALOG_SCHEMATA + 1 => {}
+            _ => panic!("Oversized catalog must not be decoded"),
+        }
+    }
+
+    #[test]
+    fn test_decode_schema_never_panics() {
+        use rand::{thread_rng, Rng, RngCore};
+
+        let mut rng = thread_rng();
+        let encoded = strict_encode(&schema()).unwrap();
+        assert_eq!(decode_schema(&encoded).unwrap(), schema());
+
+        // Truncated data
+        for len in 0..encoded.len() {
+            assert!(decode_schema(&encoded[..len]).is_err());
+        }
+
+        // Random data
+        for _ in 0..1000 {
+            let mut data = vec![0u8; rng.gen_range(0, 512)];
+            rng.fill_bytes(&mut data);
+            let _ = decode_schema(&data);
+        }
+
+        // Valid data with random bytes modified
+        for _ in 0..1000 {
+            let mut data = encoded.clone();
+            for _ in 0..rng.gen_range(1, 8) {
+                let pos = rng.gen_range(0, data.len());
+                data[pos] = rng.gen();
+            }
+            let _ = decode_schema(&data);
+        }
+    }
 }
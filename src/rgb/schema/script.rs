@@ -21,10 +21,23 @@ use num_traits::{FromPrimitive, ToPrimitive};
 /// placeholder for script data
 pub type SimplicityScript = Vec<u8>;
 
+wrapper!(
+    SchemaScript,
+    Vec<u8>,
+    doc = "Extended scripting information (like ABI extensions) for schema nodes. \
+    Until the scripting format is defined it is kept as an opaque byte string.",
+    derive = [Default, PartialEq, Eq]
+);
+
 #[non_exhaustive]
 #[derive(
     Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display, ToPrimitive, FromPrimitive,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[display_from(Debug)]
 pub enum GenesisAction {}
 
@@ -32,6 +45,11 @@ pub enum GenesisAction {}
 #[derive(
     Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display, ToPrimitive, FromPrimitive,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[display_from(Debug)]
 #[repr(u8)]
 pub enum TransitionAction {
@@ -42,6 +60,11 @@ pub enum TransitionAction {
 #[derive(
     Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display, ToPrimitive, FromPrimitive,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[display_from(Debug)]
 #[repr(u8)]
 pub enum AssignmentAction {
@@ -52,8 +75,44 @@ pub type GenesisAbi = BTreeMap<GenesisAction, Procedure>;
 pub type TransitionAbi = BTreeMap<TransitionAction, Procedure>;
 pub type AssignmentAbi = BTreeMap<AssignmentAction, Procedure>;
 
+/// Enumeration and lookup of the procedures which are bound to node or
+/// assignment actions by the schema ABI ([GenesisAbi], [TransitionAbi] and
+/// [AssignmentAbi])
+pub trait Abi {
+    type Action: Copy;
+
+    /// Iterates over all actions defined in the ABI and procedures bound to
+    /// them, in the order of the action ids
+    fn actions<'a>(&'a self) -> Box<dyn Iterator<Item = (Self::Action, &'a Procedure)> + 'a>;
+
+    /// Returns procedure bound to the `action`, if any
+    fn procedure(&self, action: Self::Action) -> Option<&Procedure>;
+}
+
+impl<A> Abi for BTreeMap<A, Procedure>
+where
+    A: Copy + Ord,
+{
+    type Action = A;
+
+    #[inline]
+    fn actions<'a>(&'a self) -> Box<dyn Iterator<Item = (A, &'a Procedure)> + 'a> {
+        Box::new(self.iter().map(|(action, procedure)| (*action, procedure)))
+    }
+
+    #[inline]
+    fn procedure(&self, action: A) -> Option<&Procedure> {
+        self.get(&action)
+    }
+}
+
 #[non_exhaustive]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Display)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[display_from(Debug)]
 pub enum Procedure {
     Standard(StandardProcedure),
@@ -64,6 +123,11 @@ pub enum Procedure {
 #[derive(
     Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display, ToPrimitive, FromPrimitive,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_crate")
+)]
 #[display_from(Debug)]
 #[repr(u8)]
 pub enum StandardProcedure {
@@ -72,6 +136,38 @@ pub enum StandardProcedure {
     Prunning = 3,
 }
 
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::*;
+    use amplify::Wrapper;
+    use bitcoin::hashes::hex::{FromHex, ToHex};
+    use serde::de::{self, Deserializer};
+    use serde::ser::Serializer;
+    use serde::{Deserialize, Serialize};
+
+    /// Schema script is serialized as a hex string
+    impl Serialize for SchemaScript {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(&self.as_inner().to_hex())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for SchemaScript {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let hex = String::deserialize(deserializer)?;
+            Vec::<u8>::from_hex(&hex)
+                .map(SchemaScript::from_inner)
+                .map_err(de::Error::custom)
+        }
+    }
+}
+
 mod strict_encoding {
     use super::*;
     use crate::strict_encoding::{Error, StrictDecode, StrictEncode};
@@ -107,3 +203,42 @@ mod strict_encoding {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_abi_actions() {
+        let abi: TransitionAbi = bmap! {
+            TransitionAction::GenerateBlank => Procedure::Simplicity { offset: 12 }
+        };
+        assert_eq!(
+            abi.actions().collect::<Vec<_>>(),
+            vec![(
+                TransitionAction::GenerateBlank,
+                &Procedure::Simplicity { offset: 12 }
+            )]
+        );
+        assert_eq!(
+            abi.procedure(TransitionAction::GenerateBlank),
+            Some(&Procedure::Simplicity { offset: 12 })
+        );
+
+        let abi: AssignmentAbi = bmap! {
+            AssignmentAction::Validate => Procedure::Standard(StandardProcedure::ConfidentialAmount)
+        };
+        assert_eq!(abi.actions().count(), 1);
+        assert_eq!(
+            abi.procedure(AssignmentAction::Validate),
+            Some(&Procedure::Standard(StandardProcedure::ConfidentialAmount))
+        );
+
+        let abi: TransitionAbi = bmap! {};
+        assert_eq!(abi.actions().next(), None);
+        assert_eq!(abi.procedure(TransitionAction::GenerateBlank), None);
+
+        let abi: GenesisAbi = bmap! {};
+        assert_eq!(abi.actions().count(), 0);
+    }
+}
@@ -920,3 +920,42 @@ mod _validation {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rgb::schema::test::schema;
+    use crate::rgb::schema::FieldType;
+    use crate::rgb::{data, validation::Failure};
+    use crate::strict_encoding::{strict_decode, strict_encode};
+
+    #[test]
+    fn test_metadata_type_mismatch() {
+        // Schema declares data format for each of the metadata fields
+        let schema = schema();
+        let ticker = &schema.field_types[&FieldType(0)];
+        let precision = &schema.field_types[&FieldType(6)];
+        assert_eq!(ticker, &DataFormat::String(16));
+        assert_eq!(
+            strict_decode::<DataFormat>(&strict_encode(ticker).unwrap()).unwrap(),
+            *ticker
+        );
+
+        let status = ticker.validate(0, &data::Revealed::String("TICK".to_string()));
+        assert!(status.failures.is_empty());
+        let status = ticker.validate(0, &data::Revealed::U8(1));
+        assert_eq!(status.failures, vec![Failure::SchemaMismatchedDataType(0)]);
+        let status = ticker.validate(0, &data::Revealed::Bytes(b"TICK".to_vec()));
+        assert_eq!(status.failures, vec![Failure::SchemaMismatchedDataType(0)]);
+
+        let status = precision.validate(6, &data::Revealed::U64(8));
+        assert!(status.failures.is_empty());
+        let status = precision.validate(6, &data::Revealed::String("8".to_string()));
+        assert_eq!(
+            status.failures,
+            vec![Failure::SchemaMismatchedBits(6, Bits::Bit64)]
+        );
+        let status = precision.validate(6, &data::Revealed::U64(19));
+        assert_eq!(status.failures, vec![Failure::SchemaMetaValueTooLarge(6)]);
+    }
+}
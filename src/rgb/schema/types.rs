@@ -13,7 +13,11 @@
 
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
-use std::{convert::TryFrom, io};
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::{convert::TryFrom, fmt, io};
+
+use crate::client_side_validation::{commit_strategy, CommitEncodeWithStrategy};
 
 pub trait UnsignedInteger:
     Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Into<u64> + std::fmt::Debug
@@ -117,8 +121,21 @@ impl Bits {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display)]
-#[display_from(Debug)]
+/// Bounds for the number of occurrences of some schema item.
+///
+/// Has compact string representation (see [Display] and [FromStr]
+/// implementations): `1` for [Occurences::Once], `0..1` for
+/// [Occurences::NoneOrOnce], `1..*` and `1..N` for [Occurences::OnceOrUpTo]
+/// and `*` (or `0..*`) and `0..N` for [Occurences::NoneOrUpTo].
+///
+/// Bounds are partially ordered by inclusion: `a <= b` means that every
+/// number of occurrences valid under `a` is also valid under `b`. Different
+/// variants defining the same range (like `Once` and `OnceOrUpTo(Some(1))`)
+/// have different encodings and are not equal, so to stay consistent with
+/// `==` they are not ordered either. There is no [Ord] implementation: ranges
+/// where neither includes the other (like `1..5` and `0..3`) can't be
+/// ordered, and any total order would contradict the inclusion-based one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u8)]
 #[non_exhaustive]
 pub enum Occurences<I>
@@ -153,6 +170,21 @@ where
         }
     }
 
+    /// Returns whether at least one occurrence is required
+    #[inline]
+    pub fn is_required(&self) -> bool {
+        match self {
+            Occurences::Once | Occurences::OnceOrUpTo(_) => true,
+            Occurences::NoneOrOnce | Occurences::NoneOrUpTo(_) => false,
+        }
+    }
+
+    /// Returns whether zero occurrences are allowed
+    #[inline]
+    pub fn is_optional(&self) -> bool {
+        !self.is_required()
+    }
+
     pub fn check<T>(&self, count: T) -> Result<(), OccurrencesError>
     where
         T: Number + Into<u128>,
@@ -184,6 +216,175 @@ where
     }
 }
 
+impl<I> Occurences<I>
+where
+    I: UnsignedInteger + From<u8> + TryFrom<u64>,
+{
+    /// Returns the bounds for the sum of two numbers of occurrences (for
+    /// instance, when the same structure is referenced twice). Upper bounds
+    /// are added, saturating into the unbounded variant if the sum exceeds
+    /// `I::MAX`. Since the variants can't express lower bounds above one, the
+    /// result requires a single occurrence if any of the operands is
+    /// required.
+    pub fn saturating_add(self, other: Self) -> Self {
+        let max = match (self.upper_bound(), other.upper_bound()) {
+            (Some(a), Some(b)) => a.checked_add(b),
+            _ => None,
+        };
+        Self::with_bounds(self.is_required() || other.is_required(), max)
+    }
+
+    /// Returns the bounds for `factor` repetitions of the structure with the
+    /// current bounds, saturating into the unbounded variant like
+    /// [Occurences::saturating_add]. Zero factor results in
+    /// `NoneOrUpTo(Some(0))`.
+    pub fn saturating_scale(self, factor: I) -> Self {
+        let factor = factor.as_u64();
+        if factor == 0 {
+            return Occurences::NoneOrUpTo(Some(I::from(0u8)));
+        }
+        let max = self.upper_bound().and_then(|max| max.checked_mul(factor));
+        Self::with_bounds(self.is_required(), max)
+    }
+
+    fn upper_bound(&self) -> Option<u64> {
+        match self {
+            Occurences::Once | Occurences::NoneOrOnce => Some(1),
+            Occurences::OnceOrUpTo(max) | Occurences::NoneOrUpTo(max) => max.map(I::as_u64),
+        }
+    }
+
+    /// Constructs the variant for the given bounds, using `Once` and
+    /// `NoneOrOnce` for the upper bound of one
+    fn with_bounds(required: bool, max: Option<u64>) -> Self {
+        let max = max.and_then(|max| I::try_from(max).ok());
+        match (required, max) {
+            (true, Some(max)) if max == I::from(1u8) => Occurences::Once,
+            (false, Some(max)) if max == I::from(1u8) => Occurences::NoneOrOnce,
+            (true, max) => Occurences::OnceOrUpTo(max),
+            (false, max) => Occurences::NoneOrUpTo(max),
+        }
+    }
+}
+
+impl<I> PartialOrd for Occurences<I>
+where
+    I: UnsignedInteger + From<u8>,
+{
+    /// Compares the ranges of allowed occurrence numbers: returns
+    /// [Ordering::Equal] if the bounds are equal, [Ordering::Less] if `self`
+    /// range is nested into `other`, [Ordering::Greater] if it includes
+    /// `other` and `None` if neither range includes the other one. The same
+    /// range defined by different variants (like `1` and `1..1`) is not
+    /// ordered, since such bounds are not equal.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let min = self.min_value().cmp(&other.min_value());
+        let max = self.max_value().cmp(&other.max_value());
+        match (min, max) {
+            (Ordering::Equal, Ordering::Equal) if self == other => Some(Ordering::Equal),
+            (Ordering::Equal, Ordering::Equal) => None,
+            (Ordering::Greater, Ordering::Less)
+            | (Ordering::Greater, Ordering::Equal)
+            | (Ordering::Equal, Ordering::Less) => Some(Ordering::Less),
+            (Ordering::Less, Ordering::Greater)
+            | (Ordering::Less, Ordering::Equal)
+            | (Ordering::Equal, Ordering::Greater) => Some(Ordering::Greater),
+            _ => None,
+        }
+    }
+}
+
+/// Formats bounds in the forms accepted by [Occurences::from_str], such that
+/// the output always parses back into an equal value for the bounds produced
+/// by parsing. Variants which parsing never produces are formatted as their
+/// parsed equivalents: `OnceOrUpTo(Some(1))` as `1..1` (parsed as `Once`),
+/// `NoneOrUpTo(Some(1))` as `0..1` (parsed as `NoneOrOnce`), and explicit
+/// upper bound equal to `I::MAX` as a number (parsed as no upper bound). Zero
+/// upper bounds are formatted as `0..0` and `1..0`, which parsing rejects.
+impl<I> fmt::Display for Occurences<I>
+where
+    I: UnsignedInteger + fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Occurences::Once => f.write_str("1"),
+            Occurences::NoneOrOnce => f.write_str("0..1"),
+            Occurences::OnceOrUpTo(None) => f.write_str("1..*"),
+            Occurences::OnceOrUpTo(Some(max)) => write!(f, "1..{}", max),
+            Occurences::NoneOrUpTo(None) => f.write_str("*"),
+            Occurences::NoneOrUpTo(Some(max)) => write!(f, "0..{}", max),
+        }
+    }
+}
+
+/// Errors parsing [Occurences] from a string
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display_from(Debug)]
+pub enum OccurrencesParseError {
+    /// String does not match any of `N`, `*`, `N..M` or `N..*` forms, or the
+    /// numbers in it do not fit into the occurrences integer type
+    InvalidFormat(String),
+
+    /// Maximal bound is less than the minimal one (like in `5..2`)
+    ReversedRange(u64, u64),
+
+    /// Only zero or one are supported as minimal bounds
+    UnsupportedMin(u64),
+
+    /// `0` and `0..0` do not allow any occurrences and are not supported
+    ZeroMax,
+}
+
+impl<I> FromStr for Occurences<I>
+where
+    I: UnsignedInteger + From<u8> + TryFrom<u64>,
+{
+    type Err = OccurrencesParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || OccurrencesParseError::InvalidFormat(s.to_string());
+        let parse = |val: &str| -> Result<Option<u64>, OccurrencesParseError> {
+            match val.trim() {
+                "*" => Ok(None),
+                num => {
+                    let num = num.parse::<u64>().map_err(|_| invalid())?;
+                    I::try_from(num).map_err(|_| invalid())?;
+                    Ok(Some(num))
+                }
+            }
+        };
+
+        let mut split = s.splitn(2, "..");
+        let first = split.next().ok_or_else(invalid)?;
+        let (min, max) = match split.next() {
+            // `N` means exactly `N`, while `*` means any number of occurrences
+            None => match parse(first)? {
+                None => (0, None),
+                Some(num) => (num, Some(num)),
+            },
+            Some(last) => (parse(first)?.ok_or_else(invalid)?, parse(last)?),
+        };
+
+        // Maximal value is encoded as "no upper bound"
+        let max = max.filter(|max| *max != I::MAX.as_u64());
+        match (min, max) {
+            (min, Some(max)) if max < min => Err(OccurrencesParseError::ReversedRange(min, max)),
+            (_, Some(0)) => Err(OccurrencesParseError::ZeroMax),
+            (1, Some(1)) => Ok(Occurences::Once),
+            (0, Some(1)) => Ok(Occurences::NoneOrOnce),
+            (0, max) => Ok(Occurences::NoneOrUpTo(
+                max.map(|max| I::try_from(max).map_err(|_| invalid()))
+                    .transpose()?,
+            )),
+            (1, max) => Ok(Occurences::OnceOrUpTo(
+                max.map(|max| I::try_from(max).map_err(|_| invalid()))
+                    .transpose()?,
+            )),
+            (min, _) => Err(OccurrencesParseError::UnsupportedMin(min)),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display)]
 #[display_from(Debug)]
 pub struct OccurrencesError {
@@ -192,6 +393,98 @@ pub struct OccurrencesError {
     pub found: u128,
 }
 
+/// Kind of mismatch between the actual number of occurrences and the bounds
+/// defined by [Occurences]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display)]
+#[display_from(Debug)]
+pub enum OccurrencesMismatch {
+    /// Number of occurrences is below the minimal bound ([OccurrencesError::min])
+    TooFew,
+    /// Number of occurrences exceeds the maximal bound ([OccurrencesError::max])
+    TooMany,
+}
+
+impl OccurrencesError {
+    /// Detects whether the found number of occurrences was below or above
+    /// the allowed bounds
+    pub fn mismatch(&self) -> OccurrencesMismatch {
+        if self.found < self.min {
+            OccurrencesMismatch::TooFew
+        } else {
+            OccurrencesMismatch::TooMany
+        }
+    }
+}
+
+macro_rules! schema_type {
+    ($name:ident, $doc:literal) => {
+        #[doc = $doc]
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug, Display)]
+        #[cfg_attr(
+            feature = "serde",
+            derive(Serialize, Deserialize),
+            serde(crate = "serde_crate", transparent)
+        )]
+        #[display_from(Debug)]
+        pub struct $name(pub u16);
+
+        impl From<u16> for $name {
+            #[inline]
+            fn from(value: u16) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for u16 {
+            #[inline]
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl From<$name> for usize {
+            #[inline]
+            fn from(value: $name) -> Self {
+                value.0 as usize
+            }
+        }
+
+        impl CommitEncodeWithStrategy for $name {
+            type Strategy = commit_strategy::UsingStrict;
+        }
+
+        impl TryFrom<usize> for $name {
+            type Error = crate::strict_encoding::Error;
+
+            fn try_from(value: usize) -> Result<Self, Self::Error> {
+                u16::try_from(value).map(Self).map_err(|_| {
+                    crate::strict_encoding::Error::ValueOutOfRange(
+                        stringify!($name).to_string(),
+                        0..(std::u16::MAX as u128 + 1),
+                        value as u128,
+                    )
+                })
+            }
+        }
+    };
+}
+
+schema_type!(
+    FieldType,
+    "Type of the metadata field; distinct from [AssignmentsType] so the two \
+    can't be mixed up as schema map keys"
+);
+schema_type!(
+    AssignmentsType,
+    "Type of the state assignment (seal definition); distinct from \
+    [FieldType] so the two can't be mixed up as schema map keys"
+);
+schema_type!(
+    TransitionType,
+    "Type of the state transition, selecting [super::TransitionSchema] \
+    which the transition is validated against"
+);
+
 #[derive(
     Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Display, ToPrimitive, FromPrimitive,
 )]
@@ -251,6 +544,41 @@ pub mod elliptic_curve {
 }
 pub use elliptic_curve::EllipticCurve;
 
+/// [Occurences] are serialized using their compact string representation
+/// (see [Display] and [FromStr] implementations), like `"1..*"`
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::*;
+    use serde::de::{self, Deserializer};
+    use serde::ser::Serializer;
+    use serde::{Deserialize, Serialize};
+
+    impl<I> Serialize for Occurences<I>
+    where
+        I: UnsignedInteger + fmt::Display,
+    {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de, I> Deserialize<'de> for Occurences<I>
+    where
+        I: UnsignedInteger + From<u8> + TryFrom<u64>,
+    {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            Occurences::from_str(&s).map_err(de::Error::custom)
+        }
+    }
+}
+
 mod strict_encoding {
     use super::*;
     use crate::strict_encoding::{Error, StrictDecode, StrictEncode};
@@ -261,6 +589,48 @@ mod strict_encoding {
     impl_enum_strict_encoding!(elliptic_curve::SignatureAlgorithm);
     impl_enum_strict_encoding!(elliptic_curve::PointSerialization);
 
+    macro_rules! impl_schema_type {
+        ($name:ident) => {
+            impl StrictEncode for $name {
+                type Error = Error;
+
+                fn strict_encode<E: io::Write>(&self, e: E) -> Result<usize, Error> {
+                    self.0.strict_encode(e)
+                }
+            }
+
+            impl StrictDecode for $name {
+                type Error = Error;
+
+                fn strict_decode<D: io::Read>(d: D) -> Result<Self, Error> {
+                    u16::strict_decode(d).map(Self)
+                }
+            }
+        };
+    }
+
+    impl_schema_type!(FieldType);
+    impl_schema_type!(AssignmentsType);
+    impl_schema_type!(TransitionType);
+
+    /// [Occurences] are encoded as a single-byte variant tag followed by the
+    /// maximum bound as `u64`:
+    /// - `0x00` for [Occurences::NoneOrOnce] and `0x01` for
+    ///   [Occurences::Once], with zero bound;
+    /// - `0xFE` for [Occurences::NoneOrUpTo] and `0xFF` for
+    ///   [Occurences::OnceOrUpTo], with the maximum bound or the maximum
+    ///   value of the integer type for unlimited bounds.
+    ///
+    /// The minimum bound is not encoded since it is implied by the variant:
+    /// it is zero for `NoneOr*` and one for `Once*` variants, so a separate
+    /// field could only carry redundant (or contradicting) data. The format
+    /// is the one already used by the existing schemata (see the RGB20
+    /// encoding test in the schema module), and changing it would change all
+    /// schema ids.
+    ///
+    /// Data with inconsistent bounds, i.e. with the minimum bound exceeding
+    /// the maximum or with non-zero bound for the fixed variants, fail with
+    /// [Error::DataIntegrityError].
     macro_rules! impl_occurences {
         ($type:ident) => {
             impl StrictEncode for Occurences<$type> {
@@ -271,6 +641,9 @@ mod strict_encoding {
                         Self::NoneOrOnce => (0x00u8, 0),
                         Self::Once => (0x01u8, 0),
                         Self::NoneOrUpTo(max) => (0xFEu8, max.unwrap_or(std::$type::MAX).into()),
+                        Self::OnceOrUpTo(Some(0)) => Err(Error::DataIntegrityError(
+                            "Occurences minimum bound exceeds the maximum".to_string(),
+                        ))?,
                         Self::OnceOrUpTo(max) => (0xFFu8, max.unwrap_or(std::$type::MAX).into()),
                     };
                     let mut len = value.0.strict_encode(&mut e)?;
@@ -297,14 +670,20 @@ mod strict_encoding {
                             invalid as u128,
                         )),
                     }?;
-                    Ok(match value {
-                        0x00u8 => Self::NoneOrOnce,
-                        0x01u8 => Self::Once,
-                        0xFEu8 => Self::NoneOrUpTo(max),
-                        0xFFu8 => Self::OnceOrUpTo(max),
-                        _ => panic!(
-                            "New occurrence types can't appear w/o this library to be aware of"
-                        ),
+                    Ok(match (value, max) {
+                        (0x00u8, Some(0)) => Self::NoneOrOnce,
+                        (0x01u8, Some(0)) => Self::Once,
+                        (0x00u8, _) | (0x01u8, _) => Err(Error::DataIntegrityError(
+                            "Occurences with fixed bounds must have zero maximum value".to_string(),
+                        ))?,
+                        (0xFEu8, max) => Self::NoneOrUpTo(max),
+                        (0xFFu8, Some(0)) => Err(Error::DataIntegrityError(
+                            "Occurences minimum bound exceeds the maximum".to_string(),
+                        ))?,
+                        (0xFFu8, max) => Self::OnceOrUpTo(max),
+                        (unknown, _) => {
+                            Err(Error::EnumValueNotKnown("Occurences".to_string(), unknown))?
+                        }
                     })
                 }
             }
@@ -319,7 +698,14 @@ mod strict_encoding {
 
 #[cfg(test)]
 mod test {
-    use super::Occurences;
+    use super::{
+        AssignmentsType, FieldType, Occurences, OccurrencesError, OccurrencesMismatch,
+        OccurrencesParseError, UnsignedInteger,
+    };
+    use crate::strict_encoding::{strict_decode, strict_encode, Error};
+    use std::cmp::Ordering;
+    use std::convert::TryFrom;
+    use std::str::FromStr;
 
     #[test]
     fn test_once_check_count() {
@@ -416,4 +802,403 @@ mod test {
         let occurence: Occurences<u32> = Occurences::NoneOrUpTo(Some(42));
         occurence.check(43u32).unwrap();
     }
+
+    #[test]
+    fn test_occurences_add() {
+        use Occurences::*;
+        let once: Occurences<u8> = Once;
+        assert_eq!(once.saturating_add(Once), OnceOrUpTo(Some(2)));
+        assert_eq!(once.saturating_add(NoneOrOnce), OnceOrUpTo(Some(2)));
+        assert_eq!(
+            NoneOrOnce.saturating_add(NoneOrOnce),
+            NoneOrUpTo::<u8>(Some(2))
+        );
+        assert_eq!(
+            NoneOrUpTo(Some(0)).saturating_add(NoneOrOnce),
+            NoneOrOnce::<u8>
+        );
+        assert_eq!(
+            NoneOrUpTo(Some(3)).saturating_add(OnceOrUpTo(Some(5))),
+            OnceOrUpTo::<u8>(Some(8))
+        );
+        assert_eq!(
+            OnceOrUpTo(Some(3)).saturating_add(NoneOrUpTo(None)),
+            OnceOrUpTo::<u8>(None)
+        );
+
+        assert_eq!(once.saturating_scale(1), Once);
+        assert_eq!(once.saturating_scale(3), OnceOrUpTo(Some(3)));
+        assert_eq!(once.saturating_scale(0), NoneOrUpTo(Some(0)));
+        assert_eq!(
+            NoneOrUpTo(Some(4)).saturating_scale(5),
+            NoneOrUpTo::<u8>(Some(20))
+        );
+        assert_eq!(NoneOrUpTo(None).saturating_scale(5), NoneOrUpTo::<u8>(None));
+    }
+
+    #[test]
+    fn test_occurences_overflow() {
+        use Occurences::*;
+        assert_eq!(
+            OnceOrUpTo(Some(std::u8::MAX)).saturating_add(Once),
+            OnceOrUpTo::<u8>(None)
+        );
+        assert_eq!(
+            NoneOrUpTo(Some(200)).saturating_add(NoneOrUpTo(Some(56))),
+            NoneOrUpTo::<u8>(None)
+        );
+        assert_eq!(
+            NoneOrUpTo(Some(200)).saturating_add(NoneOrUpTo(Some(55))),
+            NoneOrUpTo::<u8>(Some(255))
+        );
+        assert_eq!(
+            NoneOrUpTo(Some(std::u16::MAX / 2 + 1)).saturating_scale(2),
+            NoneOrUpTo::<u16>(None)
+        );
+        assert_eq!(
+            OnceOrUpTo(Some(std::u16::MAX)).saturating_scale(std::u16::MAX),
+            OnceOrUpTo::<u16>(None)
+        );
+        assert_eq!(
+            OnceOrUpTo(Some(std::u64::MAX)).saturating_add(OnceOrUpTo(Some(1))),
+            OnceOrUpTo::<u64>(None)
+        );
+    }
+
+    #[test]
+    fn test_required_optional() {
+        let required: [Occurences<u16>; 4] = [
+            Occurences::Once,
+            Occurences::OnceOrUpTo(None),
+            Occurences::OnceOrUpTo(Some(1)),
+            Occurences::OnceOrUpTo(Some(42)),
+        ];
+        for occ in required.iter() {
+            assert!(occ.is_required());
+            assert!(!occ.is_optional());
+            assert_eq!(occ.min_value(), 1);
+        }
+
+        let optional: [Occurences<u16>; 4] = [
+            Occurences::NoneOrOnce,
+            Occurences::NoneOrUpTo(None),
+            Occurences::NoneOrUpTo(Some(0)),
+            Occurences::NoneOrUpTo(Some(42)),
+        ];
+        for occ in optional.iter() {
+            assert!(!occ.is_required());
+            assert!(occ.is_optional());
+            assert_eq!(occ.min_value(), 0);
+        }
+    }
+
+    #[test]
+    fn test_mismatch_kind() {
+        let check = |occurence: Occurences<u16>, count: u16| -> OccurrencesError {
+            occurence.check(count).unwrap_err()
+        };
+
+        let err = check(Occurences::NoneOrOnce, 2);
+        assert_eq!(err.mismatch(), OccurrencesMismatch::TooMany);
+        assert_eq!((err.max, err.found), (1, 2));
+
+        let err = check(Occurences::Once, 0);
+        assert_eq!(err.mismatch(), OccurrencesMismatch::TooFew);
+        assert_eq!((err.min, err.found), (1, 0));
+        let err = check(Occurences::Once, 2);
+        assert_eq!(err.mismatch(), OccurrencesMismatch::TooMany);
+        assert_eq!((err.max, err.found), (1, 2));
+
+        let err = check(Occurences::OnceOrUpTo(None), 0);
+        assert_eq!(err.mismatch(), OccurrencesMismatch::TooFew);
+        assert_eq!((err.min, err.found), (1, 0));
+        let err = check(Occurences::OnceOrUpTo(Some(3)), 4);
+        assert_eq!(err.mismatch(), OccurrencesMismatch::TooMany);
+        assert_eq!((err.max, err.found), (3, 4));
+
+        let err = check(Occurences::NoneOrUpTo(Some(3)), 4);
+        assert_eq!(err.mismatch(), OccurrencesMismatch::TooMany);
+        assert_eq!((err.max, err.found), (3, 4));
+        Occurences::<u16>::NoneOrUpTo(None).check(0u16).unwrap();
+        Occurences::<u16>::NoneOrUpTo(None).check(u16::MAX).unwrap();
+    }
+
+    #[test]
+    fn test_occurences_from_str() {
+        let parse = |s: &str| Occurences::<u16>::from_str(s);
+
+        assert_eq!(parse("1"), Ok(Occurences::Once));
+        assert_eq!(parse("1..1"), Ok(Occurences::Once));
+        assert_eq!(parse("0..1"), Ok(Occurences::NoneOrOnce));
+        assert_eq!(parse("*"), Ok(Occurences::NoneOrUpTo(None)));
+        assert_eq!(parse("0..*"), Ok(Occurences::NoneOrUpTo(None)));
+        assert_eq!(parse("1..*"), Ok(Occurences::OnceOrUpTo(None)));
+        assert_eq!(parse("0..5"), Ok(Occurences::NoneOrUpTo(Some(5))));
+        assert_eq!(parse("1..5"), Ok(Occurences::OnceOrUpTo(Some(5))));
+        assert_eq!(parse("1..65535"), Ok(Occurences::OnceOrUpTo(None)));
+
+        assert_eq!(
+            parse("5..2"),
+            Err(OccurrencesParseError::ReversedRange(5, 2))
+        );
+        assert_eq!(parse("2..5"), Err(OccurrencesParseError::UnsupportedMin(2)));
+        assert_eq!(parse("3"), Err(OccurrencesParseError::UnsupportedMin(3)));
+        assert_eq!(parse("0"), Err(OccurrencesParseError::ZeroMax));
+        assert_eq!(parse("0..0"), Err(OccurrencesParseError::ZeroMax));
+        vec![
+            "", "..", "1..", "..1", "*..1", "a", "1..b", "0..65536", "1...2", "-1",
+        ]
+        .into_iter()
+        .for_each(|s| {
+            assert_eq!(
+                parse(s),
+                Err(OccurrencesParseError::InvalidFormat(s.to_string()))
+            )
+        });
+    }
+
+    #[test]
+    fn test_occurences_display_roundtrip() {
+        vec![
+            (Occurences::Once, "1"),
+            (Occurences::NoneOrOnce, "0..1"),
+            (Occurences::NoneOrUpTo(None), "*"),
+            (Occurences::OnceOrUpTo(None), "1..*"),
+            (Occurences::NoneOrUpTo(Some(7)), "0..7"),
+            (Occurences::OnceOrUpTo(Some(7)), "1..7"),
+        ]
+        .into_iter()
+        .for_each(|(occurences, s): (Occurences<u8>, &str)| {
+            assert_eq!(occurences.to_string(), s);
+            assert_eq!(Occurences::<u8>::from_str(s), Ok(occurences));
+        });
+    }
+
+    #[test]
+    fn test_occurences_display_parse_property() {
+        use rand::{thread_rng, Rng};
+
+        fn canonical<I>(required: bool, max: Option<I>) -> Occurences<I>
+        where
+            I: UnsignedInteger + From<u8>,
+        {
+            match (required, max) {
+                (true, Some(max)) if max == I::from(1u8) => Occurences::Once,
+                (false, Some(max)) if max == I::from(1u8) => Occurences::NoneOrOnce,
+                (true, max) => Occurences::OnceOrUpTo(max),
+                (false, max) => Occurences::NoneOrUpTo(max),
+            }
+        }
+
+        // All bounds for u8
+        for required in &[false, true] {
+            let bounds = (1..std::u8::MAX)
+                .map(Some)
+                .chain(vec![None])
+                .map(|max| canonical(*required, max));
+            for occurences in bounds {
+                assert_eq!(
+                    Occurences::<u8>::from_str(&occurences.to_string()),
+                    Ok(occurences)
+                );
+            }
+        }
+
+        // Randomly generated bounds for u16
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let max = match rng.gen_range(0, 8) {
+                0 => None,
+                _ => Some(rng.gen_range(1, std::u16::MAX)),
+            };
+            let occurences = canonical(rng.gen(), max);
+            assert_eq!(
+                Occurences::<u16>::from_str(&occurences.to_string()),
+                Ok(occurences)
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_type_encoding() {
+        let field_type = FieldType(0x1234);
+        let encoded = strict_encode(&field_type).unwrap();
+        assert_eq!(encoded, vec![0x34, 0x12]);
+        assert_eq!(strict_decode::<FieldType>(&encoded).unwrap(), field_type);
+        assert_eq!(
+            strict_decode::<AssignmentsType>(&encoded).unwrap(),
+            AssignmentsType(0x1234)
+        );
+
+        assert_eq!(
+            FieldType::try_from(std::u16::MAX as usize).unwrap(),
+            FieldType(std::u16::MAX)
+        );
+        match FieldType::try_from(std::u16::MAX as usize + 1) {
+            Err(Error::ValueOutOfRange(_, _, value)) => assert_eq!(value, 0x10000),
+            _ => panic!("FieldType must not be created from value > u16::MAX"),
+        }
+        match AssignmentsType::try_from(std::u32::MAX as usize) {
+            Err(Error::ValueOutOfRange(_, _, _)) => {}
+            _ => panic!("AssignmentsType must not be created from value > u16::MAX"),
+        }
+    }
+
+    #[test]
+    fn test_schema_type_decode_out_of_range() {
+        // Value > u16::MAX serialized with a wider integer does not fit into
+        // the two bytes of the type encoding
+        let encoded = (std::u16::MAX as u32 + 1).to_le_bytes();
+        match strict_decode::<FieldType>(&encoded) {
+            Err(Error::DataNotEntirelyConsumed) => {}
+            _ => panic!("FieldType decoding must fail for value > u16::MAX"),
+        }
+        match strict_decode::<AssignmentsType>(&encoded) {
+            Err(Error::DataNotEntirelyConsumed) => {}
+            _ => panic!("AssignmentsType decoding must fail for value > u16::MAX"),
+        }
+        match strict_decode::<FieldType>(&[0x01u8]) {
+            Err(Error::Io(_)) => {}
+            _ => panic!("FieldType decoding must fail on truncated data"),
+        }
+    }
+
+    #[test]
+    fn test_occurences_decode_unknown_tag() {
+        match strict_decode::<Occurences<u16>>(&[0x02u8, 0, 0, 0, 0, 0, 0, 0, 0]) {
+            Err(Error::EnumValueNotKnown(_, 0x02)) => {}
+            _ => panic!("Unknown occurences tag must be reported as an error"),
+        }
+    }
+
+    #[test]
+    fn test_occurences_encoding() {
+        let vectors: Vec<(Occurences<u16>, Vec<u8>)> = vec![
+            (Occurences::NoneOrOnce, vec![0x00, 0, 0, 0, 0, 0, 0, 0, 0]),
+            (Occurences::Once, vec![0x01, 0, 0, 0, 0, 0, 0, 0, 0]),
+            (
+                Occurences::NoneOrUpTo(Some(0)),
+                vec![0xFE, 0, 0, 0, 0, 0, 0, 0, 0],
+            ),
+            (
+                Occurences::NoneOrUpTo(Some(5)),
+                vec![0xFE, 5, 0, 0, 0, 0, 0, 0, 0],
+            ),
+            (
+                Occurences::NoneOrUpTo(None),
+                vec![0xFE, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0],
+            ),
+            (
+                Occurences::OnceOrUpTo(Some(0x1234)),
+                vec![0xFF, 0x34, 0x12, 0, 0, 0, 0, 0, 0],
+            ),
+            (
+                Occurences::OnceOrUpTo(None),
+                vec![0xFF, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0],
+            ),
+        ];
+        for (occurences, encoded) in vectors {
+            assert_eq!(strict_encode(&occurences).unwrap(), encoded);
+            assert_eq!(
+                strict_decode::<Occurences<u16>>(&encoded).unwrap(),
+                occurences
+            );
+        }
+    }
+
+    #[test]
+    fn test_occurences_invalid_bounds() {
+        match strict_encode(&Occurences::<u16>::OnceOrUpTo(Some(0))) {
+            Err(Error::DataIntegrityError(_)) => {}
+            _ => panic!("Occurences with min > max must not be encoded"),
+        }
+        for encoded in &[
+            // 1..0
+            [0xFFu8, 0, 0, 0, 0, 0, 0, 0, 0],
+            // Fixed bounds with explicit maximum
+            [0x01u8, 2, 0, 0, 0, 0, 0, 0, 0],
+            [0x00u8, 1, 0, 0, 0, 0, 0, 0, 0],
+        ] {
+            match strict_decode::<Occurences<u16>>(encoded) {
+                Err(Error::DataIntegrityError(_)) => {}
+                _ => panic!("Occurences with inconsistent bounds must not be decoded"),
+            }
+        }
+        match strict_decode::<Occurences<u16>>(&[0xFEu8, 0, 0, 1, 0, 0, 0, 0, 0]) {
+            Err(Error::ValueOutOfRange(_, _, 0x10000)) => {}
+            _ => panic!("Occurences bound must fit into the integer type"),
+        }
+    }
+
+    #[test]
+    fn test_occurences_order() {
+        // Nested bounds
+        assert!(Occurences::<u16>::Once < Occurences::NoneOrOnce);
+        assert!(Occurences::<u16>::Once < Occurences::OnceOrUpTo(None));
+        assert!(Occurences::<u16>::OnceOrUpTo(Some(5)) < Occurences::NoneOrUpTo(None));
+        assert!(Occurences::<u16>::NoneOrUpTo(Some(3)) < Occurences::NoneOrUpTo(Some(4)));
+        assert!(Occurences::<u16>::NoneOrUpTo(None) > Occurences::Once);
+        assert!(Occurences::<u16>::NoneOrOnce >= Occurences::Once);
+
+        // Equal bounds
+        assert_eq!(
+            Occurences::<u16>::OnceOrUpTo(Some(7)).partial_cmp(&Occurences::OnceOrUpTo(Some(7))),
+            Some(Ordering::Equal)
+        );
+        assert!(Occurences::<u16>::Once <= Occurences::Once);
+
+        // Incomparable bounds
+        assert_eq!(
+            Occurences::<u16>::OnceOrUpTo(Some(5)).partial_cmp(&Occurences::NoneOrUpTo(Some(3))),
+            None
+        );
+        assert_eq!(
+            Occurences::<u16>::OnceOrUpTo(None).partial_cmp(&Occurences::NoneOrOnce),
+            None
+        );
+    }
+
+    #[test]
+    fn test_occurences_order_equal() {
+        let all: Vec<Occurences<u16>> = vec![
+            Occurences::Once,
+            Occurences::NoneOrOnce,
+            Occurences::OnceOrUpTo(None),
+            Occurences::OnceOrUpTo(Some(1)),
+            Occurences::OnceOrUpTo(Some(5)),
+            Occurences::OnceOrUpTo(Some(u16::MAX)),
+            Occurences::NoneOrUpTo(None),
+            Occurences::NoneOrUpTo(Some(0)),
+            Occurences::NoneOrUpTo(Some(1)),
+            Occurences::NoneOrUpTo(Some(u16::MAX)),
+        ];
+        // Reflexivity
+        for occ in &all {
+            assert_eq!(occ.partial_cmp(occ), Some(Ordering::Equal));
+            assert!(occ <= occ && occ >= occ);
+        }
+
+        // Same range with different representations is not ordered, since
+        // the bounds are not equal
+        let equivalent = [
+            (Occurences::Once, Occurences::OnceOrUpTo(Some(1))),
+            (Occurences::NoneOrOnce, Occurences::NoneOrUpTo(Some(1))),
+            (
+                Occurences::OnceOrUpTo(None),
+                Occurences::OnceOrUpTo(Some(u16::MAX)),
+            ),
+            (
+                Occurences::NoneOrUpTo(None),
+                Occurences::NoneOrUpTo(Some(u16::MAX)),
+            ),
+        ];
+        for (a, b) in &equivalent {
+            assert_ne!(a, b);
+            assert_eq!(a.min_value(), b.min_value());
+            assert_eq!(a.max_value(), b.max_value());
+            assert_eq!(a.partial_cmp(b), None);
+            assert_eq!(b.partial_cmp(a), None);
+            assert!(!(a <= b) && !(a >= b));
+        }
+    }
 }
@@ -0,0 +1,420 @@
+// LNP/BP Rust Library
+// Written in 2020 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the MIT License
+// along with this software.
+// If not, see <https://opensource.org/licenses/MIT>.
+
+//! Stateful verification of a chain of contract nodes against the schema,
+//! tracking the set of seals which are defined and not yet closed
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+
+use super::{AssignmentsType, Schema, SchemaId, SchemaValidationError, TransitionType};
+use crate::rgb::{AssignmentsVariant, Genesis, Node, NodeId, Transition};
+
+/// Reference to a single seal defined by some of the contract nodes
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Display)]
+#[display_from(Debug)]
+pub struct SealPoint {
+    /// Node defining the seal
+    pub node_id: NodeId,
+    /// Type of the assignment containing the seal
+    pub assignment_type: AssignmentsType,
+    /// Index of the seal within the assignments of the given type
+    pub index: u16,
+}
+
+/// First inconsistency found by [SchemaVerifier]
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display_from(Debug)]
+pub enum VerifierError {
+    /// Genesis commits to a schema different from the one used by the
+    /// verifier
+    SchemaMismatch { expected: SchemaId, found: SchemaId },
+
+    /// Node with the given id was already processed by the verifier
+    RepeatedNode(NodeId),
+
+    /// State transition type is not defined by the schema
+    UnknownTransitionType(NodeId, TransitionType),
+
+    /// Metadata, closed or defined seals of the node do not match the schema
+    Structure(NodeId, SchemaValidationError),
+
+    /// State transition closes the seal which was already closed by another
+    /// (or the same) state transition
+    SealAlreadyClosed {
+        node_id: NodeId,
+        seal: SealPoint,
+        closed_by: NodeId,
+    },
+
+    /// State transition closes the seal which is not defined by any of the
+    /// nodes processed so far
+    UnknownSeal { node_id: NodeId, seal: SealPoint },
+}
+
+/// Verifier accepting contract genesis and state transitions one by one (in
+/// the order where each transition follows all of its ancestors) and checking
+/// each of them against the schema and the seals defined by the previously
+/// processed nodes.
+///
+/// The verifier does not check the assigned state and the scripts; this is
+/// done by [Schema::validate].
+#[derive(Clone, Debug)]
+pub struct SchemaVerifier<'schema> {
+    schema: &'schema Schema,
+    nodes: BTreeSet<NodeId>,
+    open_seals: BTreeSet<SealPoint>,
+    closed_seals: BTreeMap<SealPoint, NodeId>,
+}
+
+impl<'schema> SchemaVerifier<'schema> {
+    /// Creates verifier for the contract with the given genesis, checking
+    /// that the genesis commits to the schema and that its metadata and
+    /// defined seals match the schema
+    pub fn with(schema: &'schema Schema, genesis: &Genesis) -> Result<Self, VerifierError> {
+        let node_id = genesis.node_id();
+        let expected = schema.schema_id();
+        if genesis.schema_id() != expected {
+            Err(VerifierError::SchemaMismatch {
+                expected,
+                found: genesis.schema_id(),
+            })?
+        }
+        schema
+            .genesis
+            .validate_metadata(&metadata_counts(genesis))
+            .map_err(|err| VerifierError::Structure(node_id, err))?;
+        schema
+            .genesis
+            .validate_seals(&assignment_counts(genesis))
+            .map_err(|err| VerifierError::Structure(node_id, err))?;
+
+        let mut verifier = Self {
+            schema,
+            nodes: BTreeSet::new(),
+            open_seals: BTreeSet::new(),
+            closed_seals: BTreeMap::new(),
+        };
+        verifier.define_seals(genesis);
+        Ok(verifier)
+    }
+
+    /// Verifies the next state transition and updates the set of open seals.
+    /// If the transition is inconsistent, the verifier state is left
+    /// unchanged.
+    pub fn push_transition(&mut self, transition: &Transition) -> Result<(), VerifierError> {
+        let node_id = transition.node_id();
+        if self.nodes.contains(&node_id) {
+            Err(VerifierError::RepeatedNode(node_id))?
+        }
+
+        let type_id = transition.type_id().unwrap_or_default();
+        let transition_schema = self
+            .schema
+            .transition_schema(type_id)
+            .ok_or(VerifierError::UnknownTransitionType(node_id, type_id))?;
+
+        let mut closing = BTreeSet::new();
+        let mut closes = BTreeMap::<AssignmentsType, u16>::new();
+        for (ancestor_id, details) in transition.ancestors() {
+            for (assignment_type, indexes) in details {
+                for index in indexes {
+                    let seal = SealPoint {
+                        node_id: *ancestor_id,
+                        assignment_type: *assignment_type,
+                        index: *index,
+                    };
+                    if let Some(closed_by) = self.closed_seals.get(&seal) {
+                        Err(VerifierError::SealAlreadyClosed {
+                            node_id,
+                            seal,
+                            closed_by: *closed_by,
+                        })?
+                    }
+                    if !closing.insert(seal) {
+                        Err(VerifierError::SealAlreadyClosed {
+                            node_id,
+                            seal,
+                            closed_by: node_id,
+                        })?
+                    }
+                    if !self.open_seals.contains(&seal) {
+                        Err(VerifierError::UnknownSeal { node_id, seal })?
+                    }
+                    let count = closes.entry(*assignment_type).or_insert(0);
+                    // Saturated count still exceeds any schema limit
+                    *count = count.saturating_add(1);
+                }
+            }
+        }
+
+        transition_schema
+            .validate(
+                &metadata_counts(transition),
+                &closes,
+                &assignment_counts(transition),
+            )
+            .map_err(|err| VerifierError::Structure(node_id, err))?;
+
+        for seal in closing {
+            self.open_seals.remove(&seal);
+            self.closed_seals.insert(seal, node_id);
+        }
+        self.define_seals(transition);
+        Ok(())
+    }
+
+    /// Checks whether the seal is defined by some of the processed nodes and
+    /// is not closed yet
+    #[inline]
+    pub fn is_open(&self, seal: &SealPoint) -> bool {
+        self.open_seals.contains(seal)
+    }
+
+    /// Returns all seals which are defined by the processed nodes and are not
+    /// closed yet
+    #[inline]
+    pub fn open_seals(&self) -> &BTreeSet<SealPoint> {
+        &self.open_seals
+    }
+
+    fn define_seals(&mut self, node: &dyn Node) {
+        let node_id = node.node_id();
+        self.nodes.insert(node_id);
+        for (assignment_type, variant) in node.assignments() {
+            // Seals beyond `u16::MAX` can't be referenced by the ancestors
+            for index in 0..saturated_len(variant.len()) {
+                self.open_seals.insert(SealPoint {
+                    node_id,
+                    assignment_type: *assignment_type,
+                    index,
+                });
+            }
+        }
+    }
+}
+
+/// Counts above `u16::MAX` saturate instead of wrapping around, so the nodes
+/// with too many items can't pass a tighter schema limit
+fn saturated_len(len: usize) -> u16 {
+    u16::try_from(len).unwrap_or(core::u16::MAX)
+}
+
+fn metadata_counts(node: &dyn Node) -> BTreeMap<super::FieldType, u16> {
+    node.metadata()
+        .iter()
+        .map(|(field_type, values)| (*field_type, saturated_len(values.len())))
+        .collect()
+}
+
+fn assignment_counts(node: &dyn Node) -> BTreeMap<AssignmentsType, u16> {
+    node.assignments()
+        .iter()
+        .map(|(assignment_type, variant)| {
+            (
+                *assignment_type,
+                saturated_len(AssignmentsVariant::len(variant)),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bp;
+    use crate::rgb::schema::test::schema;
+    use crate::rgb::schema::{FieldType, StructureViolation, ValidationOperation};
+    use crate::rgb::{data, seal, Ancestors, Assignment, Assignments, Metadata};
+    use amplify::Wrapper;
+    use bitcoin::hashes::Hash;
+
+    // Issue right of the shared RGB20 schema fixture: declarative state,
+    // defined at most once by genesis and closed exactly once by the
+    // secondary issue transition, which may define it again
+    const ASSIGNMENT_ISSUE: AssignmentsType = AssignmentsType(0);
+    const TRANSITION_ISSUE: TransitionType = TransitionType(0);
+    const FIELD_ISSUED_SUPPLY: FieldType = FieldType(4);
+
+    fn metadata(fields: &[u16]) -> Metadata {
+        Metadata::from_inner(
+            fields
+                .iter()
+                .map(|field| (FieldType(*field), bset! { data::Revealed::U64(100) }))
+                .collect(),
+        )
+    }
+
+    fn assignments(vouts: &[u16]) -> Assignments {
+        bmap! {
+            ASSIGNMENT_ISSUE => AssignmentsVariant::Declarative(
+                vouts
+                    .iter()
+                    .map(|vout| Assignment::Revealed {
+                        seal_definition: seal::Revealed::WitnessVout {
+                            vout: *vout,
+                            blinding: 0x1234 + *vout as u64,
+                        },
+                        assigned_state: data::Void,
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    fn issue(ancestor_id: NodeId, index: u16, vouts: &[u16]) -> Transition {
+        let ancestors: Ancestors = bmap! {
+            ancestor_id => bmap! { ASSIGNMENT_ISSUE => vec![index] }
+        };
+        Transition::with(
+            TRANSITION_ISSUE,
+            metadata(&[FIELD_ISSUED_SUPPLY.0]),
+            ancestors,
+            assignments(vouts),
+            vec![],
+        )
+    }
+
+    fn genesis_with(schema_id: SchemaId, vouts: &[u16]) -> Genesis {
+        Genesis::with(
+            schema_id,
+            bp::Network::Testnet,
+            // Ticker, name, total & issued supply, precision and timestamp
+            metadata(&[0, 1, 3, 4, 6, 8]),
+            assignments(vouts),
+            vec![],
+        )
+    }
+
+    fn genesis(schema: &Schema) -> Genesis {
+        genesis_with(schema.schema_id(), &[0])
+    }
+
+    fn seal_point(node_id: NodeId, index: u16) -> SealPoint {
+        SealPoint {
+            node_id,
+            assignment_type: ASSIGNMENT_ISSUE,
+            index,
+        }
+    }
+
+    #[test]
+    fn test_valid_chain() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let mut verifier = SchemaVerifier::with(&schema, &genesis).unwrap();
+        let genesis_seal = seal_point(genesis.node_id(), 0);
+        assert!(verifier.is_open(&genesis_seal));
+
+        let first = issue(genesis.node_id(), 0, &[1]);
+        verifier.push_transition(&first).unwrap();
+        assert!(!verifier.is_open(&genesis_seal));
+        let expected: BTreeSet<SealPoint> = bset! { seal_point(first.node_id(), 0) };
+        assert_eq!(verifier.open_seals(), &expected);
+
+        let second = issue(first.node_id(), 0, &[2]);
+        verifier.push_transition(&second).unwrap();
+        let expected: BTreeSet<SealPoint> = bset! { seal_point(second.node_id(), 0) };
+        assert_eq!(verifier.open_seals(), &expected);
+
+        assert_eq!(
+            verifier.push_transition(&second),
+            Err(VerifierError::RepeatedNode(second.node_id()))
+        );
+
+        // Transition may close the last seal without defining a new one
+        let last = issue(second.node_id(), 0, &[]);
+        verifier.push_transition(&last).unwrap();
+        assert!(verifier.open_seals().is_empty());
+    }
+
+    #[test]
+    fn test_double_close() {
+        let schema = schema();
+        let genesis = genesis(&schema);
+        let mut verifier = SchemaVerifier::with(&schema, &genesis).unwrap();
+
+        let first = issue(genesis.node_id(), 0, &[1]);
+        verifier.push_transition(&first).unwrap();
+
+        let second = issue(genesis.node_id(), 0, &[2]);
+        assert_eq!(
+            verifier.push_transition(&second),
+            Err(VerifierError::SealAlreadyClosed {
+                node_id: second.node_id(),
+                seal: seal_point(genesis.node_id(), 0),
+                closed_by: first.node_id(),
+            })
+        );
+        // Failed transition does not change the state
+        assert_eq!(verifier.open_seals().len(), 1);
+
+        let unknown = issue(first.node_id(), 5, &[3]);
+        assert_eq!(
+            verifier.push_transition(&unknown),
+            Err(VerifierError::UnknownSeal {
+                node_id: unknown.node_id(),
+                seal: seal_point(first.node_id(), 5),
+            })
+        );
+
+        let excessive = issue(first.node_id(), 0, &[2, 3]);
+        match verifier.push_transition(&excessive) {
+            Err(VerifierError::Structure(node_id, err)) => {
+                assert_eq!(node_id, excessive.node_id());
+                assert_eq!(err.violations.len(), 1);
+                match err.violations[0] {
+                    StructureViolation::DefinedSealsOccurences(ASSIGNMENT_ISSUE, _) => {}
+                    _ => panic!("Excessive defined seals must be reported"),
+                }
+            }
+            _ => panic!("Transition defining too many seals must fail the schema"),
+        }
+        assert!(verifier.is_open(&seal_point(first.node_id(), 0)));
+    }
+
+    #[test]
+    fn test_invalid_genesis() {
+        let schema = schema();
+        let other_id = SchemaId::hash(b"other schema");
+        assert_ne!(other_id, schema.schema_id());
+        assert_eq!(
+            SchemaVerifier::with(&schema, &genesis_with(other_id, &[0])).unwrap_err(),
+            VerifierError::SchemaMismatch {
+                expected: schema.schema_id(),
+                found: other_id,
+            }
+        );
+
+        let genesis = genesis_with(schema.schema_id(), &[0, 1]);
+        match SchemaVerifier::with(&schema, &genesis) {
+            Err(VerifierError::Structure(node_id, err)) => {
+                assert_eq!(node_id, genesis.node_id());
+                assert_eq!(err.operation, ValidationOperation::GenesisSeals);
+                match err.violations[..] {
+                    [StructureViolation::DefinedSealsOccurences(ASSIGNMENT_ISSUE, _)] => {}
+                    _ => panic!("Wrong number of genesis seals must be reported"),
+                }
+            }
+            _ => panic!("Genesis defining too many seals must fail the schema"),
+        }
+    }
+
+    #[test]
+    fn test_saturated_len() {
+        assert_eq!(saturated_len(0), 0);
+        assert_eq!(saturated_len(core::u16::MAX as usize), core::u16::MAX);
+        assert_eq!(saturated_len(core::u16::MAX as usize + 1), core::u16::MAX);
+        assert_eq!(saturated_len(core::usize::MAX), core::u16::MAX);
+    }
+}
@@ -22,9 +22,10 @@ use bitcoin::{Transaction, Txid};
 use bitcoin_hashes::{sha256, sha256t, Hash, HashEngine};
 
 use crate::bp::dbc::{
-    self, Container, Proof, ScriptInfo, ScriptPubkeyComposition, ScriptPubkeyContainer,
-    TxCommitment, TxContainer, TxSupplement, TxoutContainer,
+    self, Container, Proof, ScriptPubkeyContainer, TxCommitment, TxContainer, TxSupplement,
+    TxoutContainer,
 };
+use crate::bp::{RedeemScript, WitnessScript};
 use crate::client_side_validation::{commit_strategy, CommitEncodeWithStrategy, ConsensusCommit};
 use crate::commit_verify::{CommitVerify, EmbedCommitVerify};
 use crate::lnpbp4::MultimsgCommitment;
@@ -53,6 +54,7 @@ lazy_static! {
 pub enum Error {
     NoRequiredOutputInformation(usize),
     NoRequiredPubkey(usize),
+    UnsupportedOutputScripts(usize),
     NoFeeInformation,
     #[derive_from]
     WrongPubkeyData(secp256k1::Error),
@@ -132,21 +134,14 @@ impl Anchor {
                 .get(&pubkey_key)
                 .ok_or(Error::NoRequiredPubkey(vout))?;
             let pubkey = secp256k1::PublicKey::from_slice(pubkey)?;
-            let script_info = match psbt_out
-                .redeem_script
-                .as_ref()
-                .or_else(|| psbt_out.witness_script.as_ref())
-            {
-                None => ScriptInfo::None,
-                Some(script) => ScriptInfo::LockScript(script.into()),
-            };
-            let scriptpubkey_composition = if psbt_out.redeem_script.is_some() {
-                ScriptPubkeyComposition::ScriptHash
-            } else if psbt_out.witness_script.is_some() {
-                ScriptPubkeyComposition::WScriptHash
-            } else {
-                ScriptPubkeyComposition::WPubkeyHash
-            };
+            let script_container = ScriptPubkeyContainer::construct_from_scripts(
+                &*LNPBP4_TAG,
+                pubkey,
+                &tx_out.script_pubkey.clone().into(),
+                psbt_out.redeem_script.map(RedeemScript::from).as_ref(),
+                psbt_out.witness_script.map(WitnessScript::from).as_ref(),
+            )
+            .map_err(|_| Error::UnsupportedOutputScripts(vout))?;
 
             let container = TxContainer {
                 tx: tx.clone(),
@@ -154,12 +149,7 @@ impl Anchor {
                 protocol_factor: vout as u32,
                 txout_container: TxoutContainer {
                     value: tx_out.value,
-                    script_container: ScriptPubkeyContainer {
-                        pubkey,
-                        script_info,
-                        scriptpubkey_composition,
-                        tag: *LNPBP4_TAG,
-                    },
+                    script_container,
                 },
             };
 
@@ -19,21 +19,21 @@ use crate::rgb::{amount, schema, script::StandardProcedure, AssignmentsVariant,
 
 // Data are taken according to RGB-20 (LNPBP-20) standard
 #[allow(unused)]
-const META_TOTAL_SUPPLY: usize = 3;
+const META_TOTAL_SUPPLY: schema::FieldType = schema::FieldType(3);
 #[allow(unused)]
-const META_ISSUED_SUPPLY: usize = 4;
+const META_ISSUED_SUPPLY: schema::FieldType = schema::FieldType(4);
 #[allow(unused)]
-const SEAL_ISSUE: usize = 0;
+const SEAL_ISSUE: schema::AssignmentsType = schema::AssignmentsType(0);
 #[allow(unused)]
-const SEAL_ASSETS: usize = 1;
+const SEAL_ASSETS: schema::AssignmentsType = schema::AssignmentsType(1);
 #[allow(unused)]
-const SEAL_PRUNE: usize = 2;
+const SEAL_PRUNE: schema::AssignmentsType = schema::AssignmentsType(2);
 #[allow(unused)]
-const TRANSITION_ISSUE: usize = 0;
+const TRANSITION_ISSUE: schema::TransitionType = schema::TransitionType(0);
 #[allow(unused)]
-const TRANSITION_TRANSFER: usize = 1;
+const TRANSITION_TRANSFER: schema::TransitionType = schema::TransitionType(1);
 #[allow(unused)]
-const TRANSITION_PRUNE: usize = 2;
+const TRANSITION_PRUNE: schema::TransitionType = schema::TransitionType(2);
 
 macro_rules! push_stack {
     ($self:ident, $ident:literal) => {